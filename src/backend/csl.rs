@@ -3,6 +3,8 @@
 //! This backend provides full CSL (Citation Style Language) support,
 //! allowing users to choose from 80+ bundled citation styles or provide custom CSL files.
 
+use std::cell::RefCell;
+
 use anyhow::anyhow;
 use hayagriva::archive::{locales, ArchivedStyle};
 use hayagriva::citationberg::{IndependentStyle, Locale, Style};
@@ -11,13 +13,13 @@ use lazy_static::lazy_static;
 use mdbook_preprocessor::errors::Result as MdResult;
 use regex::Regex;
 
-use crate::models::BibItem;
+use crate::models::{BackReference, BibItem};
 
 use super::hayagriva_style::{
     detect_style_format, find_style_info, supported_style_aliases, CitationContentType,
-    CitationFormat, CitationRendering, CitationStyle, DetectedStyleFormat, StyleInfo,
+    CitationFormat, CitationStyle, DetectedStyleFormat, StyleInfo,
 };
-use super::{BibliographyBackend, CitationContext, CitationVariant};
+use super::{BibliographyBackend, CitationContext, CitationVariant, OutputFormat};
 
 lazy_static! {
     static ref ANSI_REGEX: Regex = Regex::new(r"\x1b\[[0-9;]*m").unwrap();
@@ -44,6 +46,8 @@ pub struct CslBackend {
     style_info: Option<&'static StyleInfo>,
     /// Detected format from CSL metadata (used when style_info is None)
     detected_format: DetectedStyleFormat,
+    /// Citations of the current chapter awaiting their footnote (note styles only)
+    pending_notes: RefCell<Vec<BibItem>>,
 }
 
 impl CslBackend {
@@ -89,6 +93,7 @@ impl CslBackend {
             locales,
             style_info: resolved_info,
             detected_format,
+            pending_notes: RefCell::new(Vec::new()),
         })
     }
 
@@ -127,6 +132,56 @@ impl CslBackend {
         }
     }
 
+    /// Serialize the loaded style as a standalone CSL file (e.g. for pandoc's citeproc).
+    pub fn style_xml(&self) -> anyhow::Result<String> {
+        let xml = Style::Independent(self.style.clone()).to_xml()?;
+        // The serializer drops the CSL namespace, which CSL processors expect
+        let xml = match xml.strip_prefix("<style ") {
+            Some(rest) if !rest.contains("xmlns=") => {
+                format!("<style xmlns=\"http://purl.org/net/xbiblio/csl\" {rest}")
+            }
+            _ => xml,
+        };
+        Ok(format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{xml}"))
+    }
+
+    /// Render the bibliography entry text of an item, without any prefix or wrapper.
+    pub fn reference_text(&self, item: &BibItem) -> MdResult<String> {
+        // Get the hayagriva Entry from the BibItem
+        let entry = item.hayagriva_entry.as_ref().ok_or_else(|| {
+            anyhow!(
+                "BibItem '{}' missing hayagriva_entry for CSL rendering",
+                item.citation_key
+            )
+        })?;
+
+        // Create a bibliography driver
+        let mut driver = BibliographyDriver::new();
+
+        // Create a citation request to include this entry in the bibliography
+        let citation_item = CitationItem::with_entry(entry.as_ref());
+        let citation_request =
+            CitationRequest::from_items(vec![citation_item], &self.style, &self.locales);
+
+        // Register the citation request
+        driver.citation(citation_request);
+
+        // Finish and get the rendered bibliography
+        let bib_request = BibliographyRequest::new(&self.style, None, &self.locales);
+        let rendered = driver.finish(bib_request);
+
+        // Extract the bibliography entry for this item
+        let bib_html = rendered
+            .bibliography
+            .and_then(|bib| bib.items.first().map(|i| i.content.to_string()));
+
+        // If no bibliography content from hayagriva, construct a fallback
+        Ok(match bib_html {
+            Some(html) => Self::strip_ansi_codes(&html),
+            None => Self::format_fallback_bibliography(item),
+        })
+    }
+
     /// Get the effective citation format (registry or detected).
     ///
     /// Returns the `CitationFormat` from `StyleInfo` if available (for registry styles),
@@ -231,9 +286,38 @@ impl CslBackend {
         }
     }
 
+    /// Footnote label of the `n`-th (1-based) note citation in a chapter.
+    fn note_label(n: usize) -> String {
+        format!("bib-{n}")
+    }
+
+    /// Prefix an inline citation with an empty anchor element so back-references can target it.
+    fn with_anchor(citation: String, anchor: &str) -> String {
+        if anchor.is_empty() {
+            citation
+        } else {
+            format!("<span class='csl-cite-anchor' id='{anchor}'></span>{citation}")
+        }
+    }
+
+    /// Format the "Cited on" back-reference links appended to a bibliography entry.
+    pub(super) fn format_backrefs(backrefs: &[BackReference]) -> String {
+        if backrefs.is_empty() {
+            return String::new();
+        }
+        let links: Vec<String> = backrefs
+            .iter()
+            .map(|backref| format!("<a href='{}'>{}</a>", backref.href, backref.label))
+            .collect();
+        format!(
+            " <span class='csl-backrefs'>Cited on: {}</span>",
+            links.join(", ")
+        )
+    }
+
     /// Some CSL styles (like alphanumeric) don't define a bibliography section,
     /// so we construct a simple entry from the BibItem metadata.
-    fn format_fallback_bibliography(item: &BibItem) -> String {
+    pub(super) fn format_fallback_bibliography(item: &BibItem) -> String {
         let mut parts = Vec::new();
 
         // Authors (format: "LastName, F.")
@@ -284,22 +368,16 @@ impl CslBackend {
 impl BibliographyBackend for CslBackend {
     fn format_citation(&self, item: &BibItem, context: &CitationContext) -> MdResult<String> {
         let format = self.citation_format();
-        let link = format!("{}#{}", context.bib_page_path, item.citation_key);
+        let link = format!("{}#{}", context.bib_page_path, context.entry_anchor);
         let variant = context.variant;
 
         // For numeric and label styles, variant doesn't affect content
         // For author-date styles, we need to handle variants differently
         let linked_citation = match format.content {
             CitationContentType::Numeric => {
-                let content = item.index.unwrap_or(1).to_string();
-                match format.rendering {
-                    CitationRendering::Superscript => {
-                        format!("<sup><a href=\"{link}\">{content}</a></sup>")
-                    }
-                    CitationRendering::Bracketed => {
-                        format!("[[{content}]({link})]")
-                    }
-                }
+                format
+                    .rendering
+                    .numeric_citation(item.index.unwrap_or(1), &link, "")
             }
             CitationContentType::Label => {
                 // For label styles (alphanumeric), use hayagriva to generate author-based labels
@@ -308,6 +386,20 @@ impl BibliographyBackend for CslBackend {
                 let label = content.trim_matches(&['[', ']'] as &[char]);
                 format!("[[{label}]({link})]")
             }
+            CitationContentType::Note => {
+                // Footnote reference; its text is rendered with the rest of the chapter's
+                // notes so the style can shorten repeated citations (or use "Ibid.")
+                let mut notes = self.pending_notes.borrow_mut();
+                notes.push(item.clone());
+                let footnote = format!("[^{}]", Self::note_label(notes.len()));
+                match variant {
+                    CitationVariant::AuthorInText => {
+                        let author = Self::format_authors_for_citation(&item.authors);
+                        format!("{author}{footnote}")
+                    }
+                    _ => footnote,
+                }
+            }
             CitationContentType::AuthorDate => {
                 // For author-date styles, handle Pandoc citation variants
                 let full_citation =
@@ -336,95 +428,119 @@ impl BibliographyBackend for CslBackend {
             }
         };
 
-        Ok(linked_citation)
+        Ok(Self::with_anchor(linked_citation, &context.anchor))
     }
 
-    fn format_reference(&self, item: &BibItem) -> MdResult<String> {
-        let format = self.citation_format();
-
-        // Get the hayagriva Entry from the BibItem
-        let entry = item.hayagriva_entry.as_ref().ok_or_else(|| {
-            anyhow!(
-                "BibItem '{}' missing hayagriva_entry for CSL rendering",
-                item.citation_key
-            )
-        })?;
-
-        // Create a bibliography driver
-        let mut driver = BibliographyDriver::new();
-
-        // Create a citation request to include this entry in the bibliography
-        let citation_item = CitationItem::with_entry(entry.as_ref());
-        let citation_request =
-            CitationRequest::from_items(vec![citation_item], &self.style, &self.locales);
-
-        // Register the citation request
-        driver.citation(citation_request);
-
-        // Finish and get the rendered bibliography
-        let bib_request = BibliographyRequest::new(&self.style, None, &self.locales);
-        let rendered = driver.finish(bib_request);
-
-        // Extract the bibliography entry for this item
-        let bib_html = rendered
-            .bibliography
-            .and_then(|bib| bib.items.first().map(|i| i.content.to_string()));
+    fn format_reference(&self, item: &BibItem, backrefs: &[BackReference]) -> MdResult<String> {
+        self.format_anchored_reference(item, backrefs, &item.citation_key)
+    }
 
-        // If no bibliography content from hayagriva, construct a fallback
-        let bib_content = match bib_html {
-            Some(html) => Self::strip_ansi_codes(&html),
-            None => Self::format_fallback_bibliography(item),
-        };
+    fn format_anchored_reference(
+        &self,
+        item: &BibItem,
+        backrefs: &[BackReference],
+        anchor: &str,
+    ) -> MdResult<String> {
+        let format = self.citation_format();
+        let bib_content = self.reference_text(item)?;
 
         // Format entry based on content type and rendering
-        let formatted_entry = match (format.content, format.rendering) {
-            (CitationContentType::Numeric, CitationRendering::Superscript) => {
-                // Nature and similar styles use "1." format
-                let index = item.index.unwrap_or(1);
-                format!("{index}. {bib_content}")
-            }
-            (CitationContentType::Numeric, CitationRendering::Bracketed) => {
-                // IEEE and similar styles use "[1]" format
-                let index = item.index.unwrap_or(1);
-                format!("[{index}] {bib_content}")
+        let formatted_entry = match format.content {
+            CitationContentType::Numeric => {
+                // IEEE and similar styles use "[1]", Nature and similar styles use "1."
+                let prefix = format
+                    .rendering
+                    .numeric_entry_prefix(item.index.unwrap_or(1));
+                format!("{prefix} {bib_content}")
             }
-            (CitationContentType::Label, _) => {
+            CitationContentType::Label => {
                 // For label styles (alphanumeric), get the label from hayagriva citation
-                let citation_text = rendered
-                    .citations
-                    .first()
-                    .map(|c| c.citation.to_string())
-                    .unwrap_or_else(|| format!("[{}]", item.citation_key));
-
-                let clean_label = Self::strip_ansi_codes(&citation_text);
+                let citation_text =
+                    self.get_hayagriva_citation_text(item, &format!("[{}]", item.citation_key))?;
                 // trim_matches handles potential nested brackets
-                let label = clean_label.trim_matches(&['[', ']'] as &[char]);
+                let label = citation_text.trim_matches(&['[', ']'] as &[char]);
 
                 format!("[{label}] {bib_content}")
             }
-            (CitationContentType::AuthorDate, _) => {
-                // Author-date styles: no prefix
+            CitationContentType::AuthorDate | CitationContentType::Note => {
+                // Author-date and note styles: no prefix
                 bib_content
             }
         };
 
         // Wrap in a div with CSL entry class and add anchor for linking
         Ok(format!(
-            "<div class='csl-entry' id='{}'>{}</div>",
-            item.citation_key, formatted_entry
+            "<div class='csl-entry' id='{anchor}'>{}{}</div>",
+            formatted_entry,
+            Self::format_backrefs(backrefs)
         ))
     }
 
     fn name(&self) -> &str {
         "CSL"
     }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Html
+    }
+
+    fn chapter_footnotes(&self) -> MdResult<String> {
+        let notes = self.pending_notes.take();
+        if notes.is_empty() {
+            return Ok(String::new());
+        }
+
+        // One citation per note, in order, so hayagriva can track positions
+        // (first, subsequent, ibid) as the style requires
+        let mut driver = BibliographyDriver::new();
+        for (i, item) in notes.iter().enumerate() {
+            let entry = item.hayagriva_entry.as_ref().ok_or_else(|| {
+                anyhow!(
+                    "BibItem '{}' missing hayagriva_entry for CSL rendering",
+                    item.citation_key
+                )
+            })?;
+            driver.citation(CitationRequest::new(
+                vec![CitationItem::with_entry(entry.as_ref())],
+                &self.style,
+                None,
+                &self.locales,
+                Some(i + 1),
+            ));
+        }
+        let rendered = driver.finish(BibliographyRequest::new(&self.style, None, &self.locales));
+
+        let definitions: Vec<String> = notes
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let text = rendered
+                    .citations
+                    .get(i)
+                    .map(|c| Self::strip_ansi_codes(&c.citation.to_string()))
+                    .unwrap_or_else(|| Self::format_fallback_bibliography(item));
+                format!("[^{}]: {text}", Self::note_label(i + 1))
+            })
+            .collect();
+        Ok(definitions.join("\n\n"))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::hayagriva_style::CitationRendering;
     use std::sync::Arc;
 
+    #[test]
+    fn test_csl_backend_style_xml_round_trips() {
+        let backend = CslBackend::new("ieee".to_string()).unwrap();
+        let xml = backend.style_xml().unwrap();
+        assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<style xmlns="));
+        let style = Style::from_xml(xml.split_once('\n').unwrap().1).unwrap();
+        assert!(matches!(style, Style::Independent(_)));
+    }
+
     #[test]
     fn test_csl_backend_creation() {
         let backend = CslBackend::new("ieee".to_string());
@@ -491,6 +607,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "test2024".to_string(),
         };
 
         let citation = backend.format_citation(&item, &context);
@@ -530,7 +648,7 @@ mod tests {
             ..Default::default()
         };
 
-        let reference = backend.format_reference(&item);
+        let reference = backend.format_reference(&item, &[]);
         assert!(
             reference.is_ok(),
             "Reference formatting failed: {:?}",
@@ -573,6 +691,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "watson1953".to_string(),
         };
 
         let citation = backend.format_citation(&item, &context);
@@ -658,6 +778,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "test2024".to_string(),
         };
 
         let result = backend.format_citation(&item, &context).unwrap();
@@ -672,6 +794,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_citation_anchor_and_backrefs() {
+        let backend = CslBackend::new("ieee".to_string()).unwrap();
+
+        let entry_str = r#"@article{test2024,
+            author = {Smith, John},
+            title = {Test},
+            journal = {Test Journal},
+            year = {2024},
+        }"#;
+
+        let bibliography = hayagriva::io::from_biblatex_str(entry_str).unwrap();
+        let entry = bibliography.iter().next().unwrap();
+
+        let item = BibItem {
+            citation_key: "test2024".to_string(),
+            title: "Test".to_string(),
+            index: Some(1),
+            hayagriva_entry: Some(Arc::new(entry.clone())),
+            ..Default::default()
+        };
+
+        let context = CitationContext {
+            bib_page_path: "bibliography.html".to_string(),
+            chapter_path: "chapter1.md".to_string(),
+            variant: CitationVariant::Standard,
+            anchor: "bib-cite-test2024-1".to_string(),
+            entry_anchor: "test2024".to_string(),
+        };
+        let citation = backend.format_citation(&item, &context).unwrap();
+        assert!(citation.starts_with("<span class='csl-cite-anchor' id='bib-cite-test2024-1'>"));
+
+        let backrefs = vec![BackReference {
+            label: "Ch. 1".to_string(),
+            chapter_path: "chapter1.md".to_string(),
+            anchor: "bib-cite-test2024-1".to_string(),
+            href: "chapter1.html#bib-cite-test2024-1".to_string(),
+        }];
+        let reference = backend.format_reference(&item, &backrefs).unwrap();
+        assert!(reference.contains(
+            "<span class='csl-backrefs'>Cited on: <a href='chapter1.html#bib-cite-test2024-1'>Ch. 1</a></span>"
+        ));
+
+        let reference = backend.format_reference(&item, &[]).unwrap();
+        assert!(!reference.contains("csl-backrefs"));
+    }
+
     #[test]
     fn test_fallback_style_format_detection() {
         // Use a style that's in hayagriva but NOT in our registry
@@ -722,6 +891,102 @@ mod tests {
 
     // --- New style integration tests ---
 
+    /// Items parsed from BibTeX with their hayagriva entries, for note style tests.
+    fn note_items() -> Vec<BibItem> {
+        let source = r#"
+            @book{rust_book,
+                author = {Klabnik, Steve and Nichols, Carol},
+                title = {The Rust Programming Language},
+                publisher = {No Starch Press},
+                year = {2018},
+            }
+            @misc{fps,
+                author = {Perez-Sorrosal, Francisco},
+                title = {A Bib Entry},
+                year = {2020},
+            }"#;
+        hayagriva::io::from_biblatex_str(source)
+            .unwrap()
+            .iter()
+            .map(|entry| BibItem {
+                citation_key: entry.key().to_string(),
+                authors: vec![vec![entry.authors().unwrap()[0].name.clone()]],
+                hayagriva_entry: Some(Arc::new(entry.clone())),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn note_context(variant: CitationVariant) -> CitationContext {
+        CitationContext {
+            bib_page_path: "bibliography.html".to_string(),
+            chapter_path: "chapter1.md".to_string(),
+            variant,
+            anchor: String::new(),
+            entry_anchor: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_chicago_notes_citations_become_footnotes() {
+        let backend = CslBackend::new("chicago-notes".to_string()).unwrap();
+        assert_eq!(backend.citation_format().content, CitationContentType::Note);
+
+        let items = note_items();
+        let standard = note_context(CitationVariant::Standard);
+        assert_eq!(
+            backend.format_citation(&items[0], &standard).unwrap(),
+            "[^bib-1]"
+        );
+        assert_eq!(
+            backend
+                .format_citation(&items[1], &note_context(CitationVariant::AuthorInText))
+                .unwrap(),
+            "Perez-Sorrosal[^bib-2]"
+        );
+        assert_eq!(
+            backend.format_citation(&items[0], &standard).unwrap(),
+            "[^bib-3]"
+        );
+
+        let footnotes = backend.chapter_footnotes().unwrap();
+        let notes: Vec<&str> = footnotes.split("\n\n").collect();
+        assert_eq!(notes.len(), 3);
+        // Full note first, short form for the subsequent citation of the same work
+        assert_eq!(
+            notes[0],
+            "[^bib-1]: Steve Klabnik and Carol Nichols, The Rust Programming Language \
+             (No Starch Press, 2018)."
+        );
+        assert!(notes[1].starts_with("[^bib-2]: Francisco Perez-Sorrosal, "));
+        assert_eq!(
+            notes[2],
+            "[^bib-3]: Klabnik and Nichols, The Rust Programming Language."
+        );
+
+        // Notes restart with the next chapter
+        assert_eq!(backend.chapter_footnotes().unwrap(), "");
+        assert_eq!(
+            backend.format_citation(&items[1], &standard).unwrap(),
+            "[^bib-1]"
+        );
+    }
+
+    #[test]
+    fn test_detected_note_style_uses_ibid() {
+        // Not in the registry: detected as a note style from its CSL metadata
+        let backend = CslBackend::new("gb-7714-2015-note".to_string()).unwrap();
+        assert_eq!(backend.citation_format().content, CitationContentType::Note);
+
+        let items = note_items();
+        let context = note_context(CitationVariant::Standard);
+        backend.format_citation(&items[0], &context).unwrap();
+        backend.format_citation(&items[0], &context).unwrap();
+
+        let footnotes = backend.chapter_footnotes().unwrap();
+        assert!(footnotes.ends_with("[^bib-2]: 同上."), "{footnotes}");
+    }
+
     #[test]
     fn test_vancouver_superscript_citation() {
         let backend =
@@ -763,6 +1028,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "test2024".to_string(),
         };
 
         let citation = backend.format_citation(&item, &context).unwrap();
@@ -811,6 +1078,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "smith2024".to_string(),
         };
 
         let citation = backend.format_citation(&item, &context).unwrap();
@@ -855,7 +1124,7 @@ mod tests {
             ..Default::default()
         };
 
-        let reference = backend.format_reference(&item).unwrap();
+        let reference = backend.format_reference(&item, &[]).unwrap();
         println!("Alphanumeric reference: {reference}");
 
         // Should contain the label
@@ -6,9 +6,10 @@
 use handlebars::Handlebars;
 use mdbook_preprocessor::errors::Result as MdResult;
 
-use crate::models::{BibItem, Citation};
+use crate::models::{BackReference, BibItem, Citation, Reference};
+use crate::parser::BibFormat;
 
-use super::{BibliographyBackend, CitationContext};
+use super::{BibliographyBackend, CitationContext, OutputFormat};
 
 /// Custom backend using Handlebars templates.
 ///
@@ -20,6 +21,7 @@ use super::{BibliographyBackend, CitationContext};
 /// - `references`: For full bibliography entry rendering
 pub struct CustomBackend<'a> {
     handlebars: &'a Handlebars<'a>,
+    copy_label: Option<&'static str>,
 }
 
 impl<'a> CustomBackend<'a> {
@@ -28,7 +30,19 @@ impl<'a> CustomBackend<'a> {
     /// The Handlebars instance must have the `citation` and `references`
     /// templates registered before use.
     pub fn new(handlebars: &'a Handlebars<'a>) -> Self {
-        Self { handlebars }
+        Self {
+            handlebars,
+            copy_label: None,
+        }
+    }
+
+    /// Offer a button copying each entry's source, labeled after its format.
+    pub fn with_copy_button(mut self, format: BibFormat) -> Self {
+        self.copy_label = Some(match format {
+            BibFormat::BibTeX => "Copy BibTeX",
+            BibFormat::Yaml => "Copy YAML",
+        });
+        self
     }
 }
 
@@ -38,6 +52,8 @@ impl<'a> BibliographyBackend for CustomBackend<'a> {
             item: item.clone(),
             path: context.bib_page_path.clone(),
             variant: context.variant.as_template_str().to_string(),
+            anchor: context.anchor.clone(),
+            entry_anchor: context.entry_anchor.clone(),
         };
 
         self.handlebars.render("citation", &citation).map_err(|e| {
@@ -51,20 +67,41 @@ impl<'a> BibliographyBackend for CustomBackend<'a> {
         })
     }
 
-    fn format_reference(&self, item: &BibItem) -> MdResult<String> {
-        self.handlebars.render("references", item).map_err(|e| {
-            tracing::error!(
-                "Failed to render reference for '{}': {}",
-                item.citation_key,
-                e
-            );
-            e.into()
-        })
+    fn format_reference(&self, item: &BibItem, backrefs: &[BackReference]) -> MdResult<String> {
+        self.format_anchored_reference(item, backrefs, &item.citation_key)
+    }
+
+    fn format_anchored_reference(
+        &self,
+        item: &BibItem,
+        backrefs: &[BackReference],
+        anchor: &str,
+    ) -> MdResult<String> {
+        let reference = Reference {
+            item,
+            backrefs,
+            entry_anchor: anchor,
+            copy_label: self.copy_label,
+        };
+        self.handlebars
+            .render("references", &reference)
+            .map_err(|e| {
+                tracing::error!(
+                    "Failed to render reference for '{}': {}",
+                    item.citation_key,
+                    e
+                );
+                e.into()
+            })
     }
 
     fn name(&self) -> &str {
         "Custom (Handlebars)"
     }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Html
+    }
 }
 
 #[cfg(test)]
@@ -98,6 +135,8 @@ mod tests {
             bib_page_path: "bibliography.html".to_string(),
             chapter_path: "chapter1.md".to_string(),
             variant: CitationVariant::Standard,
+            anchor: String::new(),
+            entry_anchor: "test_key".to_string(),
         };
 
         let result = backend.format_citation(&item, &context);
@@ -119,8 +158,37 @@ mod tests {
             ..Default::default()
         };
 
-        let result = backend.format_reference(&item);
+        let result = backend.format_reference(&item, &[]);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "<div>test_key: Test Title</div>");
     }
+
+    #[test]
+    fn test_custom_backend_format_reference_with_backrefs() {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string(
+                "references",
+                "{{citation_key}}:{{#each backrefs}} <a href=\"{{href}}\">{{label}}</a>{{/each}}",
+            )
+            .unwrap();
+
+        let backend = CustomBackend::new(&handlebars);
+        let item = BibItem {
+            citation_key: "test_key".to_string(),
+            ..Default::default()
+        };
+        let backrefs = vec![BackReference {
+            label: "Ch. 2".to_string(),
+            chapter_path: "ch2.md".to_string(),
+            anchor: "bib-cite-test_key-1".to_string(),
+            href: "ch2.html#bib-cite-test_key-1".to_string(),
+        }];
+
+        let result = backend.format_reference(&item, &backrefs).unwrap();
+        assert_eq!(
+            result,
+            "test_key: <a href=\"ch2.html#bib-cite-test_key-1\">Ch. 2</a>"
+        );
+    }
 }
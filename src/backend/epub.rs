@@ -0,0 +1,116 @@
+//! EPUB backend producing footnote-style citations for e-readers.
+//!
+//! Most EPUB readers support neither `<details>` elements nor JavaScript, so citations
+//! become superscript note references and entries plain EPUB bibliography entries,
+//! using the EPUB 3 structural semantics (`epub:type`).
+
+use mdbook_preprocessor::errors::Result as MdResult;
+
+use crate::models::{BackReference, BibItem};
+
+use super::hayagriva_style::CitationRendering;
+use super::{BibliographyBackend, CitationContext, CslBackend, OutputFormat};
+
+/// EPUB backend rendering numbered superscript citations and bibliography entries.
+///
+/// Entry text comes from the CSL backend when one is configured, or from a plain
+/// author/title/year fallback otherwise.
+pub struct EpubBackend {
+    csl: Option<CslBackend>,
+}
+
+impl EpubBackend {
+    /// Create a new EpubBackend, formatting entries with `csl` when given.
+    pub fn new(csl: Option<CslBackend>) -> Self {
+        Self { csl }
+    }
+}
+
+impl BibliographyBackend for EpubBackend {
+    fn format_citation(&self, item: &BibItem, context: &CitationContext) -> MdResult<String> {
+        let link = format!("{}#{}", context.bib_page_path, context.entry_anchor);
+        let mut attrs = String::from(" epub:type=\"noteref\"");
+        if !context.anchor.is_empty() {
+            attrs.push_str(&format!(" id=\"{}\"", context.anchor));
+        }
+        Ok(CitationRendering::Superscript.numeric_citation(item.index.unwrap_or(1), &link, &attrs))
+    }
+
+    fn format_reference(&self, item: &BibItem, backrefs: &[BackReference]) -> MdResult<String> {
+        self.format_anchored_reference(item, backrefs, &item.citation_key)
+    }
+
+    fn format_anchored_reference(
+        &self,
+        item: &BibItem,
+        backrefs: &[BackReference],
+        anchor: &str,
+    ) -> MdResult<String> {
+        let text = match &self.csl {
+            Some(csl) => csl.reference_text(item)?,
+            None => CslBackend::format_fallback_bibliography(item),
+        };
+        let prefix = CitationRendering::Superscript.numeric_entry_prefix(item.index.unwrap_or(1));
+        Ok(format!(
+            "<div class=\"bib-entry\" epub:type=\"biblioentry\" id=\"{anchor}\">{prefix} {text}{}</div>\n",
+            CslBackend::format_backrefs(backrefs)
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "EPUB"
+    }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Epub
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::CitationVariant;
+
+    fn item() -> BibItem {
+        BibItem {
+            citation_key: "smith2024".to_string(),
+            title: "EPUB Citations".to_string(),
+            authors: vec![vec!["Smith".to_string(), "John".to_string()]],
+            pub_year: Some("2024".to_string()),
+            index: Some(3),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_epub_backend_citation_is_superscript_noteref() {
+        let backend = EpubBackend::new(None);
+        let context = CitationContext {
+            bib_page_path: "../bibliography.html".to_string(),
+            chapter_path: "part/chapter1.md".to_string(),
+            variant: CitationVariant::AuthorInText,
+            anchor: "bib-cite-smith2024-1".to_string(),
+            entry_anchor: "smith2024".to_string(),
+        };
+
+        assert_eq!(
+            backend.format_citation(&item(), &context).unwrap(),
+            "<sup><a epub:type=\"noteref\" id=\"bib-cite-smith2024-1\" \
+             href=\"../bibliography.html#smith2024\">3</a></sup>"
+        );
+    }
+
+    #[test]
+    fn test_epub_backend_reference_is_biblioentry() {
+        let backend = EpubBackend::new(None);
+        let reference = backend.format_reference(&item(), &[]).unwrap();
+
+        assert_eq!(
+            reference,
+            "<div class=\"bib-entry\" epub:type=\"biblioentry\" id=\"smith2024\">\
+             3. Smith, J. \"EPUB Citations.\" 2024.</div>\n"
+        );
+        assert!(backend.supports_renderer(OutputFormat::Epub));
+        assert!(!backend.supports_renderer(OutputFormat::Html));
+    }
+}
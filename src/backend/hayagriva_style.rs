@@ -3,6 +3,7 @@
 //! This module provides:
 //! - A registry of common citation styles with short aliases (e.g., "ieee", "apa")
 //! - Runtime detection of citation format from any CSL style's metadata
+//! - A listing of every bundled style, combining both
 //!
 //! ## Style Resolution Strategy
 //!
@@ -15,8 +16,10 @@
 
 use hayagriva::archive::ArchivedStyle;
 use hayagriva::citationberg::{
-    CitationFormat as CslCitationFormat, IndependentStyle, StyleCategory,
+    CitationFormat as CslCitationFormat, IndependentStyle, Style, StyleCategory,
+    StyleInfo as CslStyleInfo,
 };
+use serde::Serialize;
 
 /// Content source for citations - mutually exclusive options.
 ///
@@ -24,12 +27,26 @@ use hayagriva::citationberg::{
 /// - `Numeric`: Sequential index managed by us (e.g., `[1]`, `[2]`)
 /// - `Label`: Author-based label generated by hayagriva (e.g., `[Smi24]`)
 /// - `AuthorDate`: Full author-date text generated by hayagriva (e.g., `(Smith, 2024)`)
+/// - `Note`: Footnote reference, with the note text generated by hayagriva (e.g., `[^bib-1]`)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CitationContentType {
     #[default]
     Numeric,
     Label,
     AuthorDate,
+    Note,
+}
+
+impl CitationContentType {
+    /// Name of the content type, as given to `mdbook-bib styles --format`.
+    pub fn name(self) -> &'static str {
+        match self {
+            CitationContentType::Numeric => "numeric",
+            CitationContentType::Label => "label",
+            CitationContentType::AuthorDate => "author-date",
+            CitationContentType::Note => "note",
+        }
+    }
 }
 
 /// Rendering presentation for citations.
@@ -44,6 +61,28 @@ pub enum CitationRendering {
     Superscript,
 }
 
+impl CitationRendering {
+    /// Render a numeric citation linking to its bibliography entry.
+    ///
+    /// `link_attrs` are extra attributes for the superscript link (e.g. EPUB semantics).
+    pub fn numeric_citation(self, index: u32, link: &str, link_attrs: &str) -> String {
+        match self {
+            CitationRendering::Superscript => {
+                format!("<sup><a{link_attrs} href=\"{link}\">{index}</a></sup>")
+            }
+            CitationRendering::Bracketed => format!("[[{index}]({link})]"),
+        }
+    }
+
+    /// Prefix of a numeric bibliography entry: "1." for superscript styles, "[1]" otherwise.
+    pub fn numeric_entry_prefix(self, index: u32) -> String {
+        match self {
+            CitationRendering::Superscript => format!("{index}."),
+            CitationRendering::Bracketed => format!("[{index}]"),
+        }
+    }
+}
+
 /// Complete citation format specification.
 ///
 /// Combines content type (WHAT to display) with rendering (HOW to display),
@@ -194,14 +233,6 @@ static STYLE_REGISTRY: &[StyleInfo] = &[
             rendering: CitationRendering::Bracketed,
         },
     },
-    StyleInfo {
-        aliases: &["chicago-notes"],
-        archived: ArchivedStyle::ChicagoNotes,
-        format: CitationFormat {
-            content: CitationContentType::AuthorDate,
-            rendering: CitationRendering::Bracketed,
-        },
-    },
     StyleInfo {
         aliases: &["mla", "modern-language-association"],
         archived: ArchivedStyle::ModernLanguageAssociation,
@@ -242,6 +273,23 @@ static STYLE_REGISTRY: &[StyleInfo] = &[
             rendering: CitationRendering::Bracketed,
         },
     },
+    // Note styles
+    StyleInfo {
+        aliases: &["chicago-notes"],
+        archived: ArchivedStyle::ChicagoNotes,
+        format: CitationFormat {
+            content: CitationContentType::Note,
+            rendering: CitationRendering::Superscript,
+        },
+    },
+    StyleInfo {
+        aliases: &["chicago-shortened-notes"],
+        archived: ArchivedStyle::ChicagoShortenedNotes,
+        format: CitationFormat {
+            content: CitationContentType::Note,
+            rendering: CitationRendering::Superscript,
+        },
+    },
 ];
 
 /// Find a style in the registry by alias (case-insensitive).
@@ -278,6 +326,7 @@ pub fn format_style_list() -> String {
     let mut superscript = Vec::new();
     let mut label = Vec::new();
     let mut author_date = Vec::new();
+    let mut note = Vec::new();
 
     for style in STYLE_REGISTRY {
         let name = style.aliases[0];
@@ -294,6 +343,9 @@ pub fn format_style_list() -> String {
             (CitationContentType::AuthorDate, _) => {
                 author_date.push(name);
             }
+            (CitationContentType::Note, _) => {
+                note.push(name);
+            }
         }
     }
 
@@ -301,11 +353,13 @@ pub fn format_style_list() -> String {
         "Numeric styles: {}\n\
          Superscript styles: {}\n\
          Label styles: {}\n\
-         Author-date styles: {}",
+         Author-date styles: {}\n\
+         Note styles: {}",
         numeric.join(", "),
         superscript.join(", "),
         label.join(", "),
-        author_date.join(", ")
+        author_date.join(", "),
+        note.join(", ")
     )
 }
 
@@ -342,23 +396,79 @@ impl CitationStyle for DetectedStyleFormat {
 /// - `CslCitationFormat::Label` → `CitationContentType::Label` (e.g., `[Smi24]`)
 /// - `CslCitationFormat::AuthorDate` → `CitationContentType::AuthorDate` (e.g., `(Smith, 2024)`)
 /// - `CslCitationFormat::Author` → `CitationContentType::AuthorDate` (e.g., `(Smith)`)
-/// - `CslCitationFormat::Note` → `CitationContentType::AuthorDate` (footnote styles)
+/// - `CslCitationFormat::Note` → `CitationContentType::Note` (footnote styles)
 pub fn detect_style_format(style: &IndependentStyle) -> DetectedStyleFormat {
-    let csl_format = style.info.category.iter().find_map(|cat| match cat {
+    DetectedStyleFormat {
+        format: CitationFormat {
+            content: detect_content_type(&style.info),
+            rendering: CitationRendering::Bracketed, // Cannot detect from CSL metadata
+        },
+    }
+}
+
+/// Content type declared in a style's `info.category`, author-date when missing.
+fn detect_content_type(info: &CslStyleInfo) -> CitationContentType {
+    let csl_format = info.category.iter().find_map(|cat| match cat {
         StyleCategory::CitationFormat { format } => Some(*format),
         StyleCategory::Field { .. } => None,
     });
 
-    let content = match csl_format {
+    match csl_format {
         Some(CslCitationFormat::Numeric) => CitationContentType::Numeric,
         Some(CslCitationFormat::Label) => CitationContentType::Label,
+        Some(CslCitationFormat::Note) => CitationContentType::Note,
         _ => CitationContentType::AuthorDate,
-    };
-
-    DetectedStyleFormat {
-        format: CitationFormat {
-            content,
-            rendering: CitationRendering::Bracketed, // Cannot detect from CSL metadata
-        },
     }
 }
+
+/// A bundled CSL style, as listed by `mdbook-bib styles`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StyleListing {
+    /// hayagriva name of the style, usable as `csl-style`
+    pub name: &'static str,
+    /// Full title of the style
+    pub title: &'static str,
+    /// Other names accepted as `csl-style`
+    pub aliases: Vec<&'static str>,
+    /// Citation content type: `numeric`, `label`, `author-date` or `note`
+    pub format: &'static str,
+    /// Whether citations are superscript (only known for registry styles)
+    pub superscript: bool,
+    /// Whether the style only points to a parent style, and can't be used itself
+    pub dependent: bool,
+}
+
+/// List every style bundled with hayagriva, with the registry aliases and hints.
+pub fn list_archived_styles() -> Vec<StyleListing> {
+    ArchivedStyle::all()
+        .iter()
+        .map(|&archived| {
+            let names = archived.names();
+            let info = STYLE_REGISTRY.iter().find(|info| info.archived == archived);
+            let mut aliases: Vec<&'static str> = names[1..].to_vec();
+            for alias in info.map(|info| info.aliases).unwrap_or_default() {
+                if !names.contains(alias) && !aliases.contains(alias) {
+                    aliases.push(alias);
+                }
+            }
+
+            let (detected, dependent) = match archived.get() {
+                Style::Independent(style) => (detect_content_type(&style.info), false),
+                Style::Dependent(style) => (detect_content_type(&style.info), true),
+            };
+            let format = info.map(|info| info.format).unwrap_or(CitationFormat {
+                content: detected,
+                rendering: CitationRendering::Bracketed,
+            });
+
+            StyleListing {
+                name: names[0],
+                title: archived.display_name(),
+                aliases,
+                format: format.content.name(),
+                superscript: format.rendering == CitationRendering::Superscript,
+                dependent,
+            }
+        })
+        .collect()
+}
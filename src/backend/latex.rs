@@ -0,0 +1,131 @@
+//! LaTeX backend emitting native biblatex citation commands.
+//!
+//! Used when the book is built with a LaTeX-producing renderer (e.g. mdbook-latex).
+//! Citations become `\cite`-like commands and the bibliography is left to biber,
+//! so nothing is formatted here.
+
+use mdbook_preprocessor::errors::Result as MdResult;
+
+use crate::models::{BackReference, BibItem};
+
+use super::{BibliographyBackend, CitationContext, CitationVariant, OutputFormat};
+
+/// LaTeX backend producing biblatex commands instead of HTML.
+///
+/// Citation variants map to:
+/// - Standard: `\cite{key}`
+/// - AuthorInText: `\textcite{key}`
+/// - Parenthetical: `\parencite{key}`
+/// - SuppressAuthor: `\parencite*{key}`
+pub struct LatexBackend;
+
+impl LatexBackend {
+    /// Create a new LatexBackend.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Placeholder for the bibliography chapter, expanded downstream by biber.
+    ///
+    /// The chapter keeps its own Markdown heading, so biblatex's heading is disabled.
+    /// When all entries are listed, `\nocite{*}` adds the uncited ones, otherwise
+    /// `\nocite` adds the `nocite` entries.
+    pub fn print_bibliography(cited_only: bool, nocite: &[String]) -> String {
+        let nocite = if !cited_only {
+            "\\nocite{*}\n".to_string()
+        } else if nocite.is_empty() {
+            String::new()
+        } else {
+            format!("\\nocite{{{}}}\n", nocite.join(","))
+        };
+        format!("{nocite}\\printbibliography[heading=none]\n")
+    }
+}
+
+impl Default for LatexBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BibliographyBackend for LatexBackend {
+    fn format_citation(&self, item: &BibItem, context: &CitationContext) -> MdResult<String> {
+        let command = match context.variant {
+            CitationVariant::Standard => "cite",
+            CitationVariant::AuthorInText => "textcite",
+            CitationVariant::Parenthetical => "parencite",
+            CitationVariant::SuppressAuthor => "parencite*",
+        };
+        Ok(format!("\\{command}{{{}}}", item.citation_key))
+    }
+
+    fn format_reference(&self, _item: &BibItem, _backrefs: &[BackReference]) -> MdResult<String> {
+        // References are typeset by biber from `\printbibliography`
+        Ok(String::new())
+    }
+
+    fn name(&self) -> &str {
+        "LaTeX (biblatex)"
+    }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Latex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(variant: CitationVariant) -> CitationContext {
+        CitationContext {
+            bib_page_path: "bibliography.html".to_string(),
+            chapter_path: "chapter1.md".to_string(),
+            variant,
+            anchor: String::new(),
+            entry_anchor: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_latex_backend_citation_commands() {
+        let backend = LatexBackend::new();
+        let item = BibItem {
+            citation_key: "smith2024".to_string(),
+            ..Default::default()
+        };
+
+        let cases = [
+            (CitationVariant::Standard, "\\cite{smith2024}"),
+            (CitationVariant::AuthorInText, "\\textcite{smith2024}"),
+            (CitationVariant::Parenthetical, "\\parencite{smith2024}"),
+            (CitationVariant::SuppressAuthor, "\\parencite*{smith2024}"),
+        ];
+        for (variant, expected) in cases {
+            assert_eq!(
+                backend.format_citation(&item, &context(variant)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_latex_backend_leaves_references_to_biber() {
+        let backend = LatexBackend::new();
+        let item = BibItem::default();
+        assert_eq!(backend.format_reference(&item, &[]).unwrap(), "");
+        assert_eq!(
+            LatexBackend::print_bibliography(true, &[]),
+            "\\printbibliography[heading=none]\n"
+        );
+        assert_eq!(
+            LatexBackend::print_bibliography(false, &[]),
+            "\\nocite{*}\n\\printbibliography[heading=none]\n"
+        );
+        let nocite = ["a".to_string(), "b".to_string()];
+        assert_eq!(
+            LatexBackend::print_bibliography(true, &nocite),
+            "\\nocite{a,b}\n\\printbibliography[heading=none]\n"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Markdown backend producing plain Markdown for non-HTML renderers.
+//!
+//! Used for renderers such as mdBook's markdown renderer or mdbook-linkcheck, where
+//! the HTML, JavaScript and CSS of the other backends are noise or break the output.
+
+use mdbook_preprocessor::errors::Result as MdResult;
+
+use crate::models::{BackReference, BibItem};
+
+use super::{BibliographyBackend, CitationContext, CitationVariant, OutputFormat};
+
+/// Markdown backend producing plain Markdown citations and references.
+///
+/// Citations link to the bibliography chapter:
+/// - Standard: `[key]`
+/// - AuthorInText: `Smith (2024)`
+/// - Parenthetical: `(Smith, 2024)`
+/// - SuppressAuthor: `(2024)`
+///
+/// References are list items: `- **[key]** Smith, John (2024). *Title*. Publisher. DOI.`
+pub struct MarkdownBackend;
+
+impl MarkdownBackend {
+    /// Create a new MarkdownBackend.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Short author label: "Smith", "Smith and Doe" or "Smith et al.".
+    fn author_label(item: &BibItem) -> String {
+        let first = item
+            .authors
+            .first()
+            .and_then(|author| author.first())
+            .map(String::as_str)
+            .unwrap_or("N/A");
+        match item.authors.len() {
+            0 | 1 => first.to_string(),
+            2 => {
+                let second = item.authors[1].first().map(String::as_str).unwrap_or("");
+                format!("{first} and {second}")
+            }
+            _ => format!("{first} et al."),
+        }
+    }
+
+    fn year(item: &BibItem) -> &str {
+        item.pub_year.as_deref().unwrap_or("n.d.")
+    }
+}
+
+impl Default for MarkdownBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Escape characters with a Markdown meaning in inline text.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl BibliographyBackend for MarkdownBackend {
+    fn format_citation(&self, item: &BibItem, context: &CitationContext) -> MdResult<String> {
+        // The markdown output keeps chapters as .md files
+        let page = context
+            .bib_page_path
+            .strip_suffix(".html")
+            .map(|stem| format!("{stem}.md"))
+            .unwrap_or_else(|| context.bib_page_path.clone());
+        let text = match context.variant {
+            CitationVariant::Standard => format!("\\[{}\\]", escape_markdown(&item.citation_key)),
+            CitationVariant::AuthorInText => format!(
+                "{} ({})",
+                escape_markdown(&Self::author_label(item)),
+                Self::year(item)
+            ),
+            CitationVariant::Parenthetical => format!(
+                "({}, {})",
+                escape_markdown(&Self::author_label(item)),
+                Self::year(item)
+            ),
+            CitationVariant::SuppressAuthor => format!("({})", Self::year(item)),
+        };
+        if page.is_empty() {
+            // The chapter's own reference list is right below, without anchors to link to
+            return Ok(text);
+        }
+        Ok(format!("[{text}]({page})"))
+    }
+
+    fn format_reference(&self, item: &BibItem, _backrefs: &[BackReference]) -> MdResult<String> {
+        let mut reference = format!("- **\\[{}\\]**", escape_markdown(&item.citation_key));
+
+        let authors: Vec<String> = item
+            .authors
+            .iter()
+            .filter(|author| author.first().is_some_and(|family| family != "N/A"))
+            .map(|author| escape_markdown(&author.join(", ")))
+            .collect();
+        if !authors.is_empty() {
+            reference.push_str(&format!(" {}", authors.join("; ")));
+        }
+        if let Some(year) = &item.pub_year {
+            reference.push_str(&format!(" ({year})"));
+        }
+        reference.push_str(&format!(". *{}*", escape_markdown(&item.title)));
+        if !item.title.ends_with(['.', '!', '?']) {
+            reference.push('.');
+        }
+
+        if let Some(publisher) = &item.publisher {
+            match &item.address {
+                Some(address) => reference.push_str(&format!(
+                    " {}, {}.",
+                    escape_markdown(publisher),
+                    escape_markdown(address)
+                )),
+                None => reference.push_str(&format!(" {}.", escape_markdown(publisher))),
+            }
+        }
+        if let Some(doi) = &item.doi {
+            reference.push_str(&format!(" DOI: <https://doi.org/{doi}>."));
+        } else if let Some(url) = &item.url {
+            reference.push_str(&format!(" <{url}>"));
+        }
+        reference.push('\n');
+        Ok(reference)
+    }
+
+    fn name(&self) -> &str {
+        "Markdown"
+    }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item() -> BibItem {
+        BibItem {
+            citation_key: "smith_2024".to_string(),
+            title: "Markdown *everywhere*".to_string(),
+            authors: vec![
+                vec!["Smith".to_string(), "John".to_string()],
+                vec!["Doe".to_string(), "Jane".to_string()],
+            ],
+            pub_year: Some("2024".to_string()),
+            doi: Some("10.1000/xyz".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_markdown_backend_citations_link_to_markdown_page() {
+        let backend = MarkdownBackend::new();
+        let context = |variant| CitationContext {
+            bib_page_path: "../bibliography.html".to_string(),
+            chapter_path: "part/chapter1.md".to_string(),
+            variant,
+            anchor: "bib-cite-smith_2024-1".to_string(),
+            entry_anchor: "smith_2024".to_string(),
+        };
+
+        let cases = [
+            (
+                CitationVariant::Standard,
+                "[\\[smith\\_2024\\]](../bibliography.md)",
+            ),
+            (
+                CitationVariant::AuthorInText,
+                "[Smith and Doe (2024)](../bibliography.md)",
+            ),
+            (
+                CitationVariant::Parenthetical,
+                "[(Smith and Doe, 2024)](../bibliography.md)",
+            ),
+            (
+                CitationVariant::SuppressAuthor,
+                "[(2024)](../bibliography.md)",
+            ),
+        ];
+        for (variant, expected) in cases {
+            assert_eq!(
+                backend.format_citation(&item(), &context(variant)).unwrap(),
+                expected
+            );
+        }
+
+        // Citations of a chapter with its own reference list are left unlinked
+        let in_chapter = CitationContext {
+            bib_page_path: String::new(),
+            ..context(CitationVariant::Standard)
+        };
+        assert_eq!(
+            backend.format_citation(&item(), &in_chapter).unwrap(),
+            "\\[smith\\_2024\\]"
+        );
+    }
+
+    #[test]
+    fn test_markdown_backend_reference_is_a_list_item() {
+        let backend = MarkdownBackend::new();
+        assert_eq!(
+            backend.format_reference(&item(), &[]).unwrap(),
+            "- **\\[smith\\_2024\\]** Smith, John; Doe, Jane (2024). \
+             *Markdown \\*everywhere\\**. DOI: <https://doi.org/10.1000/xyz>.\n"
+        );
+        assert!(backend.supports_renderer(OutputFormat::Markdown));
+        assert!(!backend.supports_renderer(OutputFormat::Html));
+    }
+}
@@ -2,16 +2,29 @@
 //!
 //! This module provides a trait-based abstraction for different bibliography
 //! rendering backends, allowing users to choose between custom Handlebars
-//! templates (CustomBackend) or CSL citation styles (CslBackend).
+//! templates (CustomBackend) or CSL citation styles (CslBackend). LaTeX
+//! renderers get native biblatex commands instead (LatexBackend), Pandoc-based
+//! renderers get Pandoc citations back (PandocBackend), EPUB gets footnote-style
+//! citations (EpubBackend), and other non-HTML renderers get plain Markdown
+//! (MarkdownBackend).
 
 mod csl;
 mod custom;
+mod epub;
 mod hayagriva_style;
+mod latex;
+mod markdown;
+mod pandoc;
 
 pub use csl::CslBackend;
 pub use custom::CustomBackend;
+pub use epub::EpubBackend;
+pub use hayagriva_style::{list_archived_styles, StyleListing};
+pub use latex::LatexBackend;
+pub use markdown::MarkdownBackend;
+pub use pandoc::PandocBackend;
 
-use crate::models::BibItem;
+use crate::models::{BackReference, BibItem};
 use mdbook_preprocessor::errors::Result as MdResult;
 
 /// Backend mode determines which rendering system to use.
@@ -23,6 +36,21 @@ pub enum BackendMode {
     Csl,
 }
 
+/// Kind of output expected by the mdBook renderer the book is built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// HTML pages (mdBook's html renderer and unknown renderers).
+    Html,
+    /// EPUB pages, without JavaScript or CSS.
+    Epub,
+    /// Plain Markdown, without HTML, JavaScript or CSS.
+    Markdown,
+    /// LaTeX, with citations typeset by biblatex/biber.
+    Latex,
+    /// Pandoc Markdown, with citations processed by pandoc's citeproc.
+    Pandoc,
+}
+
 /// Citation variant determines how a citation should be rendered.
 ///
 /// Different citation syntaxes express different intents:
@@ -64,6 +92,10 @@ pub struct CitationContext {
     pub chapter_path: String,
     /// How the citation should be rendered (standard, author-in-text, etc.)
     pub variant: CitationVariant,
+    /// Unique id for this inline citation, used by bibliography back-references.
+    pub anchor: String,
+    /// Id of the cited entry on the page at `bib_page_path`.
+    pub entry_anchor: String,
 }
 
 /// Trait for bibliography rendering backends.
@@ -71,6 +103,10 @@ pub struct CitationContext {
 /// Implementations provide different rendering strategies:
 /// - CustomBackend: Uses Handlebars templates for custom formatting
 /// - CslBackend: Uses hayagriva's CSL driver for standardized formatting
+/// - LatexBackend: Emits biblatex commands for LaTeX renderers
+/// - PandocBackend: Re-emits Pandoc citations for Pandoc-based renderers
+/// - EpubBackend: Renders footnote-style citations for EPUB
+/// - MarkdownBackend: Produces plain Markdown for other non-HTML renderers
 pub trait BibliographyBackend {
     /// Format an inline citation reference.
     ///
@@ -89,8 +125,39 @@ pub trait BibliographyBackend {
     ///
     /// # Arguments
     /// * `item` - The bibliography item to format
-    fn format_reference(&self, item: &BibItem) -> MdResult<String>;
+    /// * `backrefs` - Locations in the book citing this item (may be empty)
+    fn format_reference(&self, item: &BibItem, backrefs: &[BackReference]) -> MdResult<String>;
+
+    /// Format a bibliography entry identified by `anchor` instead of its citation key.
+    ///
+    /// Used for the reference lists added to chapters, whose ids must not clash with the
+    /// bibliography page. Backends whose entries carry no id can keep the default.
+    fn format_anchored_reference(
+        &self,
+        item: &BibItem,
+        backrefs: &[BackReference],
+        _anchor: &str,
+    ) -> MdResult<String> {
+        self.format_reference(item, backrefs)
+    }
 
     /// Get the backend name for logging and debugging.
     fn name(&self) -> &str;
+
+    /// Whether the backend's output suits the given renderer output format.
+    fn supports_renderer(&self, format: OutputFormat) -> bool;
+
+    /// Whether citations of keys missing from the bibliography are formatted anyway,
+    /// leaving them to the renderer (pandoc's citeproc reports unknown keys itself).
+    fn passes_unknown_keys_through(&self) -> bool {
+        false
+    }
+
+    /// Footnote definitions for the citations formatted since the last call.
+    ///
+    /// Called at the end of each chapter; backends rendering citations as footnote
+    /// references (note styles) return the matching `[^label]: text` definitions.
+    fn chapter_footnotes(&self) -> MdResult<String> {
+        Ok(String::new())
+    }
 }
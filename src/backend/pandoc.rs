@@ -0,0 +1,114 @@
+//! Pandoc backend re-emitting Pandoc citation syntax.
+//!
+//! Used when the book is built with a Pandoc-based renderer (e.g. mdbook-pandoc)
+//! and `citation-syntax = "pandoc"`. Pandoc citations are left as written, unknown
+//! keys included, and native ones (`{{#cite key}}`, `@@key`) are normalized to
+//! `[@key]`, so that pandoc's citeproc renders every citation and the bibliography
+//! itself.
+
+use mdbook_preprocessor::errors::Result as MdResult;
+
+use crate::models::{BackReference, BibItem};
+
+use super::{BibliographyBackend, CitationContext, CitationVariant, OutputFormat};
+
+/// Pandoc backend producing Pandoc citation syntax instead of HTML.
+pub struct PandocBackend;
+
+impl PandocBackend {
+    /// Create a new PandocBackend.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Key as written in a Pandoc citation, using the `@{key}` form when the key
+    /// contains characters not allowed in bare Pandoc keys.
+    pub fn pandoc_key(key: &str) -> String {
+        let mut chars = key.chars();
+        let bare = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || "_:.#$%&-+?<>~/".contains(c))
+            && key.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+        if bare {
+            format!("@{key}")
+        } else {
+            format!("@{{{key}}}")
+        }
+    }
+}
+
+impl Default for PandocBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BibliographyBackend for PandocBackend {
+    fn format_citation(&self, item: &BibItem, context: &CitationContext) -> MdResult<String> {
+        let key = Self::pandoc_key(&item.citation_key);
+        Ok(match context.variant {
+            CitationVariant::AuthorInText => key,
+            CitationVariant::SuppressAuthor => format!("[-{key}]"),
+            CitationVariant::Standard | CitationVariant::Parenthetical => format!("[{key}]"),
+        })
+    }
+
+    fn format_reference(&self, _item: &BibItem, _backrefs: &[BackReference]) -> MdResult<String> {
+        // References are rendered by pandoc's citeproc from the exported CSL-JSON
+        Ok(String::new())
+    }
+
+    fn name(&self) -> &str {
+        "Pandoc (passthrough)"
+    }
+
+    fn supports_renderer(&self, format: OutputFormat) -> bool {
+        format == OutputFormat::Pandoc
+    }
+
+    fn passes_unknown_keys_through(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pandoc_backend_citation_syntax() {
+        let backend = PandocBackend::new();
+        let context = |variant| CitationContext {
+            bib_page_path: "bibliography.html".to_string(),
+            chapter_path: "chapter1.md".to_string(),
+            variant,
+            anchor: String::new(),
+            entry_anchor: String::new(),
+        };
+        let item = BibItem {
+            citation_key: "smith2024".to_string(),
+            ..Default::default()
+        };
+
+        let cases = [
+            (CitationVariant::Standard, "[@smith2024]"),
+            (CitationVariant::AuthorInText, "@smith2024"),
+            (CitationVariant::Parenthetical, "[@smith2024]"),
+            (CitationVariant::SuppressAuthor, "[-@smith2024]"),
+        ];
+        for (variant, expected) in cases {
+            assert_eq!(
+                backend.format_citation(&item, &context(variant)).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_pandoc_backend_braces_non_pandoc_keys() {
+        assert_eq!(PandocBackend::pandoc_key("doi:10.1/x"), "@doi:10.1/x");
+        assert_eq!(PandocBackend::pandoc_key("2024smith"), "@{2024smith}");
+        assert_eq!(PandocBackend::pandoc_key("smith."), "@{smith.}");
+    }
+}
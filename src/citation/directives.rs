@@ -0,0 +1,180 @@
+//! Per-chapter overrides of the book configuration.
+//!
+//! A chapter opts out of citation processing, or changes how it is processed, with an HTML
+//! comment anywhere outside code (`<!-- bib: off -->`) or a `bib:` line in its front matter.
+//! Several settings can be given at once: `<!-- bib: syntax=pandoc chapter-bib=false -->`.
+
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use mdbook_preprocessor::book::Chapter;
+use regex::Regex;
+
+use crate::config::CitationSyntax;
+
+use super::protect_code_blocks;
+
+// <!-- bib: settings -->
+const COMMENT_DIRECTIVE_PATTERN: &str = r"<!--\s*bib:(.*?)-->";
+// Leading `---` block of the chapter, holding a `bib: settings` line
+const FRONT_MATTER_PATTERN: &str = r"(?s)\A---[ \t]*\r?\n(.*?)\r?\n---[ \t]*(?:\r?\n|\z)";
+const FRONT_MATTER_LINE_PATTERN: &str = r"(?m)^bib:(.*)$";
+
+lazy_static! {
+    static ref COMMENT_DIRECTIVE_REGEX: Regex = Regex::new(COMMENT_DIRECTIVE_PATTERN).unwrap();
+    static ref FRONT_MATTER_REGEX: Regex = Regex::new(FRONT_MATTER_PATTERN).unwrap();
+    static ref FRONT_MATTER_LINE_REGEX: Regex = Regex::new(FRONT_MATTER_LINE_PATTERN).unwrap();
+}
+
+/// Settings of a single chapter, overriding the book configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChapterSettings {
+    /// Leave the chapter untouched: no citations expanded, no reference list (`off`)
+    pub off: bool,
+    /// Citation syntax of the chapter (`syntax=default|pandoc`)
+    pub citation_syntax: Option<CitationSyntax>,
+    /// Whether the chapter gets its reference list with `add-bib-in-chapters` (`chapter-bib=false`)
+    pub chapter_bib: Option<bool>,
+}
+
+impl ChapterSettings {
+    /// Read the settings given in the chapter's front matter and `<!-- bib: ... -->` comments.
+    ///
+    /// Later settings win; unknown or invalid ones are reported and ignored.
+    pub fn for_chapter(chapter: &Chapter) -> Self {
+        let mut settings = Self::default();
+        for directive in directives(&chapter.content) {
+            for setting in directive.split_whitespace() {
+                if let Err(e) = settings.apply(setting) {
+                    tracing::warn!(
+                        "Ignoring bib setting '{}' in chapter '{}': {}",
+                        setting,
+                        chapter.name,
+                        e
+                    );
+                }
+            }
+        }
+        settings
+    }
+
+    /// Whether the chapter gets a reference list when they are enabled for the book.
+    pub fn has_chapter_bib(&self) -> bool {
+        !self.off && self.chapter_bib != Some(false)
+    }
+
+    fn apply(&mut self, setting: &str) -> Result<(), anyhow::Error> {
+        match setting.split_once('=') {
+            None if setting == "off" => self.off = true,
+            None if setting == "on" => self.off = false,
+            Some(("syntax", value)) => {
+                self.citation_syntax = Some(CitationSyntax::from_str(value)?);
+            }
+            Some(("chapter-bib", value)) => {
+                self.chapter_bib = Some(value.parse().map_err(|_| {
+                    anyhow::anyhow!("'chapter-bib' must be true or false, not '{value}'")
+                })?);
+            }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Must be one of [off, on, syntax=..., chapter-bib=...]"
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace a front matter holding `bib:` settings with the equivalent `<!-- bib: ... -->`
+/// comment, returning `None` when there is nothing to replace.
+///
+/// mdBook has no front matter and would render the block as a rule followed by text; the
+/// comment keeps the settings for later passes without showing on the page.
+pub fn front_matter_to_comment(content: &str) -> Option<String> {
+    let front_matter = FRONT_MATTER_REGEX.captures(content)?;
+    let settings: Vec<&str> = FRONT_MATTER_LINE_REGEX
+        .captures_iter(&front_matter[1])
+        .map(|caps| caps.get(1).unwrap().as_str().trim())
+        .collect();
+    if settings.is_empty() {
+        return None;
+    }
+    let rest = &content[front_matter.get(0).unwrap().end()..];
+    Some(format!("<!-- bib: {} -->\n{rest}", settings.join(" ")))
+}
+
+/// The settings strings of a chapter, front matter first, in order of appearance.
+fn directives(content: &str) -> Vec<String> {
+    let mut directives: Vec<String> = Vec::new();
+    if let Some(front_matter) = FRONT_MATTER_REGEX.captures(content) {
+        directives.extend(
+            FRONT_MATTER_LINE_REGEX
+                .captures_iter(&front_matter[1])
+                .map(|caps| caps[1].trim().to_string()),
+        );
+    }
+    // Comments in code blocks are examples, not settings
+    let (content, _) = protect_code_blocks(content);
+    directives.extend(
+        COMMENT_DIRECTIVE_REGEX
+            .captures_iter(&content)
+            .map(|caps| caps[1].trim().to_string()),
+    );
+    directives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(content: &str) -> ChapterSettings {
+        ChapterSettings::for_chapter(&Chapter::new(
+            "Test",
+            content.to_string(),
+            "test.md",
+            vec![],
+        ))
+    }
+
+    #[test]
+    fn test_comment_and_front_matter_directives() {
+        assert_eq!(settings("No settings here."), ChapterSettings::default());
+        assert!(settings("# Glossary\n<!-- bib: off -->\n@user").off);
+        assert_eq!(
+            settings("<!--bib: syntax=pandoc chapter-bib=false-->"),
+            ChapterSettings {
+                off: false,
+                citation_syntax: Some(CitationSyntax::Pandoc),
+                chapter_bib: Some(false),
+            }
+        );
+        assert!(settings("---\ntitle: Changelog\nbib: off\n---\n\n# Changelog").off);
+        // A later `on` wins over the front matter
+        assert!(!settings("---\nbib: off\n---\n<!-- bib: on -->").off);
+    }
+
+    #[test]
+    fn test_directives_in_code_and_invalid_settings_are_ignored() {
+        assert!(!settings("```html\n<!-- bib: off -->\n```\nand `<!-- bib: off -->`").off);
+        assert_eq!(
+            settings("<!-- bib: syntax=latex chapter-bib=maybe verbose -->"),
+            ChapterSettings::default()
+        );
+        // Front matter only counts at the very start
+        assert!(!settings("# Title\n---\nbib: off\n---\n").off);
+    }
+
+    #[test]
+    fn test_front_matter_becomes_comment() {
+        let content =
+            "---\ntitle: Notes\nbib: syntax=pandoc\nbib: chapter-bib=false\n---\n\n# Notes";
+        let replaced = front_matter_to_comment(content).unwrap();
+        assert_eq!(
+            replaced,
+            "<!-- bib: syntax=pandoc chapter-bib=false -->\n\n# Notes"
+        );
+        assert_eq!(settings(&replaced), settings(content));
+        assert_eq!(front_matter_to_comment("---\ntitle: Notes\n---\n"), None);
+        assert_eq!(front_matter_to_comment("# Notes"), None);
+    }
+}
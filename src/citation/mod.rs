@@ -1,5 +1,5 @@
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Component, Path};
 
 use indexmap::IndexMap;
@@ -8,11 +8,21 @@ use mdbook_preprocessor::book::{Book, BookItem, Chapter};
 use regex::Regex;
 
 use crate::backend::{BibliographyBackend, CitationContext, CitationVariant};
-use crate::config::{CitationSyntax, SortOrder};
-use crate::models::BibItem;
+use crate::config::{
+    CitationSyntax, Numbering, SortOrder, DEFAULT_POPOVER_CSS_TEMPLATE, DEFAULT_POPOVER_JS_TEMPLATE,
+};
+use crate::models::{BackReference, BibItem, ChapterCitations};
 use crate::renderer;
 
+mod directives;
+mod scan;
+
+pub use directives::ChapterSettings;
+pub use scan::{find_citations, find_malformed_citations, find_nocite_keys, find_partial_key};
+
 static BIB_OUT_FILE: &str = "bibliography";
+/// Prefix of the entry ids in chapter reference lists, keeping them apart from the bibliography page.
+pub const CHAPTER_BIB_ANCHOR_PREFIX: &str = "chapter-bib-";
 
 // Placeholder used to protect escaped @ symbols during processing.
 // Uses Unicode private use area characters to avoid conflicts with normal text.
@@ -77,6 +87,12 @@ pub const PANDOC_BRACKETED_PATTERN: &str =
 pub const PANDOC_CITE_PATTERN: &str =
     r"(^|[^\\@\w/])@([a-zA-Z_][a-zA-Z0-9_]*(?:[:.#$%&\-+?<>~/][a-zA-Z0-9_]+)*)";
 
+// Entries listed without citing them: {{#nocite key1 key2}} or {{#nocite *}}
+pub const NOCITE_PATTERN: &str = r"\{\{\s*\#nocite\s+([^}]*?)\s*\}\}";
+
+// Placement of a chapter's reference list: {{#chapter_bibliography}}
+pub const CHAPTER_BIB_DIRECTIVE_PATTERN: &str = r"\{\{\s*\#chapter_bibliography\s*\}\}";
+
 // Code block patterns for protection
 const FENCED_CODE_PATTERN: &str = r"(?s)```[^\n]*\n.*?```|~~~[^\n]*\n.*?~~~";
 const INLINE_CODE_PATTERN: &str = r"`[^`\n]+`";
@@ -90,6 +106,9 @@ lazy_static! {
         Regex::new(PANDOC_SUPPRESS_AUTHOR_PATTERN).unwrap();
     static ref PANDOC_BRACKETED_REGEX: Regex = Regex::new(PANDOC_BRACKETED_PATTERN).unwrap();
     static ref PANDOC_CITE_REGEX: Regex = Regex::new(PANDOC_CITE_PATTERN).unwrap();
+    static ref NOCITE_REGEX: Regex = Regex::new(NOCITE_PATTERN).unwrap();
+    static ref CHAPTER_BIB_DIRECTIVE_REGEX: Regex =
+        Regex::new(CHAPTER_BIB_DIRECTIVE_PATTERN).unwrap();
     // Code block patterns
     static ref FENCED_CODE_REGEX: Regex = Regex::new(FENCED_CODE_PATTERN).unwrap();
     static ref INLINE_CODE_REGEX: Regex = Regex::new(INLINE_CODE_PATTERN).unwrap();
@@ -101,6 +120,20 @@ pub struct CitationResult {
     pub all_cited: HashSet<String>,
     /// Citations found per chapter, keyed by chapter path.
     pub per_chapter: IndexMap<String, HashSet<String>>,
+    /// Chapters citing each entry, keyed by citation key (in book order).
+    pub backrefs: IndexMap<String, Vec<BackReference>>,
+    /// Number of inline citations per chapter, keyed by chapter path.
+    pub citation_counts: IndexMap<String, u32>,
+    /// Entries listed through `nocite` without being cited, in order.
+    pub nocite: Vec<String>,
+    /// Chapter-local indices of the cited entries, keyed by chapter path.
+    /// Only filled with per-chapter numbering.
+    pub chapter_indices: IndexMap<String, HashMap<String, u32>>,
+}
+
+/// Build the id of the `occurrence`-th (1-based) inline citation of a key in the book.
+pub fn citation_anchor(citation_key: &str, occurrence: u32) -> String {
+    format!("bib-cite-{citation_key}-{occurrence}")
 }
 
 /// Protect code blocks from citation processing by replacing them with placeholders.
@@ -147,14 +180,32 @@ fn restore_code_blocks(content: &str, blocks: &[String]) -> String {
 }
 
 /// Expand all citation references in the book, replacing placeholders with formatted citations.
+///
+/// With `link_in_chapter`, citations link to the chapter's own reference list instead of the
+/// bibliography page. With per-chapter numbering, they are also numbered from 1 in each
+/// chapter; entries keep a book-wide index, in order of first citation, for the bibliography
+/// chapter.
+///
+/// Entries given by `{{#nocite ...}}` directives and the `nocite` keys are added to the cited
+/// entries after all citations, without any inline output.
 pub fn expand_cite_references_in_book(
     book: &mut Book,
     bibliography: &mut IndexMap<String, BibItem>,
     backend: &dyn BibliographyBackend,
     citation_syntax: &CitationSyntax,
+    numbering: Numbering,
+    link_in_chapter: bool,
+    nocite: &[String],
 ) -> CitationResult {
+    // Chapter-local numbers only make sense next to the chapter's list
+    let link_in_chapter = link_in_chapter || numbering == Numbering::PerChapter;
     let mut all_cited = HashSet::new();
     let mut per_chapter: IndexMap<String, HashSet<String>> = IndexMap::new();
+    let mut backrefs: IndexMap<String, Vec<BackReference>> = IndexMap::new();
+    let mut citation_counts: IndexMap<String, u32> = IndexMap::new();
+    let mut nocite_keys: Vec<String> = Vec::new();
+    let mut chapter_indices: IndexMap<String, HashMap<String, u32>> = IndexMap::new();
+    let mut global_indices: IndexMap<String, u32> = IndexMap::new();
     let mut last_index = 0;
 
     let syntax_info = match citation_syntax {
@@ -170,42 +221,222 @@ pub fn expand_cite_references_in_book(
                     syntax_info,
                     chapter_path.as_path().display()
                 );
+                // Citation counts before this chapter locate its first anchor per key
+                let counts_before: HashMap<String, u32> = bibliography
+                    .iter()
+                    .map(|(key, item)| (key.clone(), item.citation_count))
+                    .collect();
+                if numbering == Numbering::PerChapter {
+                    last_index = 0;
+                    for item in bibliography.values_mut() {
+                        item.index = None;
+                    }
+                }
+                if let Some(content) = directives::front_matter_to_comment(&ch.content) {
+                    ch.content = content;
+                }
+                let (content, chapter_nocite) = take_nocite_directives(ch);
+                ch.content = content;
+                nocite_keys.extend(chapter_nocite);
+
                 let mut chapter_cited = HashSet::new();
-                let new_content = replace_all_placeholders(
+                let mut new_content = replace_all_placeholders(
                     ch,
                     bibliography,
                     &mut chapter_cited,
                     backend,
                     &mut last_index,
                     citation_syntax,
+                    link_in_chapter,
                 );
+                match backend.chapter_footnotes() {
+                    Ok(footnotes) if !footnotes.is_empty() => {
+                        new_content = format!("{}\n\n{footnotes}\n", new_content.trim_end());
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(
+                        "Failed to render citation footnotes in {}: {}",
+                        chapter_path.display(),
+                        e
+                    ),
+                }
                 ch.content = new_content;
+
+                let chapter_key = chapter_path.display().to_string();
+                let chapter_html = chapter_html_path(chapter_path);
+                let label = chapter_label(ch);
+                // Iterate in bibliography order to keep back-references deterministic
+                for key in bibliography.keys().filter(|k| chapter_cited.contains(*k)) {
+                    let anchor = citation_anchor(key, counts_before[key] + 1);
+                    backrefs
+                        .entry(key.clone())
+                        .or_default()
+                        .push(BackReference {
+                            label: label.clone(),
+                            chapter_path: chapter_key.clone(),
+                            href: format!("{chapter_html}#{anchor}"),
+                            anchor,
+                        });
+                }
+
+                if numbering == Numbering::PerChapter {
+                    let mut local: Vec<(String, u32)> = bibliography
+                        .iter()
+                        .filter_map(|(key, item)| Some((key.clone(), item.index?)))
+                        .collect();
+                    local.sort_by_key(|(_, index)| *index);
+                    for (key, _) in &local {
+                        let next = global_indices.len() as u32 + 1;
+                        global_indices.entry(key.clone()).or_insert(next);
+                    }
+                    chapter_indices.insert(chapter_key.clone(), local.into_iter().collect());
+                }
+
+                let chapter_citations = bibliography
+                    .iter()
+                    .map(|(key, item)| item.citation_count - counts_before[key])
+                    .sum();
+                citation_counts.insert(chapter_key.clone(), chapter_citations);
+
                 all_cited.extend(chapter_cited.clone());
-                per_chapter.insert(chapter_path.display().to_string(), chapter_cited);
+                per_chapter.insert(chapter_key, chapter_cited);
             }
         }
     });
+    if numbering == Numbering::PerChapter {
+        for (key, item) in bibliography.iter_mut() {
+            item.index = global_indices.get(key).copied();
+        }
+    }
+
+    nocite_keys.extend(nocite.iter().cloned());
+    let nocite = resolve_nocite(bibliography, &nocite_keys);
+    all_cited.extend(nocite.iter().cloned());
+
     CitationResult {
         all_cited,
         per_chapter,
+        backrefs,
+        citation_counts,
+        nocite,
+        chapter_indices,
+    }
+}
+
+/// Remove the `{{#nocite ...}}` directives of a chapter, returning its content and their keys.
+fn take_nocite_directives(chapter: &Chapter) -> (String, Vec<String>) {
+    let (content, code_blocks) = protect_code_blocks(&chapter.content);
+    if !NOCITE_REGEX.is_match(&content) || ChapterSettings::for_chapter(chapter).off {
+        return (chapter.content.clone(), Vec::new());
+    }
+
+    let mut keys = Vec::new();
+    let content = NOCITE_REGEX.replace_all(&content, |caps: &regex::Captures| {
+        keys.extend(
+            caps[1]
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|key| !key.is_empty())
+                .map(str::to_string),
+        );
+        ""
+    });
+    (restore_code_blocks(&content, &code_blocks), keys)
+}
+
+/// Entries listed through `nocite` and not cited, numbered after the cited ones.
+///
+/// `*` stands for every entry of the bibliography.
+fn resolve_nocite(bibliography: &mut IndexMap<String, BibItem>, keys: &[String]) -> Vec<String> {
+    let keys: Vec<String> = if keys.iter().any(|key| key == "*") {
+        bibliography.keys().cloned().collect()
+    } else {
+        keys.to_vec()
+    };
+
+    let mut last_index = bibliography
+        .values()
+        .filter_map(|item| item.index)
+        .max()
+        .unwrap_or(0);
+    let mut nocite = Vec::new();
+    for key in keys {
+        let Some(item) = bibliography.get_mut(&key) else {
+            tracing::warn!("Unknown bibliography reference in nocite: '{}'", key);
+            continue;
+        };
+        if item.index.is_none() {
+            last_index += 1;
+            item.index = Some(last_index);
+            nocite.push(key);
+        }
     }
+    nocite
+}
+
+/// Keep only the back-references pointing into the given chapter, linking them in-page.
+fn local_backrefs(
+    backrefs: &IndexMap<String, Vec<BackReference>>,
+    chapter_key: &str,
+) -> IndexMap<String, Vec<BackReference>> {
+    backrefs
+        .iter()
+        .map(|(key, refs)| {
+            let local = refs
+                .iter()
+                .filter(|backref| backref.chapter_path == chapter_key)
+                .map(|backref| BackReference {
+                    href: format!("#{}", backref.anchor),
+                    ..backref.clone()
+                })
+                .collect();
+            (key.clone(), local)
+        })
+        .collect()
+}
+
+/// Label used for a chapter in back-references: "Ch. 2" when numbered, the chapter name otherwise.
+fn chapter_label(chapter: &Chapter) -> String {
+    match &chapter.number {
+        Some(number) => format!("Ch. {}", number.to_string().trim_end_matches('.')),
+        None => chapter.name.clone(),
+    }
+}
+
+/// Rendered HTML path of a chapter, relative to the book root.
+fn chapter_html_path(chapter_path: &Path) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for component in chapter_path.with_extension("html").components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            _ => {}
+        }
+    }
+    parts.join("/")
 }
 
 /// Add bibliography at the end of each chapter.
+///
+/// The list goes in place of a `{{#chapter_bibliography}}` directive when the chapter has one,
+/// headed by `chapter_refs_header` rendered for the chapter. `assets_html`, the scripts and
+/// styles the list relies on, goes at the start of the chapter.
 pub fn add_bib_at_end_of_chapters(
     book: &mut Book,
     bibliography: &mut IndexMap<String, BibItem>,
     backend: &dyn BibliographyBackend,
-    chapter_refs_header: &str,
+    chapter_refs_header: &dyn Fn(&ChapterCitations) -> String,
     order: SortOrder,
-    per_chapter_citations: &IndexMap<String, HashSet<String>>,
-    css_html: &str,
+    citations: &CitationResult,
+    assets_html: &str,
 ) {
     book.for_each_mut(|section: &mut BookItem| {
         if let BookItem::Chapter(ref mut ch) = *section {
             if let Some(ref chapter_path) = ch.path {
                 let chapter_key = chapter_path.display().to_string();
-                let cited = per_chapter_citations
+                let cited = citations
+                    .per_chapter
                     .get(&chapter_key)
                     .cloned()
                     .unwrap_or_default();
@@ -217,29 +448,159 @@ pub fn add_bib_at_end_of_chapters(
                     );
                     return;
                 }
+                if !ChapterSettings::for_chapter(ch).has_chapter_bib() {
+                    tracing::debug!("Chapter bibliography turned off in {}", chapter_key);
+                    return;
+                }
 
                 tracing::debug!("Adding bibliography at the end of chapter {}", chapter_key);
                 tracing::debug!("Refs cited in this chapter: {:?}", cited);
 
+                // With per-chapter numbering, list entries under their chapter-local indices
+                let global_indices: Vec<(String, Option<u32>)> = citations
+                    .chapter_indices
+                    .get(&chapter_key)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|(key, index)| {
+                        let item = bibliography.get_mut(key)?;
+                        Some((key.clone(), item.index.replace(*index)))
+                    })
+                    .collect();
+
                 let ch_bib_content_html = renderer::generate_bibliography_html(
                     bibliography,
                     &cited,
                     true,
                     backend,
                     order.clone(),
+                    &local_backrefs(&citations.backrefs, &chapter_key),
+                    CHAPTER_BIB_ANCHOR_PREFIX,
                 );
 
-                // Inject CSS at the start and bibliography at the end
-                let new_content = format!(
-                    "{}\n{}\n{}\n{}",
-                    css_html, ch.content, chapter_refs_header, ch_bib_content_html
-                );
+                for (key, index) in global_indices {
+                    bibliography[&key].index = index;
+                }
+
+                let header = chapter_refs_header(&ChapterCitations {
+                    chapter_name: ch.name.clone(),
+                    citation_count: citations
+                        .citation_counts
+                        .get(&chapter_key)
+                        .copied()
+                        .unwrap_or_default(),
+                    entry_count: cited
+                        .iter()
+                        .filter(|key| bibliography.contains_key(*key))
+                        .count(),
+                });
+
+                let (content, code_blocks) = protect_code_blocks(&ch.content);
+                let new_content = if CHAPTER_BIB_DIRECTIVE_REGEX.is_match(&content) {
+                    // Inject assets at the start and bibliography at the first directive
+                    let refs = format!("{header}\n{ch_bib_content_html}");
+                    let mut placed = false;
+                    let content =
+                        CHAPTER_BIB_DIRECTIVE_REGEX.replace_all(&content, |_: &regex::Captures| {
+                            if std::mem::replace(&mut placed, true) {
+                                String::new()
+                            } else {
+                                refs.clone()
+                            }
+                        });
+                    format!(
+                        "{assets_html}\n{}",
+                        restore_code_blocks(&content, &code_blocks)
+                    )
+                } else {
+                    // Inject assets at the start and bibliography at the end
+                    format!(
+                        "{}\n{}\n{}\n{}",
+                        assets_html, ch.content, header, ch_bib_content_html
+                    )
+                };
                 ch.content = new_content;
             }
         }
     });
 }
 
+/// Remove the `{{#chapter_bibliography}}` directives left in chapters without a reference list.
+pub fn remove_chapter_bibliography_directives(book: &mut Book) {
+    book.for_each_mut(|section: &mut BookItem| {
+        if let BookItem::Chapter(ref mut ch) = *section {
+            if ChapterSettings::for_chapter(ch).off {
+                return;
+            }
+            let (content, code_blocks) = protect_code_blocks(&ch.content);
+            if CHAPTER_BIB_DIRECTIVE_REGEX.is_match(&content) {
+                let content = CHAPTER_BIB_DIRECTIVE_REGEX.replace_all(&content, "");
+                ch.content = restore_code_blocks(&content, &code_blocks);
+            }
+        }
+    });
+}
+
+/// Add hover preview popovers for the inline citations of each chapter.
+///
+/// The formatted references cited in a chapter are embedded in the page as a JSON blob,
+/// keyed by citation key, so previews work offline in the static HTML output. `js_html`
+/// goes with them, for the scripts called from the formatted references.
+pub fn add_citation_popovers(
+    book: &mut Book,
+    bibliography: &IndexMap<String, BibItem>,
+    backend: &dyn BibliographyBackend,
+    per_chapter_citations: &IndexMap<String, HashSet<String>>,
+    js_html: &str,
+) {
+    book.for_each_mut(|section: &mut BookItem| {
+        if let BookItem::Chapter(ref mut ch) = *section {
+            if let Some(ref chapter_path) = ch.path {
+                let chapter_key = chapter_path.display().to_string();
+                let Some(cited) = per_chapter_citations.get(&chapter_key) else {
+                    return;
+                };
+
+                let previews: IndexMap<&str, String> = bibliography
+                    .iter()
+                    .filter(|(key, _)| cited.contains(*key))
+                    .filter_map(|(key, item)| match backend.format_reference(item, &[]) {
+                        Ok(html) => Some((key.as_str(), html.trim().to_string())),
+                        Err(e) => {
+                            tracing::warn!("Skipping popover for '{}': {}", key, e);
+                            None
+                        }
+                    })
+                    .collect();
+
+                if previews.is_empty() {
+                    return;
+                }
+
+                tracing::debug!("Adding citation popovers to chapter {}", chapter_key);
+                ch.content = format!(
+                    "{}
+
+{}{}",
+                    ch.content,
+                    js_html,
+                    citation_popovers_html(&previews)
+                );
+            }
+        }
+    });
+}
+
+/// Build the data blob, script and style implementing citation popovers.
+fn citation_popovers_html(previews: &IndexMap<&str, String>) -> String {
+    let data = renderer::script_json(previews);
+    format!(
+        "<script type=\"application/json\" id=\"bib-popover-data\">{data}</script>\n\
+         <style>{DEFAULT_POPOVER_CSS_TEMPLATE}</style>\n\
+         <script type=\"text/javascript\">\n{DEFAULT_POPOVER_JS_TEMPLATE}\n</script>\n"
+    )
+}
+
 /// Replace a single citation placeholder with its formatted citation.
 ///
 /// This helper function handles the common logic for all citation patterns:
@@ -249,7 +610,7 @@ pub fn add_bib_at_end_of_chapters(
 /// - Returns appropriate error messages for missing or invalid citations
 fn replace_citation_placeholder(
     citation_key: &str,
-    chapter_path: &Path,
+    page: &ChapterPage,
     bib: &RefCell<&mut IndexMap<String, BibItem>>,
     cited_set: &RefCell<&mut HashSet<String>>,
     idx: &RefCell<&mut u32>,
@@ -265,7 +626,6 @@ fn replace_citation_placeholder(
     let mut idx_mut = idx.borrow_mut();
 
     if bib_mut.contains_key(cite) {
-        let path_to_root = breadcrumbs_up_to_root(chapter_path);
         let item = bib_mut.get_mut(cite).unwrap();
 
         // Assign index on first occurrence
@@ -273,11 +633,15 @@ fn replace_citation_placeholder(
             **idx_mut += 1;
             item.index = Some(**idx_mut);
         }
+        item.citation_count += 1;
+        let anchor = citation_anchor(cite, item.citation_count);
 
         let context = CitationContext {
-            bib_page_path: format!("{path_to_root}{BIB_OUT_FILE}.html"),
-            chapter_path: chapter_path.display().to_string(),
+            bib_page_path: page.bib_page_path.clone(),
+            chapter_path: page.chapter_path.clone(),
             variant,
+            anchor,
+            entry_anchor: format!("{}{cite}", page.entry_anchor_prefix),
         };
 
         let formatted = backend.format_citation(item, &context).unwrap_or_else(|e| {
@@ -292,12 +656,42 @@ fn replace_citation_placeholder(
             formatted
         );
         formatted
+    } else if backend.passes_unknown_keys_through() {
+        tracing::debug!("Passing unknown reference '{}' through", cite);
+        let item = BibItem {
+            citation_key: cite.to_owned(),
+            ..Default::default()
+        };
+        let context = CitationContext {
+            bib_page_path: page.bib_page_path.clone(),
+            chapter_path: page.chapter_path.clone(),
+            variant,
+            anchor: String::new(),
+            entry_anchor: String::new(),
+        };
+        backend
+            .format_citation(&item, &context)
+            .unwrap_or_else(|e| {
+                tracing::error!("Failed to format citation for '{}': {}", cite, e);
+                format!("\\[Error formatting {cite}\\]")
+            })
     } else {
         tracing::warn!("Unknown bibliography reference: '{}'", cite);
         format!("\\[Unknown bib ref: {cite}\\]")
     }
 }
 
+/// Chapter being processed and the page its citations link to.
+struct ChapterPage {
+    chapter_path: String,
+    /// Bibliography page relative to the chapter, empty to link to the chapter itself
+    bib_page_path: String,
+    /// Prefix of the entry ids on that page
+    entry_anchor_prefix: &'static str,
+}
+
+/// Replace the citations of a chapter, linking them to the chapter's own reference list
+/// instead of the bibliography page when `link_in_chapter` is set and the chapter gets one.
 pub fn replace_all_placeholders(
     chapter: &Chapter,
     bibliography: &mut IndexMap<String, BibItem>,
@@ -305,8 +699,32 @@ pub fn replace_all_placeholders(
     backend: &dyn BibliographyBackend,
     last_index: &mut u32,
     citation_syntax: &CitationSyntax,
+    link_in_chapter: bool,
 ) -> String {
+    let settings = ChapterSettings::for_chapter(chapter);
+    if settings.off {
+        tracing::debug!("Citations turned off in chapter '{}'", chapter.name);
+        return chapter.content.clone();
+    }
+    let citation_syntax = settings.citation_syntax.as_ref().unwrap_or(citation_syntax);
+
     let chapter_path = chapter.path.as_deref().unwrap_or_else(|| Path::new(""));
+    let page = if link_in_chapter && settings.has_chapter_bib() {
+        ChapterPage {
+            chapter_path: chapter_path.display().to_string(),
+            bib_page_path: String::new(),
+            entry_anchor_prefix: CHAPTER_BIB_ANCHOR_PREFIX,
+        }
+    } else {
+        ChapterPage {
+            chapter_path: chapter_path.display().to_string(),
+            bib_page_path: format!(
+                "{}{BIB_OUT_FILE}.html",
+                breadcrumbs_up_to_root(chapter_path)
+            ),
+            entry_anchor_prefix: "",
+        }
+    };
 
     // Wrap mutable state in RefCell for interior mutability
     let bib = RefCell::new(bibliography);
@@ -316,6 +734,15 @@ pub fn replace_all_placeholders(
     // Step 1: Protect code blocks from citation processing
     let (mut content, code_blocks) = protect_code_blocks(&chapter.content);
 
+    // Formatted citations are protected the same way, so that later passes don't match
+    // them again (the Pandoc backend emits citations in the syntax it reads)
+    let protected = RefCell::new(code_blocks);
+    let protect = |citation: String| {
+        let mut protected = protected.borrow_mut();
+        protected.push(citation);
+        format!("{CODE_BLOCK_PLACEHOLDER}{}\u{E001}", protected.len() - 1)
+    };
+
     // Step 2: For Pandoc syntax, protect escaped @ symbols
     if *citation_syntax == CitationSyntax::Pandoc {
         content = ESCAPED_AT_REGEX
@@ -327,15 +754,15 @@ pub fn replace_all_placeholders(
     content = REF_REGEX
         .replace_all(&content, |caps: &regex::Captures| {
             let citation_key = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-            replace_citation_placeholder(
+            protect(replace_citation_placeholder(
                 citation_key,
-                chapter_path,
+                &page,
                 &bib,
                 &cited_set,
                 &idx,
                 backend,
                 CitationVariant::Standard,
-            )
+            ))
         })
         .into_owned();
 
@@ -344,15 +771,15 @@ pub fn replace_all_placeholders(
     content = AT_REF_REGEX
         .replace_all(&content, |caps: &regex::Captures| {
             let citation_key = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-            replace_citation_placeholder(
+            protect(replace_citation_placeholder(
                 citation_key,
-                chapter_path,
+                &page,
                 &bib,
                 &cited_set,
                 &idx,
                 backend,
                 CitationVariant::Standard,
-            )
+            ))
         })
         .into_owned();
 
@@ -362,15 +789,15 @@ pub fn replace_all_placeholders(
         content = PANDOC_SUPPRESS_AUTHOR_REGEX
             .replace_all(&content, |caps: &regex::Captures| {
                 let citation_key = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-                replace_citation_placeholder(
+                protect(replace_citation_placeholder(
                     citation_key,
-                    chapter_path,
+                    &page,
                     &bib,
                     &cited_set,
                     &idx,
                     backend,
                     CitationVariant::SuppressAuthor,
-                )
+                ))
             })
             .into_owned();
 
@@ -378,15 +805,15 @@ pub fn replace_all_placeholders(
         content = PANDOC_BRACKETED_REGEX
             .replace_all(&content, |caps: &regex::Captures| {
                 let citation_key = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-                replace_citation_placeholder(
+                protect(replace_citation_placeholder(
                     citation_key,
-                    chapter_path,
+                    &page,
                     &bib,
                     &cited_set,
                     &idx,
                     backend,
                     CitationVariant::Parenthetical,
-                )
+                ))
             })
             .into_owned();
 
@@ -396,15 +823,15 @@ pub fn replace_all_placeholders(
             .replace_all(&content, |caps: &regex::Captures| {
                 let prefix = caps.get(1).map(|m| m.as_str()).unwrap_or("");
                 let citation_key = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-                let replacement = replace_citation_placeholder(
+                let replacement = protect(replace_citation_placeholder(
                     citation_key,
-                    chapter_path,
+                    &page,
                     &bib,
                     &cited_set,
                     &idx,
                     backend,
                     CitationVariant::AuthorInText,
-                );
+                ));
                 format!("{prefix}{replacement}")
             })
             .into_owned();
@@ -413,8 +840,8 @@ pub fn replace_all_placeholders(
         content = content.replace(ESCAPED_AT_PLACEHOLDER, "@");
     }
 
-    // Step 6: Restore code blocks
-    restore_code_blocks(&content, &code_blocks)
+    // Step 6: Restore code blocks and formatted citations
+    restore_code_blocks(&content, &protected.into_inner())
 }
 
 fn breadcrumbs_up_to_root(source_file: &Path) -> String {
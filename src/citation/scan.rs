@@ -0,0 +1,197 @@
+//! Locating citations in chapter sources without expanding them.
+//!
+//! Tools working on the Markdown sources (`mdbook-bib check`, the language server) need
+//! the position of each citation, so they scan with the same patterns as the expansion.
+//! Code blocks and already matched citations are blanked out instead of replaced, which
+//! keeps the byte offsets of the original content.
+
+use std::ops::Range;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::backend::CitationVariant;
+use crate::config::CitationSyntax;
+
+use super::{
+    AT_REF_REGEX, ESCAPED_AT_REGEX, FENCED_CODE_REGEX, INLINE_CODE_REGEX, NOCITE_REGEX,
+    PANDOC_BRACKETED_REGEX, PANDOC_CITE_REGEX, PANDOC_SUPPRESS_AUTHOR_REGEX, REF_REGEX,
+};
+
+// Leftovers of citations none of the patterns accepted: {{#cite}}, {{#cite a b}}, @@
+const MALFORMED_CITE_PATTERN: &str = r"\{\{\s*\#cite\b[^}\n]*(?:\}\})?|@@\S*";
+// Leftovers of bracketed Pandoc citations: [@key, p. 3], [@1984key]
+const MALFORMED_PANDOC_PATTERN: &str = r"\[-?@[^\]\n]*\]?";
+
+// Citation openings followed by the key being typed, at the end of the text before the
+// cursor, with the key characters of the citation patterns: {{#cite ke, @@ke, {{#nocite a ke
+const PARTIAL_CITE_PATTERN: &str =
+    r"(?:\{\{\s*\#cite\s+|@@|\{\{\s*\#nocite\s+(?:[^}\s,]+[\s,]+)*)([a-zA-Z0-9_\-:./@]*)$";
+// With Pandoc syntax: [@ke, [-@ke, [@a; @ke, @ke
+const PARTIAL_PANDOC_PATTERN: &str = r"(?:^|[^\\@\w/])-?@([a-zA-Z0-9_:.#$%&\-+?<>~/]*)$";
+
+lazy_static! {
+    static ref PARTIAL_CITE_REGEX: Regex = Regex::new(PARTIAL_CITE_PATTERN).unwrap();
+    static ref PARTIAL_PANDOC_REGEX: Regex = Regex::new(PARTIAL_PANDOC_PATTERN).unwrap();
+    static ref MALFORMED_CITE_REGEX: Regex = Regex::new(MALFORMED_CITE_PATTERN).unwrap();
+    static ref MALFORMED_PANDOC_REGEX: Regex = Regex::new(MALFORMED_PANDOC_PATTERN).unwrap();
+}
+
+/// A citation found in a chapter's source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CitationMatch {
+    /// The cited key.
+    pub key: String,
+    /// The variant the citation is rendered with.
+    pub variant: CitationVariant,
+    /// Byte range of the whole citation (e.g. `{{#cite key}}`).
+    pub span: Range<usize>,
+    /// Byte range of the key alone.
+    pub key_span: Range<usize>,
+}
+
+/// Find the citations of `content`, in order of appearance.
+pub fn find_citations(content: &str, citation_syntax: &CitationSyntax) -> Vec<CitationMatch> {
+    scan(content, citation_syntax).citations
+}
+
+/// Find the keys given by `{{#nocite ...}}` directives, with their byte ranges.
+pub fn find_nocite_keys(content: &str) -> Vec<(String, Range<usize>)> {
+    let text = blank_code_blocks(content);
+    let mut keys = Vec::new();
+    for caps in NOCITE_REGEX.captures_iter(&text) {
+        let list = caps.get(1).unwrap();
+        let mut offset = list.start();
+        for part in list
+            .as_str()
+            .split_inclusive(|c: char| c.is_whitespace() || c == ',')
+        {
+            let key = part.trim_end_matches(|c: char| c.is_whitespace() || c == ',');
+            if !key.is_empty() {
+                keys.push((key.to_string(), offset..offset + key.len()));
+            }
+            offset += part.len();
+        }
+    }
+    keys
+}
+
+/// Find the text that starts a citation but matches none of the citation patterns,
+/// such as `{{#cite}}` or, with Pandoc syntax, `[@key, p. 3]`.
+pub fn find_malformed_citations(
+    content: &str,
+    citation_syntax: &CitationSyntax,
+) -> Vec<Range<usize>> {
+    scan(content, citation_syntax).malformed
+}
+
+/// Find the key being typed at the end of `text`, such as `smi` in `see {{#cite smi`, and
+/// return its byte range, empty right after the citation opening.
+pub fn find_partial_key(text: &str, citation_syntax: &CitationSyntax) -> Option<Range<usize>> {
+    let mut regexes = vec![&*PARTIAL_CITE_REGEX];
+    if *citation_syntax == CitationSyntax::Pandoc {
+        regexes.push(&PARTIAL_PANDOC_REGEX);
+    }
+    regexes
+        .into_iter()
+        .find_map(|regex| regex.captures(text))
+        .map(|caps| caps.get(1).unwrap().range())
+}
+
+struct Scan {
+    citations: Vec<CitationMatch>,
+    malformed: Vec<Range<usize>>,
+}
+
+/// Match the citation patterns in the order the expansion applies them, then look for
+/// citation openings in what is left once code and citations are blanked out.
+fn scan(content: &str, citation_syntax: &CitationSyntax) -> Scan {
+    let mut text = blank_code_blocks(content);
+    let pandoc = *citation_syntax == CitationSyntax::Pandoc;
+    if pandoc {
+        let escaped: Vec<Range<usize>> = ESCAPED_AT_REGEX
+            .find_iter(&text)
+            .map(|m| m.range())
+            .collect();
+        for range in escaped {
+            blank(&mut text, range);
+        }
+    }
+
+    let mut patterns: Vec<(&Regex, usize, CitationVariant)> = vec![
+        (&REF_REGEX, 1, CitationVariant::Standard),
+        (&AT_REF_REGEX, 2, CitationVariant::Standard),
+    ];
+    if pandoc {
+        patterns.extend([
+            (
+                &*PANDOC_SUPPRESS_AUTHOR_REGEX,
+                1,
+                CitationVariant::SuppressAuthor,
+            ),
+            (&*PANDOC_BRACKETED_REGEX, 1, CitationVariant::Parenthetical),
+            (&*PANDOC_CITE_REGEX, 2, CitationVariant::AuthorInText),
+        ]);
+    }
+
+    let mut citations = Vec::new();
+    let mut malformed = Vec::new();
+    for (regex, key_group, variant) in patterns {
+        if variant == CitationVariant::AuthorInText {
+            // Before `@key` is taken out of a bracket it could not be read as a whole
+            malformed.extend(MALFORMED_PANDOC_REGEX.find_iter(&text).map(|m| m.range()));
+        }
+        let found: Vec<CitationMatch> = regex
+            .captures_iter(&text)
+            .map(|caps| {
+                let key = caps.get(key_group).unwrap();
+                let mut span = caps.get(0).unwrap().range();
+                if variant == CitationVariant::AuthorInText {
+                    // The match starts with the character before the `@`
+                    span.start = key.start() - 1;
+                }
+                CitationMatch {
+                    key: key.as_str().to_string(),
+                    variant,
+                    span,
+                    key_span: key.range(),
+                }
+            })
+            .collect();
+        for citation in &found {
+            blank(&mut text, citation.span.clone());
+        }
+        citations.extend(found);
+    }
+    citations.sort_by_key(|citation| citation.span.start);
+    malformed.extend(MALFORMED_CITE_REGEX.find_iter(&text).map(|m| m.range()));
+    malformed.sort_by_key(|range| range.start);
+    Scan {
+        citations,
+        malformed,
+    }
+}
+
+/// Blank out fenced and inline code, as `protect_code_blocks` sets it aside.
+fn blank_code_blocks(content: &str) -> String {
+    let mut text = content.to_string();
+    for regex in [&*FENCED_CODE_REGEX, &*INLINE_CODE_REGEX] {
+        let blocks: Vec<Range<usize>> = regex.find_iter(&text).map(|m| m.range()).collect();
+        for range in blocks {
+            blank(&mut text, range);
+        }
+    }
+    text
+}
+
+/// Replace a range of `text` with spaces of the same byte length, keeping line breaks.
+fn blank(text: &mut String, range: Range<usize>) {
+    let blanked: String = text[range.clone()]
+        .chars()
+        .map(|c| match c {
+            '\n' => "\n".to_string(),
+            c => " ".repeat(c.len_utf8()),
+        })
+        .collect();
+    text.replace_range(range, &blanked);
+}
@@ -0,0 +1,592 @@
+//! `mdbook-bib add`: append an entry to a book's bibliography from a DOI, ISBN or arXiv id.
+//!
+//! Metadata comes from DOI content negotiation (as CSL-JSON), Open Library for ISBNs and the
+//! arXiv API. Each service can be replaced by another base URL, such as a local mirror. The
+//! entry's key follows the book's `key-pattern`.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use mdbook_preprocessor::errors::Error;
+use regex::Regex;
+use reqwest::header::{ACCEPT, USER_AGENT};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::parser::{self, BibFormat};
+use crate::renderer;
+use crate::{config, io};
+
+use super::convert::{convert, write_bibtex_entry, TargetFormat};
+use super::render::TAG_REGEX;
+use super::BookProject;
+
+lazy_static! {
+    static ref DOI_REGEX: Regex = Regex::new(r"^10\.\d{4,9}/\S+$").unwrap();
+    // New identifiers (2101.00001v2) and old ones (hep-th/9901001)
+    static ref ARXIV_REGEX: Regex =
+        Regex::new(r"^(?:\d{4}\.\d{4,5}|[a-z\-]+(?:\.[A-Z]{2})?/\d{7})(?:v\d+)?$").unwrap();
+    static ref ISBN_REGEX: Regex = Regex::new(r"^(?:\d{9}[\dX]|\d{13})$").unwrap();
+    static ref YEAR_REGEX: Regex = Regex::new(r"\b\d{4}\b").unwrap();
+}
+
+// Words skipped for the `{title}` field of keys
+const TITLE_STOP_WORDS: &[&str] = &["a", "an", "the", "on", "of", "in", "for", "to", "and"];
+
+/// A work to look up, by one of its identifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identifier {
+    Doi(String),
+    Isbn(String),
+    Arxiv(String),
+}
+
+impl FromStr for Identifier {
+    type Err = Error;
+
+    /// Read a bare identifier, one with a `doi:`, `isbn:` or `arXiv:` prefix, or a doi.org or
+    /// arxiv.org URL.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim();
+        let lowercase = input.to_lowercase();
+        let strip = |prefixes: &[&str]| {
+            prefixes
+                .iter()
+                .find(|prefix| lowercase.starts_with(*prefix))
+                .map(|prefix| &input[prefix.len()..])
+        };
+
+        if let Some(doi) = strip(&[
+            "https://doi.org/",
+            "http://doi.org/",
+            "https://dx.doi.org/",
+            "http://dx.doi.org/",
+            "doi:",
+        ]) {
+            return Ok(Identifier::Doi(doi.to_string()));
+        }
+        if let Some(id) = strip(&["https://arxiv.org/abs/", "http://arxiv.org/abs/", "arxiv:"]) {
+            return Ok(Identifier::Arxiv(id.to_string()));
+        }
+        let isbn: String = strip(&["isbn:"])
+            .unwrap_or(input)
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' '))
+            .collect::<String>()
+            .to_uppercase();
+
+        if DOI_REGEX.is_match(input) {
+            Ok(Identifier::Doi(input.to_string()))
+        } else if ARXIV_REGEX.is_match(input) {
+            Ok(Identifier::Arxiv(input.to_string()))
+        } else if ISBN_REGEX.is_match(&isbn) {
+            Ok(Identifier::Isbn(isbn))
+        } else {
+            Err(anyhow!(
+                "'{input}' is not a DOI, an ISBN or an arXiv identifier"
+            ))
+        }
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Doi(doi) => write!(f, "DOI {doi}"),
+            Identifier::Isbn(isbn) => write!(f, "ISBN {isbn}"),
+            Identifier::Arxiv(id) => write!(f, "arXiv:{id}"),
+        }
+    }
+}
+
+impl Identifier {
+    /// Service queried unless another base URL is given.
+    pub fn default_base_url(&self) -> &'static str {
+        match self {
+            Identifier::Doi(_) => "https://doi.org",
+            Identifier::Isbn(_) => "https://openlibrary.org",
+            Identifier::Arxiv(_) => "https://export.arxiv.org",
+        }
+    }
+}
+
+/// A work as described by a metadata service, ready to be written as BibTeX.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Work {
+    pub entry_type: &'static str,
+    /// Family and given names of the authors; organizations only have a family name
+    pub authors: Vec<(String, String)>,
+    /// BibTeX fields other than the authors
+    pub fields: IndexMap<&'static str, String>,
+}
+
+impl Work {
+    /// The work as a BibTeX entry, with the special characters of its values escaped.
+    pub fn to_bibtex(&self, key: &str) -> String {
+        let mut fields: IndexMap<&str, String> = self
+            .fields
+            .iter()
+            .map(|(&field, value)| {
+                if VERBATIM_FIELDS.contains(&field) {
+                    (field, value.clone())
+                } else {
+                    (field, escape_bibtex(value))
+                }
+            })
+            .collect();
+        if !self.authors.is_empty() {
+            let authors: Vec<String> = self
+                .authors
+                .iter()
+                .map(|(family, given)| (escape_bibtex(family), escape_bibtex(given)))
+                .map(|(family, given)| match given.as_str() {
+                    "" if family.contains(' ') => format!("{{{family}}}"),
+                    "" => family,
+                    given => format!("{family}, {given}"),
+                })
+                .collect();
+            fields.insert("author", authors.join(" and "));
+        }
+        write_bibtex_entry(self.entry_type, key, fields)
+    }
+
+    /// The citation key of the work following `pattern`, with a letter appended when the key
+    /// is already `taken`.
+    pub fn citation_key(&self, pattern: &str, taken: impl Fn(&str) -> bool) -> String {
+        let author = self
+            .authors
+            .first()
+            .map(|(family, _)| key_part(family))
+            .filter(|part| !part.is_empty())
+            .unwrap_or_else(|| "anon".to_string());
+        let year = self
+            .fields
+            .get("year")
+            .cloned()
+            .unwrap_or_else(|| "nd".to_string());
+        let title = self
+            .fields
+            .get("title")
+            .into_iter()
+            .flat_map(|title| title.split_whitespace())
+            .map(key_part)
+            .find(|word| !word.is_empty() && !TITLE_STOP_WORDS.contains(&word.as_str()))
+            .unwrap_or_else(|| "untitled".to_string());
+        let key = pattern
+            .replace("{author}", &author)
+            .replace("{year}", &year)
+            .replace("{title}", &title);
+
+        if !taken(&key) {
+            return key;
+        }
+        ('a'..='z')
+            .map(|suffix| format!("{key}{suffix}"))
+            .chain((2..).map(|n| format!("{key}-{n}")))
+            .find(|candidate| !taken(candidate))
+            .expect("Endless candidates")
+    }
+}
+
+/// Lowercase ASCII letters and digits of a name, accents removed.
+fn key_part(text: &str) -> String {
+    text.nfd()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Text of a metadata value, without markup, line breaks or unbalanced braces.
+fn clean(text: &str) -> String {
+    let text = TAG_REGEX.replace_all(text, "");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut depth: i32 = 0;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            break;
+        }
+    }
+    if depth == 0 {
+        text
+    } else {
+        text.replace(['{', '}'], "")
+    }
+}
+
+/// Fields BibTeX reads verbatim, where a backslash would be kept as is.
+const VERBATIM_FIELDS: &[&str] = &["doi", "eprint", "url"];
+
+/// Escape the characters with a special meaning in BibTeX values. Braces stay balanced, as
+/// `clean` drops unbalanced ones.
+fn escape_bibtex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '%' | '&' | '#' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Family and given names of "Given Family".
+fn split_name(name: &str) -> (String, String) {
+    let name = clean(name);
+    match name.rsplit_once(' ') {
+        Some((given, family)) => (family.to_string(), given.to_string()),
+        None => (name, String::new()),
+    }
+}
+
+/// Look up the metadata of a work, from `base_url` or the identifier's default service.
+pub fn resolve(identifier: &Identifier, base_url: Option<&str>) -> Result<Work, Error> {
+    let base_url = base_url
+        .unwrap_or(identifier.default_base_url())
+        .trim_end_matches('/');
+    match identifier {
+        Identifier::Doi(doi) => {
+            // DOIs may hold `#`, `?` or `;`, which would end the path
+            let path: Vec<String> = doi.split('/').map(renderer::percent_encode).collect();
+            let body = fetch(
+                &format!("{base_url}/{}", path.join("/")),
+                "application/vnd.citationstyles.csl+json",
+            )?;
+            let item: Value = serde_json::from_str(&body)
+                .with_context(|| format!("Invalid CSL-JSON for {identifier}"))?;
+            Ok(work_from_csl_json(&item))
+        }
+        Identifier::Isbn(isbn) => {
+            let body = fetch(
+                &format!("{base_url}/api/books?bibkeys=ISBN:{isbn}&format=json&jscmd=data"),
+                "application/json",
+            )?;
+            let books: Value = serde_json::from_str(&body)
+                .with_context(|| format!("Invalid Open Library answer for {identifier}"))?;
+            let book = books
+                .get(format!("ISBN:{isbn}"))
+                .ok_or_else(|| anyhow!("No book found for {identifier}"))?;
+            Ok(work_from_open_library(book, isbn))
+        }
+        Identifier::Arxiv(id) => {
+            let body = fetch(
+                &format!("{base_url}/api/query?id_list={id}"),
+                "application/atom+xml",
+            )?;
+            let feed: ArxivFeed = quick_xml::de::from_str(&body)
+                .with_context(|| format!("Invalid arXiv answer for {identifier}"))?;
+            let entry = feed
+                .entry
+                .into_iter()
+                .next()
+                .filter(|entry| !entry.id.contains("/api/errors"))
+                .ok_or_else(|| anyhow!("No paper found for {identifier}"))?;
+            Ok(work_from_arxiv(entry, id))
+        }
+    }
+}
+
+fn fetch(url: &str, accept: &str) -> Result<String, Error> {
+    tracing::info!("Fetching {url}");
+    let response = reqwest::blocking::Client::new()
+        .get(url)
+        .header(ACCEPT, accept)
+        .header(
+            USER_AGENT,
+            concat!("mdbook-bib/", env!("CARGO_PKG_VERSION")),
+        )
+        .send()
+        .with_context(|| format!("Failed to reach {url}"))?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Err(anyhow!("Nothing found at {url}")),
+        status if !status.is_success() => Err(anyhow!("{url} answered {status}")),
+        _ => Ok(response.text()?),
+    }
+}
+
+/// Text of a JSON string or number, or of the first element of an array of them.
+fn json_text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(text) => Some(clean(text)).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(values) => json_text(values.first()),
+        _ => None,
+    }
+}
+
+fn work_from_csl_json(item: &Value) -> Work {
+    let csl_type = item.get("type").and_then(Value::as_str).unwrap_or_default();
+    let (entry_type, container_field, publisher_field) = match csl_type {
+        "article-journal" | "article" | "article-magazine" | "article-newspaper" => {
+            ("article", "journal", "publisher")
+        }
+        "paper-conference" => ("inproceedings", "booktitle", "publisher"),
+        "chapter" => ("incollection", "booktitle", "publisher"),
+        "book" | "monograph" => ("book", "series", "publisher"),
+        "report" => ("techreport", "series", "institution"),
+        "thesis" => ("phdthesis", "series", "school"),
+        _ => ("misc", "howpublished", "publisher"),
+    };
+
+    let authors = item
+        .get("author")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|person| {
+            let part = |name: &str| json_text(person.get(name));
+            match (part("family"), part("literal").or_else(|| part("name"))) {
+                (Some(family), _) => Some((family, part("given").unwrap_or_default())),
+                (None, Some(literal)) => Some((literal, String::new())),
+                (None, None) => None,
+            }
+        })
+        .collect();
+
+    let mut fields = IndexMap::new();
+    let year = item
+        .pointer("/issued/date-parts/0/0")
+        .map(|year| json_text(Some(year)));
+    for (field, value) in [
+        ("title", json_text(item.get("title"))),
+        (container_field, json_text(item.get("container-title"))),
+        ("year", year.flatten()),
+        ("volume", json_text(item.get("volume"))),
+        ("number", json_text(item.get("issue"))),
+        ("pages", json_text(item.get("page"))),
+        (publisher_field, json_text(item.get("publisher"))),
+        ("isbn", json_text(item.get("ISBN"))),
+        ("doi", json_text(item.get("DOI"))),
+        ("url", json_text(item.get("URL"))),
+    ] {
+        if let Some(value) = value {
+            fields.insert(field, value);
+        }
+    }
+    Work {
+        entry_type,
+        authors,
+        fields,
+    }
+}
+
+fn work_from_open_library(book: &Value, isbn: &str) -> Work {
+    let authors = book
+        .get("authors")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|author| json_text(author.get("name")))
+        .map(|name| split_name(&name))
+        .collect();
+    let title = match (
+        json_text(book.get("title")),
+        json_text(book.get("subtitle")),
+    ) {
+        (Some(title), Some(subtitle)) => Some(format!("{title}: {subtitle}")),
+        (title, _) => title,
+    };
+    let year = json_text(book.get("publish_date"))
+        .and_then(|date| YEAR_REGEX.find(&date).map(|year| year.as_str().to_string()));
+
+    let mut fields = IndexMap::new();
+    for (field, value) in [
+        ("title", title),
+        ("year", year),
+        ("publisher", json_text(book.pointer("/publishers/0/name"))),
+        ("address", json_text(book.pointer("/publish_places/0/name"))),
+        ("pagetotal", json_text(book.get("number_of_pages"))),
+        ("isbn", Some(isbn.to_string())),
+        ("url", json_text(book.get("url"))),
+    ] {
+        if let Some(value) = value {
+            fields.insert(field, value);
+        }
+    }
+    Work {
+        entry_type: "book",
+        authors,
+        fields,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ArxivFeed {
+    #[serde(default)]
+    entry: Vec<ArxivEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArxivEntry {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    published: String,
+    #[serde(default)]
+    author: Vec<ArxivAuthor>,
+    /// `arxiv:doi`, matched by its local name
+    #[serde(default)]
+    doi: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArxivAuthor {
+    name: String,
+}
+
+fn work_from_arxiv(entry: ArxivEntry, id: &str) -> Work {
+    let mut fields = IndexMap::new();
+    fields.insert("title", clean(&entry.title));
+    if let Some(year) = YEAR_REGEX.find(&entry.published) {
+        fields.insert("year", year.as_str().to_string());
+    }
+    if let Some(doi) = entry.doi {
+        fields.insert("doi", clean(&doi));
+    }
+    fields.insert("eprint", id.to_string());
+    fields.insert("eprinttype", "arxiv".to_string());
+    fields.insert("url", format!("https://arxiv.org/abs/{id}"));
+    Work {
+        entry_type: "misc",
+        authors: entry
+            .author
+            .iter()
+            .map(|author| split_name(&author.name))
+            .collect(),
+        fields,
+    }
+}
+
+/// What to add, from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct AddOptions {
+    /// Root directory of the book
+    pub book_dir: PathBuf,
+    /// DOI, ISBN or arXiv identifier of the work
+    pub identifier: String,
+    /// Citation key instead of one following `key-pattern`
+    pub key: Option<String>,
+    /// Metadata service instead of the identifier's default one
+    pub base_url: Option<String>,
+}
+
+/// An entry appended to the bibliography.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Added {
+    pub key: String,
+    /// Bibliography file, from the book directory
+    pub path: PathBuf,
+    /// The entry as written
+    pub entry: String,
+    /// Fields dropped when the entry was converted to YAML
+    pub lost: Vec<String>,
+}
+
+/// Look up a work and append it to the book's bibliography file.
+///
+/// Works whose DOI is already in the bibliography are refused.
+pub fn add(options: &AddOptions) -> Result<Added, Error> {
+    let identifier: Identifier = options.identifier.parse()?;
+    if let Some(key) = &options.key {
+        config::check_citation_key(key)?;
+    }
+    let project = BookProject::load(&options.book_dir)?;
+    let config = project.config()?;
+    let file = config
+        .bibliography
+        .ok_or_else(|| anyhow!("No bibliography file in [preprocessor.bib] to add entries to"))?;
+    let path = project.display_path(Path::new(file));
+    let full_path = project.ctx.root.join(&path);
+    let format = io::detect_format(&path);
+    let content = if full_path.exists() {
+        fs::read_to_string(&full_path)
+            .with_context(|| format!("Failed to read {}", full_path.display()))?
+    } else {
+        String::new()
+    };
+    let bibliography = if content.trim().is_empty() {
+        IndexMap::new()
+    } else {
+        parser::parse_bibliography(content.clone(), format)?
+    };
+
+    let check_doi = |doi: &str| match bibliography.values().find(|item| {
+        item.doi
+            .as_deref()
+            .is_some_and(|known| known.eq_ignore_ascii_case(doi))
+    }) {
+        Some(item) => Err(anyhow!(
+            "'{}' in {} already has DOI {doi}",
+            item.citation_key,
+            path.display()
+        )),
+        None => Ok(()),
+    };
+    if let Identifier::Doi(doi) = &identifier {
+        check_doi(doi)?;
+    }
+    let work = resolve(&identifier, options.base_url.as_deref())?;
+    if let Some(doi) = work.fields.get("doi") {
+        check_doi(doi)?;
+    }
+
+    let key = match &options.key {
+        Some(key) if bibliography.contains_key(key) => {
+            return Err(anyhow!("'{key}' is already in {}", path.display()));
+        }
+        Some(key) => key.clone(),
+        None => work.citation_key(config.key_pattern, |key| bibliography.contains_key(key)),
+    };
+    let bibtex = work.to_bibtex(&key);
+    let (entry, lost) = match format {
+        BibFormat::BibTeX => (bibtex, Vec::new()),
+        BibFormat::Yaml => {
+            let conversion = convert(bibtex, BibFormat::BibTeX, TargetFormat::Yaml)?;
+            let lost = conversion.lost.iter().map(ToString::to_string).collect();
+            (conversion.output, lost)
+        }
+    };
+
+    let mut output = content;
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    if !output.trim().is_empty() {
+        output.push('\n');
+    }
+    output.push_str(&entry);
+    fs::write(&full_path, output)
+        .with_context(|| format!("Failed to write {}", full_path.display()))?;
+    Ok(Added {
+        key,
+        path,
+        entry,
+        lost,
+    })
+}
+
+/// Add a work to the book's bibliography and print the new entry.
+pub fn run(options: &AddOptions) -> Result<(), Error> {
+    let added = add(options)?;
+    println!("Added '{}' to {}:\n", added.key, added.path.display());
+    print!("{}", added.entry);
+    if !added.lost.is_empty() {
+        eprintln!("{} field(s) lost in conversion:", added.lost.len());
+        for field in &added.lost {
+            eprintln!("  {field}");
+        }
+    }
+    Ok(())
+}
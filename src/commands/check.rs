@@ -0,0 +1,211 @@
+//! `mdbook-bib check`: lint a book's citations without building it.
+//!
+//! Reports citations of unknown keys, malformed citations and entries that are never
+//! cited, with `file:line:column` locations.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use mdbook_preprocessor::book::BookItem;
+use mdbook_preprocessor::errors::Error;
+
+use crate::citation::{
+    find_citations, find_malformed_citations, find_nocite_keys, ChapterSettings,
+};
+use crate::config::{CitationSyntax, Config};
+use crate::models::BibItem;
+
+use super::{line_col, BibliographySource, BookProject};
+
+/// How serious a finding is. Errors make the check fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A finding of the check, located in a chapter, the bibliography or `book.toml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// `file`, `file:line` or `file:line:column`
+    pub location: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {severity}: {}", self.location, self.message)
+    }
+}
+
+/// Check the citations of every chapter against the bibliography.
+pub fn check_book(
+    project: &BookProject,
+    config: &Config,
+    bibliography: &IndexMap<String, BibItem>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut used: HashSet<&str> = HashSet::new();
+    let mut nocite_all = config.nocite.iter().any(|key| key == "*");
+
+    for key in &config.nocite {
+        if key != "*" && !bibliography.contains_key(key) {
+            diagnostics.push(Diagnostic {
+                location: "book.toml".to_string(),
+                severity: Severity::Error,
+                message: format!("unknown citation key '{key}' in nocite"),
+            });
+        }
+        used.insert(key);
+    }
+
+    for item in project.book.iter() {
+        let BookItem::Chapter(chapter) = item else {
+            continue;
+        };
+        let Some(path) = &chapter.path else {
+            continue;
+        };
+        let settings = ChapterSettings::for_chapter(chapter);
+        if settings.off {
+            continue;
+        }
+        let citation_syntax = settings
+            .citation_syntax
+            .as_ref()
+            .unwrap_or(&config.citation_syntax);
+        let path = project.display_path(path);
+        let content = &chapter.content;
+        let location = |offset: usize| {
+            let (line, column) = line_col(content, offset);
+            format!("{}:{line}:{column}", path.display())
+        };
+
+        let cited = find_citations(content, citation_syntax)
+            .into_iter()
+            .map(|citation| citation.key)
+            .chain(find_nocite_keys(content).into_iter().map(|(key, _)| key));
+        for key in cited {
+            if key == "*" {
+                nocite_all = true;
+            } else if let Some((key, _)) = bibliography.get_key_value(&key) {
+                used.insert(key);
+            }
+        }
+        diagnostics.extend(
+            check_content(content, citation_syntax, bibliography)
+                .into_iter()
+                .map(|finding| Diagnostic {
+                    location: location(finding.span.start),
+                    severity: Severity::Error,
+                    message: finding.message,
+                }),
+        );
+    }
+
+    if !nocite_all {
+        diagnostics.extend(unused_entries(project, config, bibliography, &used));
+    }
+    diagnostics
+}
+
+/// An error in a chapter's content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// Byte range of the unknown key or the malformed citation
+    pub span: Range<usize>,
+    pub message: String,
+}
+
+/// Find the citations of unknown keys and the malformed citations of a chapter, in order.
+pub fn check_content(
+    content: &str,
+    citation_syntax: &CitationSyntax,
+    bibliography: &IndexMap<String, BibItem>,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for citation in find_citations(content, citation_syntax) {
+        if !bibliography.contains_key(&citation.key) {
+            findings.push(Finding {
+                span: citation.key_span,
+                message: format!("unknown citation key '{}'", citation.key),
+            });
+        }
+    }
+    for (key, span) in find_nocite_keys(content) {
+        if key != "*" && !bibliography.contains_key(&key) {
+            findings.push(Finding {
+                span,
+                message: format!("unknown citation key '{key}' in nocite"),
+            });
+        }
+    }
+    for span in find_malformed_citations(content, citation_syntax) {
+        let message = format!("malformed citation '{}'", content[span.clone()].trim_end());
+        findings.push(Finding { span, message });
+    }
+    findings.sort_by_key(|finding| finding.span.start);
+    findings
+}
+
+/// Warn about the entries never cited, at their line in the bibliography file.
+fn unused_entries(
+    project: &BookProject,
+    config: &Config,
+    bibliography: &IndexMap<String, BibItem>,
+    used: &HashSet<&str>,
+) -> Vec<Diagnostic> {
+    let source = BibliographySource::load(project, config);
+    let bib_location = source
+        .as_ref()
+        .map(|source| source.path.display().to_string())
+        .unwrap_or_else(|| "bibliography".to_string());
+
+    bibliography
+        .iter()
+        .filter(|(key, _)| !used.contains(key.as_str()))
+        .map(|(key, item)| {
+            let line = source
+                .as_ref()
+                .and_then(|source| {
+                    let span = source.span_of(item)?;
+                    Some(format!(":{}", line_col(&source.content, span.start).0))
+                })
+                .unwrap_or_default();
+            Diagnostic {
+                location: format!("{bib_location}{line}"),
+                severity: Severity::Warning,
+                message: format!("entry '{key}' is never cited"),
+            }
+        })
+        .collect()
+}
+
+/// Check the book in `book_dir`, printing the findings.
+///
+/// Returns whether the check passed: no errors, and no warnings either when `strict`.
+pub fn run(book_dir: &Path, strict: bool) -> Result<bool, Error> {
+    let project = BookProject::load(book_dir)?;
+    let config = project.config()?;
+    let bibliography = project.load_bibliography(&config)?;
+    let diagnostics = check_book(&project, &config, &bibliography);
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    println!("{errors} error(s), {warnings} warning(s)");
+    Ok(errors == 0 && (!strict || warnings == 0))
+}
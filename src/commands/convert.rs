@@ -0,0 +1,765 @@
+//! `mdbook-bib convert`: convert a bibliography between BibTeX, hayagriva YAML and CSL-JSON.
+//!
+//! Entries are read with the same parser as a book build. hayagriva only writes YAML, so
+//! BibTeX and CSL-JSON are written here from its entries. Fields that cannot be carried
+//! over are reported instead of being dropped silently.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use hayagriva::io::to_yaml_str;
+use hayagriva::types::{Date, Person};
+use hayagriva::{Entry, Library};
+use indexmap::IndexMap;
+use mdbook_preprocessor::errors::Error;
+use serde_json::{json, Map, Value};
+
+use crate::file_utils;
+use crate::models::BibItem;
+use crate::parser::{self, BibFormat};
+
+/// Output formats accepted by `--to`.
+pub const FORMATS: &[(&str, TargetFormat)] = &[
+    ("bibtex", TargetFormat::BibTeX),
+    ("yaml", TargetFormat::Yaml),
+    ("csl-json", TargetFormat::CslJson),
+];
+
+// BibTeX fields hayagriva reads, with their aliases. Any other field is not converted.
+const READ_BIBTEX_FIELDS: &[&str] = &[
+    "abstract",
+    "addendum",
+    "address",
+    "afterword",
+    "annotation",
+    "annotator",
+    "archiveprefix",
+    "author",
+    "bookauthor",
+    "booktitle",
+    "chapter",
+    "commentator",
+    "crossref",
+    "date",
+    "doi",
+    "edition",
+    "editor",
+    "eprint",
+    "eprinttype",
+    "eventdate",
+    "eventtitle",
+    "foreword",
+    "holder",
+    "howpublished",
+    "institution",
+    "introduction",
+    "isan",
+    "isbn",
+    "ismn",
+    "issn",
+    "issue",
+    "issuetitle",
+    "iswc",
+    "journal",
+    "journaltitle",
+    "langid",
+    "language",
+    "location",
+    "maintitle",
+    "month",
+    "note",
+    "number",
+    "organization",
+    "pages",
+    "pagetotal",
+    "pmid",
+    "publisher",
+    "school",
+    "series",
+    "shorttitle",
+    "title",
+    "translator",
+    "type",
+    "url",
+    "urldate",
+    "venue",
+    "version",
+    "volume",
+    "volumes",
+    "year",
+];
+
+// Fields of a parent entry (journal, proceedings, book...) that flat formats keep on the
+// entry itself
+const MERGED_PARENT_FIELDS: &[&str] = &[
+    "date",
+    "editor",
+    "publisher",
+    "location",
+    "organization",
+    "issue",
+    "volume",
+    "volume-total",
+    "edition",
+    "serial-number",
+    "language",
+];
+
+// Order of the fields in written BibTeX entries
+const BIBTEX_FIELD_ORDER: &[&str] = &[
+    "author",
+    "editor",
+    "title",
+    "journal",
+    "booktitle",
+    "series",
+    "howpublished",
+    "year",
+    "month",
+    "date",
+    "volume",
+    "volumes",
+    "number",
+    "chapter",
+    "pages",
+    "pagetotal",
+    "edition",
+    "publisher",
+    "address",
+    "school",
+    "institution",
+    "organization",
+    "type",
+    "language",
+    "isbn",
+    "issn",
+    "doi",
+    "pmid",
+    "eprint",
+    "eprinttype",
+    "url",
+    "urldate",
+    "note",
+    "abstract",
+];
+
+/// Format a bibliography is converted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetFormat {
+    BibTeX,
+    Yaml,
+    CslJson,
+}
+
+impl TargetFormat {
+    /// Format of an output file, from its extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = file_utils::get_filename_extension(path)?.to_lowercase();
+        match ext.as_str() {
+            "bib" | "bibtex" => Some(TargetFormat::BibTeX),
+            "yaml" | "yml" => Some(TargetFormat::Yaml),
+            "json" => Some(TargetFormat::CslJson),
+            _ => None,
+        }
+    }
+
+    /// The format a bibliography is read in.
+    pub fn from_bib_format(format: BibFormat) -> Self {
+        match format {
+            BibFormat::BibTeX => TargetFormat::BibTeX,
+            BibFormat::Yaml => TargetFormat::Yaml,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            TargetFormat::BibTeX => "BibTeX",
+            TargetFormat::Yaml => "hayagriva YAML",
+            TargetFormat::CslJson => "CSL-JSON",
+        }
+    }
+}
+
+/// A field of an entry that did not make it into the converted bibliography.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LostField {
+    /// Citation key of the entry.
+    pub key: String,
+    /// Field name, in the input format when it could not be read, in hayagriva's
+    /// otherwise (`parent.` for fields of the parent entry).
+    pub field: String,
+    /// Why the field was lost.
+    pub reason: String,
+}
+
+impl fmt::Display for LostField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: '{}' {}", self.key, self.field, self.reason)
+    }
+}
+
+/// A converted bibliography and the fields lost on the way.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub output: String,
+    pub lost: Vec<LostField>,
+}
+
+impl Conversion {
+    /// Write the output to a file or standard output, and list the lost fields on
+    /// standard error.
+    pub fn write(&self, output: Option<&Path>) -> Result<(), Error> {
+        match output {
+            Some(path) => fs::write(path, &self.output)
+                .with_context(|| format!("Failed to write {}", path.display()))?,
+            None => print!("{}", self.output),
+        }
+        if !self.lost.is_empty() {
+            eprintln!("{} field(s) lost in conversion:", self.lost.len());
+            for field in &self.lost {
+                eprintln!("  {field}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert bibliography `content` from one format to another.
+pub fn convert(content: String, from: BibFormat, to: TargetFormat) -> Result<Conversion, Error> {
+    let bibliography = parser::parse_bibliography(content, from)?;
+    convert_entries(bibliography.values(), from, to)
+}
+
+/// Convert parsed entries, read from a bibliography in the `from` format.
+pub fn convert_entries<'a>(
+    items: impl IntoIterator<Item = &'a BibItem>,
+    from: BibFormat,
+    to: TargetFormat,
+) -> Result<Conversion, Error> {
+    let items: Vec<&BibItem> = items.into_iter().collect();
+    let mut lost = unread_fields(&items, from)?;
+
+    let entries: Vec<&Entry> = items
+        .iter()
+        .filter_map(|item| item.hayagriva_entry.as_deref())
+        .collect();
+    let output = match to {
+        TargetFormat::Yaml => {
+            let mut library = Library::new();
+            for entry in entries {
+                library.push(entry);
+            }
+            to_yaml_str(&library).context("Failed to write YAML bibliography")?
+        }
+        TargetFormat::BibTeX => {
+            let mut output = String::new();
+            for entry in entries {
+                output.push_str(&to_bibtex(&flatten(entry, to, &mut lost)?, &mut lost)?);
+            }
+            output
+        }
+        TargetFormat::CslJson => {
+            let items = csl_json_items(entries, &mut lost)?;
+            format!("{}\n", serde_json::to_string_pretty(&items)?)
+        }
+    };
+    Ok(Conversion { output, lost })
+}
+
+/// Fields of the entries' sources that hayagriva does not read.
+fn unread_fields(items: &[&BibItem], format: BibFormat) -> Result<Vec<LostField>, Error> {
+    let mut lost = Vec::new();
+    for item in items {
+        let source = item.raw_source.as_deref().unwrap_or_default();
+        let (fields, format_name) = match format {
+            BibFormat::BibTeX => {
+                let fields = bibtex_field_names(source)
+                    .into_iter()
+                    .filter(|field| !READ_BIBTEX_FIELDS.contains(&field.as_str()))
+                    .collect::<Vec<_>>();
+                (fields, "BibTeX")
+            }
+            BibFormat::Yaml => {
+                // hayagriva ignores the keys it does not know, so they are missing once read
+                let read = match &item.hayagriva_entry {
+                    Some(entry) => serde_json::to_value(entry.as_ref())?,
+                    None => Value::Null,
+                };
+                let fields = yaml_field_names(source)
+                    .into_iter()
+                    .filter(|field| {
+                        read.get(field).is_none()
+                            && !(field == "serial" && read.get("serial-number").is_some())
+                    })
+                    .collect::<Vec<_>>();
+                (fields, "YAML")
+            }
+        };
+        lost.extend(fields.into_iter().map(|field| LostField {
+            key: item.citation_key.clone(),
+            field,
+            reason: format!("is not read from {format_name}"),
+        }));
+    }
+    Ok(lost)
+}
+
+/// Names of the fields in the source of a BibTeX entry, lowercased.
+fn bibtex_field_names(source: &str) -> Vec<String> {
+    // Fields start after the citation key
+    let Some(key_end) = source.find(',') else {
+        return Vec::new();
+    };
+    let body = &source[key_end + 1..];
+    let mut names = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut field_start = 0;
+    for (idx, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => quoted = !quoted,
+            ',' if depth == 0 && !quoted => field_start = idx + 1,
+            '=' if depth == 0 && !quoted => {
+                names.push(body[field_start..idx].trim().to_lowercase());
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+/// Keys of the fields in the source of a hayagriva YAML entry.
+fn yaml_field_names(source: &str) -> Vec<String> {
+    let mut lines = source.lines().skip(1).filter(|line| {
+        let line = line.trim_start();
+        !line.is_empty() && !line.starts_with('#')
+    });
+    let Some(first) = lines.next() else {
+        return Vec::new();
+    };
+    let indent = first.len() - first.trim_start().len();
+    std::iter::once(first)
+        .chain(lines)
+        .filter(|line| line.len() - line.trim_start().len() == indent)
+        .filter_map(|line| line.trim_start().split_once(':'))
+        .map(|(field, _)| {
+            field
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+        .collect()
+}
+
+/// An entry with its parent's fields merged in, as flat formats expect them.
+struct FlatEntry {
+    key: String,
+    /// hayagriva entry type
+    kind: String,
+    parent_kind: Option<String>,
+    /// The entry's fields, then those of its parent it does not have
+    fields: Map<String, Value>,
+    /// Title of the parent (journal, proceedings, book...)
+    container_title: Option<Value>,
+    /// Title of the parent's parent (book series...)
+    collection_title: Option<Value>,
+}
+
+fn flatten(entry: &Entry, to: TargetFormat, lost: &mut Vec<LostField>) -> Result<FlatEntry, Error> {
+    let key = entry.key().to_string();
+    let mut lose = |field: String| {
+        lost.push(LostField {
+            key: key.clone(),
+            field,
+            reason: format!("has no {} equivalent", to.name()),
+        })
+    };
+
+    let Value::Object(mut fields) = serde_json::to_value(entry)? else {
+        return Err(anyhow!("Entry {key} is not serialized as a map"));
+    };
+    let kind = text(&fields.remove("type").unwrap_or_default());
+    let mut parents = one_or_many(fields.remove("parent"));
+    if parents.len() > 1 {
+        parents.drain(1..).for_each(|_| lose("parent".to_string()));
+    }
+
+    let mut flat = FlatEntry {
+        key: key.clone(),
+        kind,
+        parent_kind: None,
+        fields,
+        container_title: None,
+        collection_title: None,
+    };
+    let Some(Value::Object(mut parent)) = parents.pop() else {
+        return Ok(flat);
+    };
+    flat.parent_kind = parent.remove("type").map(|kind| text(&kind));
+    flat.container_title = parent.remove("title");
+    for grandparent in one_or_many(parent.remove("parent")) {
+        let Value::Object(mut grandparent) = grandparent else {
+            continue;
+        };
+        match grandparent.remove("title") {
+            Some(title) if flat.collection_title.is_none() => flat.collection_title = Some(title),
+            Some(_) => lose("parent.parent.title".to_string()),
+            None => {}
+        }
+        grandparent.remove("type");
+        for field in grandparent.keys() {
+            lose(format!("parent.parent.{field}"));
+        }
+    }
+    for (field, value) in parent {
+        if MERGED_PARENT_FIELDS.contains(&field.as_str()) && !flat.fields.contains_key(&field) {
+            flat.fields.insert(field, value);
+        } else {
+            lose(format!("parent.{field}"));
+        }
+    }
+    Ok(flat)
+}
+
+/// Write an entry as BibTeX, with the fields biblatex also understands.
+fn to_bibtex(entry: &FlatEntry, lost: &mut Vec<LostField>) -> Result<String, Error> {
+    let parent_kind = entry.parent_kind.as_deref();
+    let genre = entry.fields.get("genre").map(text).unwrap_or_default();
+    let (entry_type, container_field) = match (entry.kind.as_str(), parent_kind) {
+        ("article", Some("proceedings" | "conference")) => ("inproceedings", "booktitle"),
+        ("article", _) => ("article", "journal"),
+        ("chapter" | "anthos", _) => ("incollection", "booktitle"),
+        ("book" | "anthology", _) => ("book", "series"),
+        ("proceedings", _) => ("proceedings", "series"),
+        ("thesis", _) if genre.to_lowercase().contains("master") => ("mastersthesis", "series"),
+        ("thesis", _) => ("phdthesis", "series"),
+        ("report", _) => ("techreport", "series"),
+        ("manuscript", _) => ("unpublished", "howpublished"),
+        _ => ("misc", "howpublished"),
+    };
+
+    let mut fields: IndexMap<&str, String> = IndexMap::new();
+    let mut unmapped = Vec::new();
+    if let Some(title) = &entry.container_title {
+        fields.insert(container_field, text(title));
+    }
+    if let Some(title) = &entry.collection_title {
+        if fields.contains_key("series") {
+            unmapped.push("parent.parent.title".to_string());
+        } else {
+            fields.insert("series", text(title));
+        }
+    }
+    for (field, value) in &entry.fields {
+        let mut set = |name, value| {
+            fields.entry(name).or_insert(value);
+        };
+        match field.as_str() {
+            "author" => set("author", bibtex_names(value)?),
+            "editor" => set("editor", bibtex_names(value)?),
+            "date" => {
+                let date: Date = serde_json::from_value(value.clone())?;
+                set("year", date.year.to_string());
+                if let Some(month) = date.month {
+                    set("month", (month + 1).to_string());
+                }
+                if date.day.is_some() {
+                    set("date", text(value));
+                }
+            }
+            "publisher" => match value {
+                Value::Object(publisher) => {
+                    if let Some(name) = publisher.get("name").filter(|name| !name.is_null()) {
+                        set("publisher", text(name));
+                    }
+                    if let Some(location) = publisher.get("location") {
+                        set("address", text(location));
+                    }
+                }
+                _ => set("publisher", text(value)),
+            },
+            "organization" => match entry.kind.as_str() {
+                "thesis" => set("school", text(value)),
+                "report" => set("institution", text(value)),
+                _ => set("organization", text(value)),
+            },
+            "url" => match value {
+                Value::Object(url) => {
+                    if let Some(link) = url.get("value") {
+                        set("url", text(link));
+                    }
+                    if let Some(date) = url.get("date") {
+                        set("urldate", text(date));
+                    }
+                }
+                _ => set("url", text(value)),
+            },
+            "serial-number" => {
+                for (kind, number) in value.as_object().into_iter().flatten() {
+                    match kind.as_str() {
+                        "doi" | "isbn" | "issn" | "pmid" => set(kind.as_str(), text(number)),
+                        "arxiv" => {
+                            set("eprint", text(number));
+                            set("eprinttype", "arxiv".to_string());
+                        }
+                        "serial" => set("number", text(number)),
+                        _ => unmapped.push(format!("serial-number.{kind}")),
+                    }
+                }
+            }
+            "title" | "volume" | "edition" | "chapter" | "note" | "abstract" | "language" => {
+                set(field.as_str(), text(value))
+            }
+            "location" => set("address", text(value)),
+            "issue" => set("number", text(value)),
+            "volume-total" => set("volumes", text(value)),
+            "page-range" => set("pages", text(value)),
+            "page-total" => set("pagetotal", text(value)),
+            "genre" => set("type", text(value)),
+            _ => unmapped.push(field.clone()),
+        }
+    }
+    lost.extend(unmapped.into_iter().map(|field| LostField {
+        key: entry.key.clone(),
+        field,
+        reason: format!("has no {} equivalent", TargetFormat::BibTeX.name()),
+    }));
+
+    Ok(format!(
+        "{}\n",
+        write_bibtex_entry(entry_type, &entry.key, fields)
+    ))
+}
+
+/// Write a BibTeX entry, its fields in the usual order.
+pub(crate) fn write_bibtex_entry(
+    entry_type: &str,
+    key: &str,
+    mut fields: IndexMap<&str, String>,
+) -> String {
+    fields.sort_by_key(|name, _| BIBTEX_FIELD_ORDER.iter().position(|field| field == name));
+    let mut output = format!("@{entry_type}{{{key},\n");
+    for (name, value) in fields {
+        output.push_str(&format!("  {name} = {{{value}}},\n"));
+    }
+    output.push_str("}\n");
+    output
+}
+
+/// Write entries as CSL-JSON items, parents flattened into their children.
+pub(crate) fn csl_json_items<'a>(
+    entries: impl IntoIterator<Item = &'a Entry>,
+    lost: &mut Vec<LostField>,
+) -> Result<Vec<Value>, Error> {
+    entries
+        .into_iter()
+        .map(|entry| to_csl_json(&flatten(entry, TargetFormat::CslJson, lost)?, lost))
+        .collect()
+}
+
+/// Write an entry as a CSL-JSON item.
+fn to_csl_json(entry: &FlatEntry, lost: &mut Vec<LostField>) -> Result<Value, Error> {
+    let parent_kind = entry.parent_kind.as_deref();
+    let csl_type = match (entry.kind.as_str(), parent_kind) {
+        ("article", Some("proceedings" | "conference")) => "paper-conference",
+        ("article", Some("newspaper")) => "article-newspaper",
+        ("article", Some("periodical")) => "article-journal",
+        ("article", _) => "article",
+        ("chapter" | "anthos", _) => "chapter",
+        ("book" | "anthology" | "proceedings", _) => "book",
+        ("thesis", _) => "thesis",
+        ("report", _) => "report",
+        ("manuscript", _) => "manuscript",
+        ("web", _) => "webpage",
+        ("blog", _) => "post-weblog",
+        ("thread", _) => "post",
+        ("patent", _) => "patent",
+        ("legislation", _) => "legislation",
+        ("case", _) => "legal_case",
+        ("reference", _) => "entry-encyclopedia",
+        ("video", _) => "motion_picture",
+        ("audio", _) => "song",
+        ("performance", _) => "performance",
+        ("artwork", _) => "graphic",
+        ("map", _) => "map",
+        ("repository", _) => "software",
+        ("periodical", _) => "periodical",
+        _ => "document",
+    };
+
+    let mut item = Map::new();
+    let mut unmapped = Vec::new();
+    item.insert("id".to_string(), json!(entry.key));
+    item.insert("type".to_string(), json!(csl_type));
+    if let Some(title) = &entry.container_title {
+        item.insert("container-title".to_string(), json!(text(title)));
+    }
+    if let Some(title) = &entry.collection_title {
+        item.insert("collection-title".to_string(), json!(text(title)));
+    }
+    for (field, value) in &entry.fields {
+        let set = |item: &mut Map<String, Value>, name: &str, value: Value| {
+            item.entry(name).or_insert(value);
+        };
+        match field.as_str() {
+            "author" | "editor" => set(&mut item, field.as_str(), csl_names(value)?),
+            "date" => set(&mut item, "issued", csl_date(value)?),
+            "publisher" => match value {
+                Value::Object(publisher) => {
+                    if let Some(name) = publisher.get("name").filter(|name| !name.is_null()) {
+                        set(&mut item, "publisher", json!(text(name)));
+                    }
+                    if let Some(location) = publisher.get("location") {
+                        set(&mut item, "publisher-place", json!(text(location)));
+                    }
+                }
+                _ => set(&mut item, "publisher", json!(text(value))),
+            },
+            "url" => match value {
+                Value::Object(url) => {
+                    if let Some(link) = url.get("value") {
+                        set(&mut item, "URL", json!(text(link)));
+                    }
+                    if let Some(date) = url.get("date") {
+                        set(&mut item, "accessed", csl_date(date)?);
+                    }
+                }
+                _ => set(&mut item, "URL", json!(text(value))),
+            },
+            "serial-number" => {
+                for (kind, number) in value.as_object().into_iter().flatten() {
+                    match kind.as_str() {
+                        "doi" | "isbn" | "issn" | "pmid" | "pmcid" => {
+                            set(&mut item, &kind.to_uppercase(), json!(text(number)))
+                        }
+                        "serial" => set(&mut item, "number", json!(text(number))),
+                        _ => unmapped.push(format!("serial-number.{kind}")),
+                    }
+                }
+            }
+            "organization" if !item.contains_key("publisher") => {
+                set(&mut item, "publisher", json!(text(value)))
+            }
+            "title" | "volume" | "issue" | "edition" | "language" | "archive" | "note"
+            | "abstract" | "genre" | "call-number" => {
+                set(&mut item, field.as_str(), json!(text(value)))
+            }
+            "location" => set(&mut item, "publisher-place", json!(text(value))),
+            "archive-location" => set(&mut item, "archive_location", json!(text(value))),
+            "chapter" => set(&mut item, "chapter-number", json!(text(value))),
+            "volume-total" => set(&mut item, "number-of-volumes", json!(text(value))),
+            "page-range" => set(&mut item, "page", json!(text(value))),
+            "page-total" => set(&mut item, "number-of-pages", json!(text(value))),
+            _ => unmapped.push(field.clone()),
+        }
+    }
+    lost.extend(unmapped.into_iter().map(|field| LostField {
+        key: entry.key.clone(),
+        field,
+        reason: format!("has no {} equivalent", TargetFormat::CslJson.name()),
+    }));
+    Ok(Value::Object(item))
+}
+
+/// A value that may be a single item or a list of items.
+fn one_or_many(value: Option<Value>) -> Vec<Value> {
+    match value {
+        Some(Value::Array(values)) => values,
+        Some(value) => vec![value],
+        None => Vec::new(),
+    }
+}
+
+/// Text of a serialized field, which may be a string, a number or a `value` map.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Object(map) if map.contains_key("value") => text(&map["value"]),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn persons(value: &Value) -> Result<Vec<Person>, Error> {
+    one_or_many(Some(value.clone()))
+        .into_iter()
+        .map(|person| Ok(serde_json::from_value(person)?))
+        .collect()
+}
+
+/// Names in BibTeX's `von Last, Jr, First` form, joined with `and`.
+fn bibtex_names(value: &Value) -> Result<String, Error> {
+    let names: Vec<String> = persons(value)?
+        .into_iter()
+        .map(|person| {
+            let mut family = match &person.prefix {
+                Some(prefix) => format!("{prefix} {}", person.name),
+                None => person.name.clone(),
+            };
+            if person.given_name.is_none() && family.contains(' ') {
+                // A single name with spaces, such as an organization, is kept whole
+                family = format!("{{{family}}}");
+            }
+            [Some(family), person.suffix, person.given_name]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .collect();
+    Ok(names.join(" and "))
+}
+
+fn csl_names(value: &Value) -> Result<Value, Error> {
+    let names = persons(value)?
+        .into_iter()
+        .map(|person| {
+            let Some(given) = person.given_name else {
+                return json!({ "literal": person.name });
+            };
+            let mut name = Map::new();
+            name.insert("family".to_string(), json!(person.name));
+            name.insert("given".to_string(), json!(given));
+            if let Some(prefix) = person.prefix {
+                name.insert("non-dropping-particle".to_string(), json!(prefix));
+            }
+            if let Some(suffix) = person.suffix {
+                name.insert("suffix".to_string(), json!(suffix));
+            }
+            Value::Object(name)
+        })
+        .collect();
+    Ok(Value::Array(names))
+}
+
+fn csl_date(value: &Value) -> Result<Value, Error> {
+    let date: Date = serde_json::from_value(value.clone())?;
+    let parts: Vec<i32> = [Some(date.year)]
+        .into_iter()
+        .chain([date.month, date.day].map(|part| part.map(|part| i32::from(part) + 1)))
+        .map_while(|part| part)
+        .collect();
+    Ok(json!({ "date-parts": [parts] }))
+}
+
+/// Convert the bibliography at `input`, writing it to `output` or standard output, and
+/// list the lost fields on standard error.
+///
+/// Without `to`, the format comes from the output's extension, or is YAML for a BibTeX
+/// input and BibTeX for a YAML one.
+pub fn run(input: &Path, to: Option<TargetFormat>, output: Option<&Path>) -> Result<(), Error> {
+    let from = crate::io::detect_format(input);
+    let to = to
+        .or_else(|| output.and_then(TargetFormat::from_path))
+        .unwrap_or(match from {
+            BibFormat::BibTeX => TargetFormat::Yaml,
+            BibFormat::Yaml => TargetFormat::BibTeX,
+        });
+    let content =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    convert(content, from, to)?.write(output)
+}
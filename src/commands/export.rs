@@ -0,0 +1,168 @@
+//! `mdbook-bib export`: write the entries a book cites as a standalone bibliography.
+//!
+//! Citations are found the way a build finds them, over the whole book or a few of its
+//! chapters, so the subset is the one their bibliography would list.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use mdbook_preprocessor::book::{Book, BookItem, Chapter};
+use mdbook_preprocessor::errors::Error;
+use regex::Regex;
+
+use crate::models::BibItem;
+use crate::parser::{self, BibFormat};
+
+use super::convert::{convert_entries, Conversion, TargetFormat};
+use super::{collect_citations, BookProject};
+
+lazy_static! {
+    // `crossref = {parent}` or `xdata = {a, b}` field of a BibTeX entry
+    static ref CROSSREF_REGEX: Regex =
+        Regex::new(r#"(?i)\b(?:crossref|xdata)\s*=\s*[{"]([^}"]*)[}"]"#).unwrap();
+}
+
+/// What to export, from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Root directory of the book
+    pub book_dir: PathBuf,
+    /// Chapters whose citations are exported, as listed in `SUMMARY.md` or from the book
+    /// directory; the whole book when empty
+    pub chapters: Vec<PathBuf>,
+    /// Output format; from the output file, or the bibliography's own format, when not given
+    pub to: Option<TargetFormat>,
+    /// File to write instead of standard output
+    pub output: Option<PathBuf>,
+    /// Order the entries by first citation instead of as in the bibliography
+    pub by_citation: bool,
+}
+
+/// Export the entries cited (or listed with `nocite`) in the selected chapters.
+///
+/// In the bibliography's own format, entries are copied as written, along with the `@string`
+/// and `@preamble` blocks and the `crossref` parents they may depend on. Otherwise they are
+/// converted as `mdbook-bib convert` would, reporting the fields lost.
+pub fn export(project: &BookProject, options: &ExportOptions) -> Result<Conversion, Error> {
+    let config = project.config()?;
+    let (content, format) = project.read_bibliography_source(&config)?;
+    let mut bibliography = parser::parse_bibliography(content.clone(), format)?;
+    let mut book = select_chapters(project, &options.chapters)?;
+    let citations = collect_citations(&mut book, &config, &mut bibliography);
+
+    let mut cited: Vec<&BibItem> = bibliography
+        .values()
+        .filter(|item| citations.all_cited.contains(&item.citation_key))
+        .collect();
+    if options.by_citation {
+        cited.sort_by_key(|item| item.index);
+    }
+
+    let source_format = TargetFormat::from_bib_format(format);
+    let to = options
+        .to
+        .or_else(|| options.output.as_deref().and_then(TargetFormat::from_path))
+        .unwrap_or(source_format);
+    if to == source_format && cited.iter().all(|item| item.raw_source.is_some()) {
+        // Entries are copied as written, so nothing is lost
+        let definitions = match format {
+            BibFormat::BibTeX => parser::bibtex_definitions(&content),
+            BibFormat::Yaml => Vec::new(),
+        };
+        let entries: Vec<String> = definitions
+            .into_iter()
+            .chain(
+                with_crossref_parents(cited, &bibliography)
+                    .into_iter()
+                    .filter_map(|item| item.raw_source.as_deref()),
+            )
+            .map(|source| format!("{}\n", source.trim()))
+            .collect();
+        return Ok(Conversion {
+            output: entries.join("\n"),
+            lost: Vec::new(),
+        });
+    }
+    convert_entries(cited, format, to)
+}
+
+/// The entries followed by the `crossref` and `xdata` entries they depend on, which BibTeX
+/// expects after them.
+fn with_crossref_parents<'a>(
+    mut entries: Vec<&'a BibItem>,
+    bibliography: &'a IndexMap<String, BibItem>,
+) -> Vec<&'a BibItem> {
+    let mut listed: HashSet<&str> = entries
+        .iter()
+        .map(|item| item.citation_key.as_str())
+        .collect();
+    let mut next = 0;
+    while let Some(item) = entries.get(next) {
+        let source = item.raw_source.as_deref().unwrap_or_default();
+        for caps in CROSSREF_REGEX.captures_iter(source) {
+            for key in caps[1].split(',').map(str::trim) {
+                if let Some((key, parent)) = bibliography.get_key_value(key) {
+                    if listed.insert(key) {
+                        entries.push(parent);
+                    }
+                }
+            }
+        }
+        next += 1;
+    }
+    entries
+}
+
+/// The selected chapters, in book order, or the whole book when none are selected.
+fn select_chapters(project: &BookProject, selected: &[PathBuf]) -> Result<Book, Error> {
+    if selected.is_empty() {
+        return Ok(project.book.clone());
+    }
+    // A chapter is selected by its path in `SUMMARY.md` or from the book directory
+    let is_chapter = |selection: &PathBuf, chapter: &Chapter| {
+        chapter.path.as_ref().is_some_and(|path| {
+            let path = project.display_path(path);
+            project.display_path(selection) == path || selection.components().eq(path.components())
+        })
+    };
+    let chapters: Vec<&Chapter> = project
+        .book
+        .iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => Some(chapter),
+            _ => None,
+        })
+        .filter(|chapter| {
+            selected
+                .iter()
+                .any(|selection| is_chapter(selection, chapter))
+        })
+        .collect();
+    if let Some(missing) = selected.iter().find(|selection| {
+        !chapters
+            .iter()
+            .any(|chapter| is_chapter(selection, chapter))
+    }) {
+        return Err(anyhow!("No chapter {} in SUMMARY.md", missing.display()));
+    }
+
+    let items = chapters
+        .into_iter()
+        .map(|chapter| {
+            // Sub-chapters are only exported when selected themselves
+            let mut chapter = chapter.clone();
+            chapter.sub_items.clear();
+            BookItem::Chapter(chapter)
+        })
+        .collect();
+    Ok(Book::new_with_items(items))
+}
+
+/// Export the cited entries of the book.
+pub fn run(options: &ExportOptions) -> Result<(), Error> {
+    let project = BookProject::load(&options.book_dir)?;
+    export(&project, options)?.write(options.output.as_deref())
+}
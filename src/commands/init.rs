@@ -0,0 +1,219 @@
+//! `mdbook-bib init`: set up a book to cite from a bibliography.
+//!
+//! Adds a `[preprocessor.bib]` section to `book.toml`, creates a starter bibliography and,
+//! on request, copies out the built-in templates so they can be customized. Existing files
+//! are never overwritten, and a book already configured is left alone.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use mdbook_preprocessor::config::Config as BookConfig;
+use mdbook_preprocessor::errors::Error;
+use toml::Value;
+
+use crate::backend::CslBackend;
+use crate::config::{
+    DEFAULT_CITE_HB_TEMPLATE, DEFAULT_CSS_TEMPLATE, DEFAULT_HB_TEMPLATE, DEFAULT_JS_TEMPLATE,
+};
+use crate::io;
+use crate::parser::BibFormat;
+
+/// Backends accepted by `--backend`.
+pub const BACKENDS: &[&str] = &["custom", "csl"];
+/// Citation syntaxes accepted by `--syntax`.
+pub const SYNTAXES: &[&str] = &["default", "pandoc"];
+
+/// Built-in templates copied out with `--templates`: configuration key, file name and content.
+const TEMPLATES: &[(&str, &str, &str)] = &[
+    ("hb-tpl", "references.hbs", DEFAULT_HB_TEMPLATE),
+    ("cite-hb-tpl", "cite_key.hbs", DEFAULT_CITE_HB_TEMPLATE),
+    ("css", "satancisco.css", DEFAULT_CSS_TEMPLATE),
+    ("js", "copy2clipboard.js", DEFAULT_JS_TEMPLATE),
+];
+
+/// Directory of the copied templates, in the book's source directory.
+const TEMPLATE_DIR: &str = "render";
+
+const STARTER_BIBTEX: &str = "@book{knuth1984,
+    author = {Knuth, Donald E.},
+    title = {The TeXbook},
+    publisher = {Addison-Wesley},
+    year = {1984},
+}
+";
+
+const STARTER_YAML: &str = "knuth1984:
+  type: book
+  title: The TeXbook
+  author: Knuth, Donald E.
+  publisher: Addison-Wesley
+  date: 1984
+";
+
+/// How to set up the book, from the command line.
+#[derive(Debug, Clone)]
+pub struct InitOptions {
+    /// Root directory of the book, holding `book.toml`
+    pub book_dir: PathBuf,
+    /// Bibliography to create, from the book's source directory; YAML for `.yaml` files
+    pub bibliography: PathBuf,
+    /// One of `BACKENDS`; `csl` when a style is given
+    pub backend: Option<String>,
+    /// Bundled CSL style of the `csl` backend
+    pub csl_style: Option<String>,
+    /// One of `SYNTAXES`
+    pub citation_syntax: String,
+    /// Copy the built-in templates, style sheet and script to customize them
+    pub templates: bool,
+}
+
+impl Default for InitOptions {
+    fn default() -> Self {
+        InitOptions {
+            book_dir: PathBuf::from("."),
+            bibliography: PathBuf::from("references.bib"),
+            backend: None,
+            csl_style: None,
+            citation_syntax: "default".to_string(),
+            templates: false,
+        }
+    }
+}
+
+/// What `init` did with a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Created,
+    /// `book.toml` got its `[preprocessor.bib]` section
+    Configured,
+    /// The file (or the section of `book.toml`) already existed and was left as is
+    Kept,
+}
+
+/// Set up the book in `options.book_dir`, returning each file touched with what was done,
+/// with paths from the book directory.
+///
+/// A `book.toml` that already has a `[preprocessor.bib]` section is reported as kept, and
+/// nothing is created.
+pub fn init(options: &InitOptions) -> Result<Vec<(PathBuf, Outcome)>, Error> {
+    let book_toml = options.book_dir.join("book.toml");
+    if !book_toml.exists() {
+        return Err(anyhow!(
+            "No book.toml in {}. Create the book with `mdbook init` first",
+            options.book_dir.display()
+        ));
+    }
+    let book_config = BookConfig::from_disk(&book_toml)
+        .with_context(|| format!("Failed to read {}", book_toml.display()))?;
+    let src = book_config.book.src.clone();
+
+    let backend = match (options.backend.as_deref(), &options.csl_style) {
+        (Some("custom"), Some(_)) => {
+            return Err(anyhow!("A CSL style needs the csl backend"));
+        }
+        (Some(backend), _) => backend,
+        (None, Some(_)) => "csl",
+        (None, None) => "custom",
+    };
+    let csl_style = (backend == "csl").then(|| options.csl_style.as_deref().unwrap_or("apa"));
+    if let Some(style) = csl_style {
+        CslBackend::new(style.to_string()).with_context(|| {
+            format!("Unknown CSL style '{style}'. See `mdbook-bib styles` for the bundled ones")
+        })?;
+    }
+
+    if book_config
+        .get::<toml::value::Table>("preprocessor.bib")?
+        .is_some()
+    {
+        // The files would not be the ones the existing section points to
+        return Ok(vec![(PathBuf::from("book.toml"), Outcome::Kept)]);
+    }
+
+    let mut files = Vec::new();
+    let starter = match io::detect_format(&options.bibliography) {
+        BibFormat::BibTeX => STARTER_BIBTEX,
+        BibFormat::Yaml => STARTER_YAML,
+    };
+    files.push(create_file(
+        &options.book_dir,
+        &src.join(&options.bibliography),
+        starter,
+    )?);
+
+    let mut section = vec![
+        ("bibliography", toml_path(&options.bibliography)?),
+        ("backend", Value::String(backend.to_string())),
+    ];
+    if let Some(style) = csl_style {
+        section.push(("csl-style", Value::String(style.to_string())));
+    }
+    section.push((
+        "citation-syntax",
+        Value::String(options.citation_syntax.clone()),
+    ));
+    if options.templates {
+        for (key, name, content) in TEMPLATES {
+            let path = Path::new(TEMPLATE_DIR).join(name);
+            files.push(create_file(&options.book_dir, &src.join(&path), content)?);
+            section.push((key, toml_path(&path)?));
+        }
+    }
+
+    let mut content = fs::read_to_string(&book_toml)
+        .with_context(|| format!("Failed to read {}", book_toml.display()))?;
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str("\n[preprocessor.bib]\n");
+    for (key, value) in section {
+        content.push_str(&format!("{key} = {value}\n"));
+    }
+    fs::write(&book_toml, content)
+        .with_context(|| format!("Failed to write {}", book_toml.display()))?;
+    files.push((PathBuf::from("book.toml"), Outcome::Configured));
+    Ok(files)
+}
+
+/// Write `content` to `path` (from the book directory) unless the file exists.
+fn create_file(book_dir: &Path, path: &Path, content: &str) -> Result<(PathBuf, Outcome), Error> {
+    let full_path = book_dir.join(path);
+    if full_path.exists() {
+        return Ok((path.to_path_buf(), Outcome::Kept));
+    }
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&full_path, content)
+        .with_context(|| format!("Failed to write {}", full_path.display()))?;
+    Ok((path.to_path_buf(), Outcome::Created))
+}
+
+/// A path as a TOML string, with `/` separators as in the rest of `book.toml`.
+fn toml_path(path: &Path) -> Result<Value, Error> {
+    let parts = path
+        .iter()
+        .map(|part| {
+            part.to_str()
+                .ok_or_else(|| anyhow!("Path contains invalid UTF-8: {path:?}"))
+        })
+        .collect::<Result<Vec<&str>, Error>>()?;
+    Ok(Value::String(parts.join("/")))
+}
+
+/// Set up the book and list what was done.
+pub fn run(options: &InitOptions) -> Result<(), Error> {
+    for (path, outcome) in init(options)? {
+        match outcome {
+            Outcome::Created => println!("Created {}", path.display()),
+            Outcome::Configured => println!("Added [preprocessor.bib] to {}", path.display()),
+            Outcome::Kept if path == Path::new("book.toml") => {
+                println!("Kept the existing [preprocessor.bib] section of book.toml")
+            }
+            Outcome::Kept => println!("Kept {}, which already exists", path.display()),
+        }
+    }
+    Ok(())
+}
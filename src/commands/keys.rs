@@ -0,0 +1,64 @@
+//! `mdbook-bib keys`: list the citation keys of a book, for editor completion.
+//!
+//! Entries come from the bibliography the book is configured with, so the list matches
+//! what a build sees.
+
+use std::path::Path;
+
+use mdbook_preprocessor::errors::Error;
+use serde::Serialize;
+
+use crate::models::BibItem;
+
+use super::BookProject;
+
+/// A citable entry, with what an editor shows next to its key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyListing {
+    pub key: String,
+    pub title: String,
+    /// Authors as `Last, First`
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+    #[serde(rename = "type")]
+    pub entry_type: Option<String>,
+}
+
+impl From<&BibItem> for KeyListing {
+    fn from(item: &BibItem) -> Self {
+        KeyListing {
+            key: item.citation_key.clone(),
+            title: item.title.clone(),
+            authors: item
+                .authors
+                .iter()
+                .map(|parts| parts.join(", "))
+                // The parser's placeholder for entries without authors
+                .filter(|author| author != "N/A")
+                .collect(),
+            year: item.pub_year.clone(),
+            entry_type: item.entry_type.as_deref().map(str::to_lowercase),
+        }
+    }
+}
+
+/// The entries of the book's bibliography, in bibliography order.
+pub fn list_keys(project: &BookProject) -> Result<Vec<KeyListing>, Error> {
+    let config = project.config()?;
+    let bibliography = project.load_cached_bibliography(&config)?;
+    Ok(bibliography.values().map(KeyListing::from).collect())
+}
+
+/// Print the keys of the book in `book_dir`, one per line, or the entries as JSON.
+pub fn run(book_dir: &Path, json: bool) -> Result<(), Error> {
+    let project = BookProject::load(book_dir)?;
+    let keys = list_keys(&project)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&keys)?);
+    } else {
+        for listing in &keys {
+            println!("{}", listing.key);
+        }
+    }
+    Ok(())
+}
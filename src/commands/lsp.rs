@@ -0,0 +1,479 @@
+//! `mdbook-bib lsp`: a language server for the citations in a book's chapters.
+//!
+//! Speaks the Language Server Protocol over standard input and output. Editors get the
+//! bibliography's keys as completions, the formatted reference on hover, unknown keys and
+//! malformed citations as diagnostics, and the entry in the bibliography file as the
+//! definition of a citation.
+
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use handlebars::Handlebars;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics, ShowMessage,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, CompletionTextEdit,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, Documentation, GotoDefinitionResponse,
+    Hover, HoverContents, HoverProviderCapability, InitializeParams, Location, MarkupContent,
+    MarkupKind, MessageType, OneOf, Position, PublishDiagnosticsParams, ServerCapabilities,
+    ShowMessageParams, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use mdbook_preprocessor::book::Chapter;
+use mdbook_preprocessor::errors::Error;
+use regex::Regex;
+
+use crate::backend::{BackendMode, BibliographyBackend, CslBackend, CustomBackend};
+use crate::citation::{find_citations, find_nocite_keys, find_partial_key, ChapterSettings};
+use crate::config::CitationSyntax;
+use crate::models::BibItem;
+
+use super::check::check_content;
+use super::render::to_plain_text;
+use super::{BibliographySource, BookProject};
+
+lazy_static! {
+    // Controls and hidden sources of the reference templates are not part of the reference
+    static ref HIDDEN_HTML_REGEX: Regex =
+        Regex::new(r"(?s)<button\b.*?</button>|<pre\b[^>]*\bhidden\b.*?</pre>").unwrap();
+}
+
+/// What the server knows of a book: its configuration and bibliography.
+pub struct Workspace {
+    citation_syntax: CitationSyntax,
+    bibliography: IndexMap<String, BibItem>,
+    /// Plain text of each entry's reference, as the configured backend formats it
+    references: HashMap<String, String>,
+    /// Absolute path of the bibliography file, with its content
+    source: Option<(PathBuf, BibliographySource)>,
+}
+
+impl Workspace {
+    /// Load the book in `book_dir`, reading a Zotero library from its saved copy if any.
+    pub fn load(book_dir: &Path) -> Result<Self, Error> {
+        let project = BookProject::load(book_dir)?;
+        let config = project.config()?;
+        let bibliography = project.load_cached_bibliography(&config)?;
+
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string("references", &config.bib_hb_html)
+            .context("Failed to register references template")?;
+        handlebars
+            .register_template_string("citation", &config.cite_hb_html)
+            .context("Failed to register citation template")?;
+        let backend: Box<dyn BibliographyBackend> = match config.backend {
+            BackendMode::Custom => Box::new(CustomBackend::new(&handlebars)),
+            BackendMode::Csl => {
+                let style = config.csl_style.as_deref().unwrap_or("apa");
+                Box::new(
+                    CslBackend::new(style.to_string())
+                        .context("Failed to initialize CSL backend")?,
+                )
+            }
+        };
+        let references = bibliography
+            .values()
+            .filter_map(|item| match backend.format_reference(item, &[]) {
+                Ok(html) => Some((item.citation_key.clone(), reference_text(&html))),
+                Err(e) => {
+                    tracing::warn!("Failed to format '{}': {e}", item.citation_key);
+                    None
+                }
+            })
+            .collect();
+
+        let source = BibliographySource::load(&project, &config).map(|source| {
+            let path = fs::canonicalize(book_dir)
+                .unwrap_or_else(|_| book_dir.to_path_buf())
+                .join(&source.path);
+            (path, source)
+        });
+        Ok(Workspace {
+            citation_syntax: config.citation_syntax.clone(),
+            bibliography,
+            references,
+            source,
+        })
+    }
+
+    /// The keys of the bibliography, when a key is being typed at `position`.
+    pub fn completions(&self, content: &str, position: Position) -> Vec<CompletionItem> {
+        let Some(citation_syntax) = self.citation_syntax_of(content) else {
+            return Vec::new();
+        };
+        let offset = offset_at(content, position);
+        let line_start = content[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        let Some(partial) = find_partial_key(&content[line_start..offset], &citation_syntax) else {
+            return Vec::new();
+        };
+        // The whole key typed so far is replaced
+        let range =
+            lsp_types::Range::new(position_at(content, line_start + partial.start), position);
+
+        self.bibliography
+            .values()
+            .map(|item| {
+                let key = &item.citation_key;
+                let title = item.title.trim();
+                CompletionItem {
+                    label: key.clone(),
+                    kind: Some(CompletionItemKind::REFERENCE),
+                    detail: Some(match &item.pub_year {
+                        Some(year) => format!("{title} ({year})"),
+                        None => title.to_string(),
+                    }),
+                    documentation: self
+                        .references
+                        .get(key)
+                        .map(|reference| Documentation::String(reference.clone())),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, key.clone()))),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+
+    /// The formatted reference of the entry cited at `position`.
+    pub fn hover(&self, content: &str, position: Position) -> Option<Hover> {
+        let (key, span) = self.citation_at(content, offset_at(content, position))?;
+        let reference = self.references.get(&key)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: reference.clone(),
+            }),
+            range: Some(range_of(content, span)),
+        })
+    }
+
+    /// The entry cited at `position`, in the bibliography file.
+    pub fn definition(&self, content: &str, position: Position) -> Option<Location> {
+        let (key, _) = self.citation_at(content, offset_at(content, position))?;
+        let item = self.bibliography.get(&key)?;
+        let (path, source) = self.source.as_ref()?;
+        Some(Location::new(
+            Url::from_file_path(path).ok()?,
+            range_of(&source.content, source.span_of(item)?),
+        ))
+    }
+
+    /// Unknown keys and malformed citations of a chapter, as `mdbook-bib check` reports them.
+    pub fn diagnostics(&self, content: &str) -> Vec<Diagnostic> {
+        let Some(citation_syntax) = self.citation_syntax_of(content) else {
+            return Vec::new();
+        };
+        check_content(content, &citation_syntax, &self.bibliography)
+            .into_iter()
+            .map(|finding| Diagnostic {
+                range: range_of(content, finding.span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some("mdbook-bib".to_string()),
+                message: finding.message,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    /// Citation syntax of a chapter, or `None` when the chapter opts out of citations.
+    fn citation_syntax_of(&self, content: &str) -> Option<CitationSyntax> {
+        let chapter = Chapter::new("", content.to_string(), "", vec![]);
+        let settings = ChapterSettings::for_chapter(&chapter);
+        (!settings.off).then(|| {
+            settings
+                .citation_syntax
+                .unwrap_or_else(|| self.citation_syntax.clone())
+        })
+    }
+
+    /// The key cited or listed with `nocite` at a byte offset, with the citation's range.
+    fn citation_at(&self, content: &str, offset: usize) -> Option<(String, Range<usize>)> {
+        let citation_syntax = self.citation_syntax_of(content)?;
+        find_citations(content, &citation_syntax)
+            .into_iter()
+            .map(|citation| (citation.key, citation.span))
+            .chain(find_nocite_keys(content))
+            .find(|(_, span)| span.start <= offset && offset <= span.end)
+    }
+}
+
+/// The plain text of a formatted reference, one non-blank line per line of output.
+fn reference_text(html: &str) -> String {
+    let text = to_plain_text(&HIDDEN_HTML_REGEX.replace_all(html, ""));
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Byte offset of a position, whose character is counted in UTF-16 code units.
+fn offset_at(content: &str, position: Position) -> usize {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(position.line as usize)
+        .map(str::len)
+        .sum();
+    let line = content[line_start..].split('\n').next().unwrap_or_default();
+    let mut units = 0;
+    for (idx, c) in line.char_indices() {
+        if units >= position.character as usize {
+            return line_start + idx;
+        }
+        units += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Position of a byte offset, its character counted in UTF-16 code units.
+fn position_at(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+fn range_of(content: &str, span: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range::new(
+        position_at(content, span.start),
+        position_at(content, span.end),
+    )
+}
+
+/// The server's state: the book and the documents open in the editor.
+struct Server<'a> {
+    connection: &'a Connection,
+    book_dir: PathBuf,
+    workspace: Option<Workspace>,
+    documents: HashMap<Url, String>,
+}
+
+impl Server<'_> {
+    /// Load the book again, keeping the last one loaded if it fails.
+    fn reload(&mut self) -> Result<(), Error> {
+        match Workspace::load(&self.book_dir) {
+            Ok(workspace) => self.workspace = Some(workspace),
+            Err(e) => {
+                let params = ShowMessageParams {
+                    typ: MessageType::WARNING,
+                    message: format!(
+                        "mdbook-bib: failed to load the book in {}: {e:#}",
+                        self.book_dir.display()
+                    ),
+                };
+                self.notify::<ShowMessage>(params)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Result<(), Error> {
+        match request.method.as_str() {
+            Completion::METHOD => self.respond::<Completion>(request, |server, params| {
+                let position = params.text_document_position;
+                let (workspace, content) = server.document(&position.text_document.uri)?;
+                Some(CompletionResponse::Array(
+                    workspace.completions(content, position.position),
+                ))
+            }),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, |server, params| {
+                let position = params.text_document_position_params;
+                let (workspace, content) = server.document(&position.text_document.uri)?;
+                workspace.hover(content, position.position)
+            }),
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(request, |server, params| {
+                let position = params.text_document_position_params;
+                let (workspace, content) = server.document(&position.text_document.uri)?;
+                workspace
+                    .definition(content, position.position)
+                    .map(GotoDefinitionResponse::Scalar)
+            }),
+            _ => {
+                let response = Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request '{}'", request.method),
+                );
+                self.connection.sender.send(response.into())?;
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<(), Error> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(&uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // Changes hold the whole text, as the server asks for full synchronization
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.documents.insert(uri.clone(), change.text);
+                    self.publish_diagnostics(&uri)?;
+                }
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // The configuration or the bibliography changed
+                let extension = Path::new(params.text_document.uri.path())
+                    .extension()
+                    .and_then(|extension| extension.to_str());
+                if matches!(extension, Some("toml" | "bib" | "yaml" | "yml")) {
+                    self.reload()?;
+                    let uris: Vec<Url> = self.documents.keys().cloned().collect();
+                    for uri in &uris {
+                        self.publish_diagnostics(uri)?;
+                    }
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.publish_diagnostics(&uri)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The workspace and the content of an open document.
+    fn document(&self, uri: &Url) -> Option<(&Workspace, &str)> {
+        Some((self.workspace.as_ref()?, self.documents.get(uri)?))
+    }
+
+    /// Answer a request, or report its parameters as invalid.
+    fn respond<R: lsp_types::request::Request>(
+        &self,
+        request: Request,
+        handler: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Result<(), Error> {
+        let response = match serde_json::from_value(request.params) {
+            Ok(params) => Response::new_ok(request.id, handler(self, params)),
+            Err(e) => Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string()),
+        };
+        self.connection.sender.send(response.into())?;
+        Ok(())
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(
+        &self,
+        params: N::Params,
+    ) -> Result<(), Error> {
+        let notification = Notification::new(N::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into())?;
+        Ok(())
+    }
+
+    /// Publish the diagnostics of a chapter; closed documents and other files get none.
+    fn publish_diagnostics(&self, uri: &Url) -> Result<(), Error> {
+        let diagnostics = match self.document(uri) {
+            Some((workspace, content)) if uri.path().ends_with(".md") => {
+                workspace.diagnostics(content)
+            }
+            _ => Vec::new(),
+        };
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams::new(
+            uri.clone(),
+            diagnostics,
+            None,
+        ))
+    }
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            // After `@`, `{{#cite ` and between `nocite` keys
+            trigger_characters: Some(vec!["@".to_string(), " ".to_string(), ",".to_string()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+/// The book directory of the editor's workspace, from the `initialize` request.
+fn root_dir(params: &InitializeParams) -> Option<PathBuf> {
+    #[allow(deprecated)]
+    let root_uri = params.root_uri.as_ref();
+    params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(root_uri)
+        .and_then(|uri| uri.to_file_path().ok())
+}
+
+/// Serve a client over `connection` until it shuts the server down.
+///
+/// Without `book_dir`, the book is the root of the editor's workspace.
+pub fn serve(connection: &Connection, book_dir: Option<&Path>) -> Result<(), Error> {
+    let (id, params) = connection.initialize_start()?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    let book_dir = book_dir
+        .map(Path::to_path_buf)
+        .or_else(|| root_dir(&params))
+        .unwrap_or_else(|| PathBuf::from("."));
+    connection.initialize_finish(
+        id,
+        serde_json::json!({
+            "capabilities": capabilities(),
+            "serverInfo": { "name": "mdbook-bib", "version": env!("CARGO_PKG_VERSION") },
+        }),
+    )?;
+
+    let mut server = Server {
+        connection,
+        book_dir,
+        workspace: None,
+        documents: HashMap::new(),
+    };
+    server.reload()?;
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                server.handle_request(request)?;
+            }
+            Message::Notification(notification) => server.handle_notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Run the language server over standard input and output.
+pub fn run(book_dir: Option<&Path>) -> Result<(), Error> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection, book_dir)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
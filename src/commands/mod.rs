@@ -0,0 +1,217 @@
+//! Subcommands of the `mdbook-bib` binary, working on a book's sources without building it.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use indexmap::IndexMap;
+use mdbook_preprocessor::book::{Book, BookItem, Chapter};
+use mdbook_preprocessor::config::Config as BookConfig;
+use mdbook_preprocessor::errors::Error;
+use mdbook_preprocessor::PreprocessorContext;
+use mdbook_summary::{parse_summary, SummaryItem};
+
+use crate::backend::MarkdownBackend;
+use crate::citation::{expand_cite_references_in_book, CitationResult};
+use crate::config::{Config, Numbering};
+use crate::models::BibItem;
+use crate::parser::BibFormat;
+use crate::{parser, Bibliography, ZOTERO_CACHE_FILE};
+
+pub mod add;
+pub mod check;
+pub mod convert;
+pub mod export;
+pub mod init;
+pub mod keys;
+pub mod lsp;
+pub mod render;
+pub mod stats;
+pub mod styles;
+
+/// A book on disk, read the way mdBook hands it to the preprocessor.
+pub struct BookProject {
+    /// Context the preprocessor would run with, for the HTML renderer.
+    pub ctx: PreprocessorContext,
+    /// The `[preprocessor.bib]` table.
+    pub table: toml::value::Table,
+    /// Chapters listed in `SUMMARY.md`, with their content.
+    pub book: Book,
+}
+
+impl BookProject {
+    /// Read `book.toml`, its `[preprocessor.bib]` table and the chapters of `SUMMARY.md`.
+    pub fn load(book_dir: &Path) -> Result<Self, Error> {
+        let book_toml = book_dir.join("book.toml");
+        let book_config = BookConfig::from_disk(&book_toml)
+            .with_context(|| format!("Failed to read {}", book_toml.display()))?;
+        let table = book_config
+            .get::<toml::value::Table>("preprocessor.bib")?
+            .ok_or_else(|| anyhow!("No [preprocessor.bib] section in {}", book_toml.display()))?;
+
+        let src_dir = book_dir.join(&book_config.book.src);
+        // Report configuration errors upfront
+        Config::build_from(Some(&table), src_dir.clone())?;
+
+        let summary_path = src_dir.join("SUMMARY.md");
+        let summary = fs::read_to_string(&summary_path)
+            .with_context(|| format!("Failed to read {}", summary_path.display()))?;
+        let summary = parse_summary(&summary)
+            .with_context(|| format!("Failed to parse {}", summary_path.display()))?;
+        let items = [
+            &summary.prefix_chapters,
+            &summary.numbered_chapters,
+            &summary.suffix_chapters,
+        ]
+        .into_iter()
+        .flat_map(|items| load_items(&src_dir, items, &[]))
+        .collect();
+
+        let ctx = PreprocessorContext::new(book_dir.to_path_buf(), book_config, "html".to_string());
+        Ok(BookProject {
+            ctx,
+            table,
+            book: Book::new_with_items(items),
+        })
+    }
+
+    /// The `[preprocessor.bib]` configuration.
+    pub fn config(&self) -> Result<Config<'_>, Error> {
+        Config::build_from(
+            Some(&self.table),
+            self.ctx.root.join(&self.ctx.config.book.src),
+        )
+    }
+
+    /// Load and parse the bibliography, from the configured file or Zotero.
+    pub fn load_bibliography(&self, config: &Config) -> Result<IndexMap<String, BibItem>, Error> {
+        Ok(self.read_bibliography(config)?.0)
+    }
+
+    /// Load and parse the bibliography, along with the format it was written in.
+    pub fn read_bibliography(
+        &self,
+        config: &Config,
+    ) -> Result<(IndexMap<String, BibItem>, BibFormat), Error> {
+        let (content, format) = self.read_bibliography_source(config)?;
+        Ok((parser::parse_bibliography(content, format)?, format))
+    }
+
+    /// Read the bibliography as written, along with its format.
+    pub fn read_bibliography_source(&self, config: &Config) -> Result<(String, BibFormat), Error> {
+        Bibliography::retrieve_bibliography_content(&self.ctx, config)
+    }
+
+    /// Like `load_bibliography`, but a Zotero library is read from the copy saved by the
+    /// last download, when there is one, instead of being downloaded again.
+    pub fn load_cached_bibliography(
+        &self,
+        config: &Config,
+    ) -> Result<IndexMap<String, BibItem>, Error> {
+        let cache = self.ctx.root.join(ZOTERO_CACHE_FILE);
+        if config.bibliography.is_none() && config.zotero_uid.is_some() && cache.exists() {
+            tracing::info!("Reading Zotero library from {}", cache.display());
+            let content = fs::read_to_string(&cache)
+                .with_context(|| format!("Failed to read {}", cache.display()))?;
+            return parser::parse_bibliography(content, BibFormat::BibTeX);
+        }
+        self.load_bibliography(config)
+    }
+
+    /// Path of a source file as shown to the user, relative to the book directory.
+    pub fn display_path(&self, path: &Path) -> PathBuf {
+        self.ctx
+            .config
+            .book
+            .src
+            .join(path)
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect()
+    }
+}
+
+/// Find the citations of `book` as a build would, numbering the entries in order of
+/// first citation.
+pub(crate) fn collect_citations(
+    book: &mut Book,
+    config: &Config,
+    bibliography: &mut IndexMap<String, BibItem>,
+) -> CitationResult {
+    expand_cite_references_in_book(
+        book,
+        bibliography,
+        &MarkdownBackend::new(),
+        &config.citation_syntax,
+        Numbering::Global,
+        false,
+        &config.nocite,
+    )
+}
+
+/// Build the book items of a `SUMMARY.md` section, reading each chapter's file.
+///
+/// Missing files are read as empty chapters, as mdBook would create them.
+fn load_items(src_dir: &Path, items: &[SummaryItem], parents: &[String]) -> Vec<BookItem> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            SummaryItem::Link(link) => {
+                let mut chapter = match &link.location {
+                    Some(location) => {
+                        let content = fs::read_to_string(src_dir.join(location))
+                            .inspect_err(|e| {
+                                tracing::warn!("Failed to read {}: {e}", location.display())
+                            })
+                            .unwrap_or_default();
+                        Chapter::new(&link.name, content, location, parents.to_vec())
+                    }
+                    None => Chapter::new_draft(&link.name, parents.to_vec()),
+                };
+                let mut names = parents.to_vec();
+                names.push(link.name.clone());
+                chapter.sub_items = load_items(src_dir, &link.nested_items, &names);
+                Some(BookItem::Chapter(chapter))
+            }
+            SummaryItem::Separator => Some(BookItem::Separator),
+            SummaryItem::PartTitle(title) => Some(BookItem::PartTitle(title.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 1-based line and column (in characters) of a byte offset.
+pub(crate) fn line_col(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The bibliography file of a book, to locate its entries.
+pub(crate) struct BibliographySource {
+    /// Path from the book directory
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl BibliographySource {
+    /// Read the configured bibliography file, or the saved copy of a Zotero library.
+    pub fn load(project: &BookProject, config: &Config) -> Option<Self> {
+        let path = match config.bibliography {
+            Some(file) => project.display_path(Path::new(file)),
+            None => PathBuf::from(ZOTERO_CACHE_FILE),
+        };
+        let content = fs::read_to_string(project.ctx.root.join(&path)).ok()?;
+        Some(BibliographySource { path, content })
+    }
+
+    /// Byte range of an entry's source in the file, unless the file changed since it was parsed.
+    pub fn span_of(&self, item: &BibItem) -> Option<Range<usize>> {
+        let span = item.source_span.clone()?;
+        (self.content.get(span.clone()) == item.raw_source.as_deref()).then_some(span)
+    }
+}
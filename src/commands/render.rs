@@ -0,0 +1,201 @@
+//! `mdbook-bib render`: preview the citations and references of a few entries.
+//!
+//! Goes through the same backends as a book build, with a CSL style or the Handlebars
+//! templates, to compare styles or test custom templates without building a book.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use handlebars::Handlebars;
+use indexmap::{IndexMap, IndexSet};
+use lazy_static::lazy_static;
+use mdbook_preprocessor::errors::Error;
+use regex::Regex;
+use toml::value::{Table, Value};
+
+use crate::backend::{
+    BibliographyBackend, CitationContext, CitationVariant, CslBackend, CustomBackend,
+};
+use crate::citation::citation_anchor;
+use crate::config::Config;
+use crate::models::BibItem;
+use crate::{io, parser};
+
+/// Citation variants accepted by `--variant`.
+pub const VARIANTS: &[(&str, CitationVariant)] = &[
+    ("standard", CitationVariant::Standard),
+    ("author-in-text", CitationVariant::AuthorInText),
+    ("parenthetical", CitationVariant::Parenthetical),
+    ("suppress-author", CitationVariant::SuppressAuthor),
+];
+
+lazy_static! {
+    pub(crate) static ref TAG_REGEX: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref MARKDOWN_LINK_REGEX: Regex = Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+    static ref ENTITY_REGEX: Regex = Regex::new(r"&(\#x[0-9a-fA-F]+|\#[0-9]+|[a-z]+);").unwrap();
+    static ref BLANK_LINES_REGEX: Regex = Regex::new(r"\n\s*\n(\s*\n)+").unwrap();
+}
+
+/// What to render, from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Bibliography file (`.bib` or `.yaml`)
+    pub bib: PathBuf,
+    /// CSL style; the Handlebars templates are used without one
+    pub style: Option<String>,
+    /// Entries to cite, in order; all entries when empty
+    pub keys: Vec<String>,
+    pub variant: CitationVariant,
+    /// Handlebars template for references, as `hb-tpl`
+    pub hb_tpl: Option<PathBuf>,
+    /// Handlebars template for citations, as `cite-hb-tpl`
+    pub cite_hb_tpl: Option<PathBuf>,
+    /// Print plain text instead of HTML
+    pub text: bool,
+}
+
+/// Render the citations of the requested entries, then their reference list.
+pub fn render(options: &RenderOptions) -> Result<String, Error> {
+    let format = io::detect_format(&options.bib);
+    let content = io::load_bibliography(&options.bib)?;
+    let mut bibliography = parser::parse_bibliography(content, format)?;
+
+    let keys: Vec<String> = if options.keys.is_empty() {
+        bibliography.keys().cloned().collect()
+    } else {
+        options.keys.clone()
+    };
+    if let Some(key) = keys.iter().find(|key| !bibliography.contains_key(*key)) {
+        return Err(anyhow!(
+            "Unknown citation key '{key}' in {}",
+            options.bib.display()
+        ));
+    }
+
+    let table = config_table(options)?;
+    let config = Config::build_from(Some(&table), PathBuf::new())?;
+    let mut handlebars = Handlebars::new();
+    handlebars
+        .register_template_string("references", &config.bib_hb_html)
+        .context("Failed to register references template")?;
+    handlebars
+        .register_template_string("citation", &config.cite_hb_html)
+        .context("Failed to register citation template")?;
+    let backend: Box<dyn BibliographyBackend> = match &options.style {
+        Some(style) => {
+            Box::new(CslBackend::new(style.clone()).context("Failed to initialize CSL backend")?)
+        }
+        None => Box::new(CustomBackend::new(&handlebars)),
+    };
+
+    let citations = render_citations(&mut bibliography, &keys, backend.as_ref(), options.variant)?;
+    // Entries cited several times are listed once
+    let cited: IndexSet<&String> = keys.iter().collect();
+    let references = cited
+        .into_iter()
+        .map(|key| {
+            Ok(backend
+                .format_reference(&bibliography[key], &[])?
+                .trim()
+                .to_string())
+        })
+        .collect::<Result<Vec<String>, Error>>()?;
+
+    let output = format!(
+        "Citations:\n\n{citations}\n\nReferences:\n\n{}\n",
+        references.join("\n")
+    );
+    Ok(if options.text {
+        to_plain_text(&output)
+    } else {
+        output
+    })
+}
+
+/// Configuration equivalent to the options, to load templates as a book would.
+fn config_table(options: &RenderOptions) -> Result<Table, Error> {
+    let mut table = Table::new();
+    let path_value = |path: &Path| {
+        path.to_str()
+            .map(|path| Value::String(path.to_string()))
+            .ok_or_else(|| anyhow!("Path contains invalid UTF-8: {path:?}"))
+    };
+    if let Some(style) = &options.style {
+        table.insert("backend".to_string(), Value::String("csl".to_string()));
+        table.insert("csl-style".to_string(), Value::String(style.clone()));
+    }
+    if let Some(path) = &options.hb_tpl {
+        table.insert("hb-tpl".to_string(), path_value(path)?);
+    }
+    if let Some(path) = &options.cite_hb_tpl {
+        table.insert("cite-hb-tpl".to_string(), path_value(path)?);
+    }
+    Ok(table)
+}
+
+/// One citation per line, numbered in order, followed by any footnotes of note styles.
+fn render_citations(
+    bibliography: &mut IndexMap<String, BibItem>,
+    keys: &[String],
+    backend: &dyn BibliographyBackend,
+    variant: CitationVariant,
+) -> Result<String, Error> {
+    let mut last_index = 0;
+    let mut lines = Vec::new();
+    for key in keys {
+        let item = bibliography.get_mut(key).expect("Checked keys");
+        if item.index.is_none() {
+            last_index += 1;
+            item.index = Some(last_index);
+        }
+        item.citation_count += 1;
+        let context = CitationContext {
+            bib_page_path: String::new(),
+            chapter_path: String::new(),
+            variant,
+            anchor: citation_anchor(key, item.citation_count),
+            entry_anchor: key.clone(),
+        };
+        lines.push(backend.format_citation(item, &context)?);
+    }
+
+    let footnotes = backend.chapter_footnotes()?;
+    if !footnotes.trim().is_empty() {
+        lines.push(String::new());
+        lines.push(footnotes.trim().to_string());
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Strip HTML tags and Markdown links, keeping the text a reader would see.
+pub(crate) fn to_plain_text(html: &str) -> String {
+    let text = TAG_REGEX.replace_all(html, "");
+    let text = MARKDOWN_LINK_REGEX.replace_all(&text, "$1");
+    let text = ENTITY_REGEX.replace_all(&text, |caps: &regex::Captures| {
+        let entity = &caps[1];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        decoded.map_or_else(|| caps[0].to_string(), String::from)
+    });
+    let text = text.lines().map(str::trim).collect::<Vec<_>>().join("\n");
+    let text = BLANK_LINES_REGEX.replace_all(&text, "\n\n");
+    format!("{}\n", text.trim())
+}
+
+/// Print the rendered citations and references.
+pub fn run(options: &RenderOptions) -> Result<(), Error> {
+    print!("{}", render(options)?);
+    Ok(())
+}
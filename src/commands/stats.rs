@@ -0,0 +1,253 @@
+//! `mdbook-bib stats`: report how a book uses its bibliography.
+//!
+//! Citations are counted the way a build finds them: per entry, per chapter, and over the
+//! years and entry types of the bibliography.
+
+use std::cmp::Reverse;
+use std::path::Path;
+
+use indexmap::IndexMap;
+use mdbook_preprocessor::book::BookItem;
+use mdbook_preprocessor::errors::Error;
+use serde::Serialize;
+
+use super::{collect_citations, BookProject};
+
+/// Citations of a bibliography entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryStats {
+    pub key: String,
+    /// Inline citations in the book
+    pub citations: u32,
+    /// Chapters citing the entry
+    pub chapters: usize,
+}
+
+/// Citations of a chapter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChapterStats {
+    /// Path from the book directory
+    pub path: String,
+    /// Inline citations in the chapter
+    pub citations: u32,
+    /// Distinct entries cited
+    pub entries: usize,
+}
+
+/// Entries sharing a year or an entry type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupStats {
+    pub name: String,
+    /// Entries of the bibliography
+    pub entries: usize,
+    /// Entries cited at least once
+    pub cited: usize,
+}
+
+/// Citation usage of a book.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BookStats {
+    pub total_citations: u32,
+    /// Cited entries, most cited first
+    pub entries: Vec<EntryStats>,
+    /// Chapters, most citing first
+    pub chapters: Vec<ChapterStats>,
+    /// Entries never cited inline, in bibliography order
+    pub uncited: Vec<String>,
+    /// By publication year, oldest first
+    pub years: Vec<GroupStats>,
+    /// By entry type, most common first
+    pub types: Vec<GroupStats>,
+}
+
+/// Count the citations of the book.
+pub fn book_stats(project: &BookProject) -> Result<BookStats, Error> {
+    let config = project.config()?;
+    let mut bibliography = project.load_cached_bibliography(&config)?;
+    let mut book = project.book.clone();
+    let citations = collect_citations(&mut book, &config, &mut bibliography);
+
+    let mut entries: Vec<EntryStats> = bibliography
+        .values()
+        .filter(|item| item.citation_count > 0)
+        .map(|item| EntryStats {
+            key: item.citation_key.clone(),
+            citations: item.citation_count,
+            chapters: citations
+                .backrefs
+                .get(&item.citation_key)
+                .map_or(0, Vec::len),
+        })
+        .collect();
+    entries.sort_by_key(|entry| Reverse(entry.citations));
+
+    let mut chapters: Vec<ChapterStats> = project
+        .book
+        .iter()
+        .filter_map(|item| match item {
+            BookItem::Chapter(chapter) => chapter.path.as_ref(),
+            _ => None,
+        })
+        .map(|path| {
+            let chapter_key = path.display().to_string();
+            ChapterStats {
+                path: project.display_path(path).display().to_string(),
+                citations: citations
+                    .citation_counts
+                    .get(&chapter_key)
+                    .copied()
+                    .unwrap_or(0),
+                entries: citations.per_chapter.get(&chapter_key).map_or(0, |cited| {
+                    cited
+                        .iter()
+                        .filter(|key| bibliography.contains_key(*key))
+                        .count()
+                }),
+            }
+        })
+        .collect();
+    chapters.sort_by_key(|chapter| Reverse(chapter.citations));
+
+    let mut years: IndexMap<String, GroupStats> = IndexMap::new();
+    let mut types: IndexMap<String, GroupStats> = IndexMap::new();
+    for item in bibliography.values() {
+        let year = item
+            .pub_year
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string());
+        let entry_type = item
+            .entry_type
+            .as_deref()
+            .unwrap_or("unknown")
+            .to_lowercase();
+        for (groups, name) in [(&mut years, year), (&mut types, entry_type)] {
+            let group = groups.entry(name.clone()).or_insert(GroupStats {
+                name,
+                entries: 0,
+                cited: 0,
+            });
+            group.entries += 1;
+            group.cited += usize::from(item.citation_count > 0);
+        }
+    }
+    let mut years: Vec<GroupStats> = years.into_values().collect();
+    // Numeric years first, in order, then anything else
+    years.sort_by_key(|group| group.name.parse::<i64>().map_err(|_| group.name.clone()));
+    let mut types: Vec<GroupStats> = types.into_values().collect();
+    types.sort_by_key(|group| Reverse(group.entries));
+
+    Ok(BookStats {
+        total_citations: entries.iter().map(|entry| entry.citations).sum(),
+        entries,
+        chapters,
+        uncited: bibliography
+            .values()
+            .filter(|item| item.citation_count == 0)
+            .map(|item| item.citation_key.clone())
+            .collect(),
+        years,
+        types,
+    })
+}
+
+/// Render the report as aligned tables.
+pub fn format_report(stats: &BookStats) -> String {
+    let mut report = format!(
+        "{} citation(s), {} of {} entries cited\n",
+        stats.total_citations,
+        stats.entries.len(),
+        stats.entries.len() + stats.uncited.len()
+    );
+
+    let rows: Vec<[String; 3]> = stats
+        .entries
+        .iter()
+        .map(|entry| {
+            [
+                entry.key.clone(),
+                entry.citations.to_string(),
+                entry.chapters.to_string(),
+            ]
+        })
+        .collect();
+    report.push_str(&count_table(["ENTRY", "CITATIONS", "CHAPTERS"], &rows));
+
+    let rows: Vec<[String; 3]> = stats
+        .chapters
+        .iter()
+        .map(|chapter| {
+            [
+                chapter.path.clone(),
+                chapter.citations.to_string(),
+                chapter.entries.to_string(),
+            ]
+        })
+        .collect();
+    report.push_str(&count_table(["CHAPTER", "CITATIONS", "ENTRIES"], &rows));
+
+    for (title, groups) in [("YEAR", &stats.years), ("TYPE", &stats.types)] {
+        let rows: Vec<[String; 3]> = groups
+            .iter()
+            .map(|group| {
+                [
+                    group.name.clone(),
+                    group.entries.to_string(),
+                    group.cited.to_string(),
+                ]
+            })
+            .collect();
+        report.push_str(&count_table([title, "ENTRIES", "CITED"], &rows));
+    }
+
+    if stats.uncited.is_empty() {
+        report.push_str("\nNever cited: none\n");
+    } else {
+        report.push_str("\nNever cited:\n");
+        for key in &stats.uncited {
+            report.push_str(&format!("  {key}\n"));
+        }
+    }
+    report
+}
+
+/// A table preceded by a blank line, the first column left-aligned and the counts right-aligned.
+fn count_table(headers: [&str; 3], rows: &[[String; 3]]) -> String {
+    let widths: Vec<usize> = (0..3)
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain([headers[column].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: [&str; 3]| {
+        format!(
+            "{:w0$}  {:>w1$}  {:>w2$}\n",
+            cells[0],
+            cells[1],
+            cells[2],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2]
+        )
+    };
+
+    let mut table = format!("\n{}", line(headers));
+    for row in rows {
+        table.push_str(&line([&row[0], &row[1], &row[2]]));
+    }
+    table
+}
+
+/// Print the citation report of the book in `book_dir`, as tables or JSON.
+pub fn run(book_dir: &Path, json: bool) -> Result<(), Error> {
+    let project = BookProject::load(book_dir)?;
+    let stats = book_stats(&project)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print!("{}", format_report(&stats));
+    }
+    Ok(())
+}
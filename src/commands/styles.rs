@@ -0,0 +1,56 @@
+//! `mdbook-bib styles`: list the CSL styles bundled with hayagriva.
+
+use mdbook_preprocessor::errors::Error;
+
+use crate::backend::{list_archived_styles, StyleListing};
+
+/// Citation formats accepted by `--format`.
+pub const STYLE_FORMATS: &[&str] = &["numeric", "author-date", "label", "note"];
+
+/// Bundled styles, only those of `format` when given.
+pub fn styles(format: Option<&str>) -> Vec<StyleListing> {
+    list_archived_styles()
+        .into_iter()
+        .filter(|style| format.is_none_or(|format| style.format == format))
+        .collect()
+}
+
+/// Render styles as an aligned table, one style per line.
+pub fn format_table(styles: &[StyleListing]) -> String {
+    let width = styles
+        .iter()
+        .map(|style| style.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("NAME".len());
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+
+    let mut table = format!(
+        "{:width$}  {:11}  {:11}  {:9}  ALIASES\n",
+        "NAME", "FORMAT", "SUPERSCRIPT", "DEPENDENT"
+    );
+    for style in styles {
+        let line = format!(
+            "{:width$}  {:11}  {:11}  {:9}  {}",
+            style.name,
+            style.format,
+            yes_no(style.superscript),
+            yes_no(style.dependent),
+            style.aliases.join(", ")
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Print the bundled styles, as a table or JSON.
+pub fn run(format: Option<&str>, json: bool) -> Result<(), Error> {
+    let styles = styles(format);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&styles)?);
+    } else {
+        print!("{}", format_table(&styles));
+    }
+    Ok(())
+}
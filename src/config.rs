@@ -10,7 +10,7 @@ use toml::value::Table;
 use toml::Value;
 use tracing::info;
 
-use crate::backend::BackendMode;
+use crate::backend::{BackendMode, OutputFormat};
 
 /// Helper to extract a string from a TOML value with a descriptive error.
 fn value_as_str<'a>(value: &'a Value, field_name: &str) -> Result<&'a str, anyhow::Error> {
@@ -19,6 +19,59 @@ fn value_as_str<'a>(value: &'a Value, field_name: &str) -> Result<&'a str, anyho
         .ok_or_else(|| anyhow!("'{field_name}' must be a string"))
 }
 
+/// Check a `key-pattern`: fields among `KEY_PATTERN_FIELDS`, and characters allowed in keys.
+fn parse_key_pattern(pattern: &str) -> Result<&str, anyhow::Error> {
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        check_key_pattern_text(&rest[..start])?;
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("Unclosed field in key-pattern '{pattern}'"))?;
+        let field = &rest[start + 1..start + end];
+        if !KEY_PATTERN_FIELDS.contains(&field) {
+            return Err(anyhow!(
+                "Unknown field '{{{field}}}' in key-pattern. Use one of [{}]",
+                KEY_PATTERN_FIELDS
+                    .iter()
+                    .map(|field| format!("{{{field}}}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    check_key_pattern_text(rest)?;
+    Ok(pattern)
+}
+
+fn check_key_pattern_text(text: &str) -> Result<(), anyhow::Error> {
+    match text.chars().find(|c| !is_key_char(*c)) {
+        Some(c) => Err(anyhow!(
+            "'{c}' can't be part of a citation key in key-pattern. \
+            Use letters, digits and _-:./ besides the fields"
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Check a citation key given for a new entry: not empty, and made of the characters a
+/// `key-pattern` may use.
+pub fn check_citation_key(key: &str) -> Result<(), anyhow::Error> {
+    if key.is_empty() {
+        return Err(anyhow!("The citation key can't be empty"));
+    }
+    match key.chars().find(|c| !is_key_char(*c)) {
+        Some(c) => Err(anyhow!(
+            "'{c}' can't be part of citation key '{key}'. Use letters, digits and _-:./"
+        )),
+        None => Ok(()),
+    }
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-:./".contains(c)
+}
+
 /// Helper to extract a bool from a TOML value with a descriptive error.
 fn value_as_bool(value: &Value, field_name: &str) -> Result<bool, anyhow::Error> {
     value
@@ -26,6 +79,16 @@ fn value_as_bool(value: &Value, field_name: &str) -> Result<bool, anyhow::Error>
         .ok_or_else(|| anyhow!("'{field_name}' must be a boolean"))
 }
 
+/// Helper to extract a list of strings from a TOML array with a descriptive error.
+fn value_as_str_list(value: &Value, field_name: &str) -> Result<Vec<String>, anyhow::Error> {
+    value
+        .as_array()
+        .ok_or_else(|| anyhow!("'{field_name}' must be a list of strings"))?
+        .iter()
+        .map(|v| value_as_str(v, field_name).map(str::to_string))
+        .collect()
+}
+
 /// Helper to convert OsString to String with a descriptive error.
 fn os_string_to_string(os: OsString) -> Result<String, anyhow::Error> {
     os.into_string()
@@ -56,10 +119,16 @@ fn load_template(
     Ok(wrapper(&content))
 }
 
+/// Fields of `key-pattern`: first author's family name, year and first word of the title.
+pub const KEY_PATTERN_FIELDS: &[&str] = &["author", "year", "title"];
+pub const DEFAULT_KEY_PATTERN: &str = "{author}{year}";
+
 pub static DEFAULT_JS_TEMPLATE: &str = include_str!("./render/copy2clipboard.js");
 pub static DEFAULT_CSS_TEMPLATE: &str = include_str!("./render/satancisco.css");
 pub static DEFAULT_HB_TEMPLATE: &str = include_str!("./render/references.hbs");
 pub static DEFAULT_CITE_HB_TEMPLATE: &str = include_str!("./render/cite_key.hbs");
+pub static DEFAULT_POPOVER_JS_TEMPLATE: &str = include_str!("./render/cite_popover.js");
+pub static DEFAULT_POPOVER_CSS_TEMPLATE: &str = include_str!("./render/cite_popover.css");
 pub static DEFAULT_CHAPTER_REFS_FOOTER_HB_TEMPLATE: &str =
     include_str!("./render/chapter_refs_header.hbs");
 
@@ -83,6 +152,37 @@ pub enum SortOrder {
     Key,
     Author,
     Index,
+    /// Multi-key sort specification, e.g. `["year:desc", "author", "title"]`
+    Spec(Vec<SortCriterion>),
+}
+
+/// `BibItem` field that can be used as a sort criterion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortField {
+    Key,
+    Author,
+    Title,
+    Year,
+    Month,
+    EntryType,
+    Index,
+    /// Number of times the entry is cited in the book
+    Citations,
+}
+
+/// Direction of a single sort criterion.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// A single `field[:direction]` entry of a multi-key sort specification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SortCriterion {
+    pub field: SortField,
+    pub direction: SortDirection,
 }
 
 /// Citation syntax determines which patterns are recognized in markdown.
@@ -98,6 +198,59 @@ pub enum CitationSyntax {
     Pandoc,
 }
 
+/// Machine-readable citation metadata embedded in the bibliography page for reference managers.
+///
+/// - `Coins`: COinS spans (OpenURL ContextObjects), detected by Zotero and similar tools
+/// - `JsonLd`: schema.org `ScholarlyArticle`/`Book`/... descriptions as JSON-LD
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CitationMetadata {
+    #[default]
+    None,
+    Coins,
+    JsonLd,
+    /// Both COinS and JSON-LD
+    All,
+}
+
+impl FromStr for CitationMetadata {
+    type Err = ParseEnumError;
+    fn from_str(input: &str) -> Result<CitationMetadata, Self::Err> {
+        match input {
+            "none" => Ok(CitationMetadata::None),
+            "coins" => Ok(CitationMetadata::Coins),
+            "json-ld" => Ok(CitationMetadata::JsonLd),
+            "all" => Ok(CitationMetadata::All),
+            _ => Err(ParseEnumError(format!(
+                "Unknown citation metadata '{input}'. Must be one of [none, coins, json-ld, all]",
+            ))),
+        }
+    }
+}
+
+/// How numeric citation indices are assigned.
+///
+/// - `Global`: one numbering for the whole book, in order of first citation
+/// - `PerChapter`: every chapter numbers its citations from 1, matching its own reference list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Numbering {
+    #[default]
+    Global,
+    PerChapter,
+}
+
+impl FromStr for Numbering {
+    type Err = ParseEnumError;
+    fn from_str(input: &str) -> Result<Numbering, Self::Err> {
+        match input {
+            "global" => Ok(Numbering::Global),
+            "per-chapter" => Ok(Numbering::PerChapter),
+            _ => Err(ParseEnumError(format!(
+                "Unknown numbering '{input}'. Must be one of [global, per-chapter]",
+            ))),
+        }
+    }
+}
+
 impl FromStr for CitationSyntax {
     type Err = ParseEnumError;
     fn from_str(input: &str) -> Result<CitationSyntax, Self::Err> {
@@ -126,6 +279,65 @@ impl FromStr for SortOrder {
     }
 }
 
+impl FromStr for SortField {
+    type Err = ParseEnumError;
+    fn from_str(input: &str) -> Result<SortField, Self::Err> {
+        match input {
+            "key" => Ok(SortField::Key),
+            "author" => Ok(SortField::Author),
+            "title" => Ok(SortField::Title),
+            "year" => Ok(SortField::Year),
+            "month" => Ok(SortField::Month),
+            "entry_type" | "type" => Ok(SortField::EntryType),
+            "index" => Ok(SortField::Index),
+            "citations" | "citation_count" => Ok(SortField::Citations),
+            _ => Err(ParseEnumError(format!(
+                "Unknown sort field '{input}'. \
+                Must be one of [key author title year month entry_type index citations]",
+            ))),
+        }
+    }
+}
+
+impl FromStr for SortDirection {
+    type Err = ParseEnumError;
+    fn from_str(input: &str) -> Result<SortDirection, Self::Err> {
+        match input {
+            "asc" => Ok(SortDirection::Asc),
+            "desc" => Ok(SortDirection::Desc),
+            _ => Err(ParseEnumError(format!(
+                "Unknown sort direction '{input}'. Must be one of [asc desc]",
+            ))),
+        }
+    }
+}
+
+impl FromStr for SortCriterion {
+    type Err = ParseEnumError;
+    fn from_str(input: &str) -> Result<SortCriterion, Self::Err> {
+        let (field, direction) = match input.trim().split_once(':') {
+            Some((field, direction)) => (field.trim(), direction.trim().parse()?),
+            None => (input.trim(), SortDirection::default()),
+        };
+        Ok(SortCriterion {
+            field: field.parse()?,
+            direction,
+        })
+    }
+}
+
+/// Parse a multi-key sort specification from a TOML array of `field[:direction]` strings.
+fn parse_sort_spec(values: &[Value]) -> Result<SortOrder, Error> {
+    if values.is_empty() {
+        return Err(anyhow!("'order' must contain at least one sort criterion"));
+    }
+    let criteria = values
+        .iter()
+        .map(|v| Ok(SortCriterion::from_str(value_as_str(v, "order")?)?))
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(SortOrder::Spec(criteria))
+}
+
 #[derive(Debug)]
 pub struct Config<'a> {
     /// Title for the Bibliography section of the book
@@ -134,14 +346,22 @@ pub struct Config<'a> {
     pub bibliography: Option<&'a str>,
     /// Whether to add or not the bibliography at the end of each chapter too
     pub add_bib_in_each_chapter: bool,
+    /// Link citations to the chapter's own reference list instead of the bibliography chapter
+    pub link_in_chapters: bool,
+    /// Whether to add the bibliography chapter at the end of the book
+    pub global_bib: bool,
     /// Zotero user ID, as alternative to Bibtex file
     pub zotero_uid: Option<&'a str>,
     /// List only cited references, instead of all from bibliography
     pub cited_only: bool,
+    /// Entries listed in the bibliography without being cited (`*` for all)
+    pub nocite: Vec<String>,
     /// HTML content of the Handlebars render template for references
     pub bib_hb_html: String,
     /// HTML content of the Handlebars render template for inline citations
     pub cite_hb_html: String,
+    /// HTML content of the Handlebars render template for the header of chapter references
+    pub chapter_refs_hb_html: String,
     /// Extra CSS style content for the ad-hoc Handlebars template
     pub css_html: String,
     /// Extra Javascript functions for the ad-hoc Handlebars template
@@ -154,6 +374,28 @@ pub struct Config<'a> {
     pub csl_style: Option<String>,
     /// Citation syntax: which patterns are recognized in markdown
     pub citation_syntax: CitationSyntax,
+    /// List the chapters citing each entry in the bibliography ("Cited on: Ch. 2, Ch. 5")
+    pub backrefs: bool,
+    /// Show the formatted reference in a popover when hovering/focusing inline citations
+    pub cite_popover: bool,
+    /// Offer a download of the original source of the listed entries in the bibliography
+    pub download_bib: bool,
+    /// Add a button copying each entry's original source to the Custom backend's references
+    pub copy_bib: bool,
+    /// Numbering of citations: book-wide, or restarting in each chapter's reference list
+    pub numbering: Numbering,
+    /// Machine-readable metadata (COinS/JSON-LD) added to the bibliography page
+    pub citation_metadata: CitationMetadata,
+    /// Renderers producing LaTeX, for which citations become biblatex commands
+    pub latex_renderers: Vec<String>,
+    /// Pandoc-based renderers, for which Pandoc citations are passed through untouched
+    pub pandoc_renderers: Vec<String>,
+    /// EPUB renderers, for which citations are footnote-style note references
+    pub epub_renderers: Vec<String>,
+    /// Non-HTML renderers, for which citations and references are plain Markdown
+    pub markdown_renderers: Vec<String>,
+    /// Citation key of the entries added with `mdbook-bib add`, e.g. `{author}{year}`
+    pub key_pattern: &'a str,
 }
 
 impl<'a> Config<'a> {
@@ -177,6 +419,17 @@ impl<'a> Config<'a> {
             Some(v) => value_as_bool(v, "add-bib-in-chapters")?,
         };
 
+        let link_in_chapters = match table.get("link-in-chapters") {
+            None => false,
+            Some(v) => value_as_bool(v, "link-in-chapters")?,
+        };
+        if link_in_chapters && !add_bib_in_each_chapter {
+            return Err(anyhow!(
+                "link-in-chapters needs the chapter reference lists. \
+                Set add-bib-in-chapters = true."
+            ));
+        }
+
         let zotero_uid = table
             .get("zotero-uid")
             .map(|v| value_as_str(v, "zotero-uid"))
@@ -196,6 +449,11 @@ impl<'a> Config<'a> {
             },
         };
 
+        let nocite = match table.get("nocite") {
+            Some(v) => value_as_str_list(v, "nocite")?,
+            None => Vec::new(),
+        };
+
         let bib_hb_html = load_template(
             table,
             "hb-tpl",
@@ -214,6 +472,15 @@ impl<'a> Config<'a> {
             |c| c.to_string(),
         )?;
 
+        let chapter_refs_hb_html = load_template(
+            table,
+            "chapter-refs-tpl",
+            &book_src_path,
+            DEFAULT_CHAPTER_REFS_FOOTER_HB_TEMPLATE,
+            "HB template for chapter references header",
+            |c| c.to_string(),
+        )?;
+
         let css_html = load_template(
             table,
             "css",
@@ -233,6 +500,7 @@ impl<'a> Config<'a> {
         )?;
 
         let order = match table.get("order") {
+            Some(Value::Array(specs)) => parse_sort_spec(specs)?,
             Some(v) => SortOrder::from_str(value_as_str(v, "order")?)?,
             None => SortOrder::None,
         };
@@ -270,20 +538,139 @@ impl<'a> Config<'a> {
             None => CitationSyntax::Default,
         };
 
+        let backrefs = match table.get("backrefs") {
+            None => false,
+            Some(v) => value_as_bool(v, "backrefs")?,
+        };
+
+        let cite_popover = match table.get("cite-popover") {
+            None => false,
+            Some(v) => value_as_bool(v, "cite-popover")?,
+        };
+
+        let download_bib = match table.get("download-bib") {
+            None => false,
+            Some(v) => value_as_bool(v, "download-bib")?,
+        };
+
+        let copy_bib = match table.get("copy-bib") {
+            None => false,
+            Some(v) => value_as_bool(v, "copy-bib")?,
+        };
+
+        let numbering = match table.get("numbering") {
+            Some(v) => Numbering::from_str(value_as_str(v, "numbering")?)?,
+            None => Numbering::Global,
+        };
+        if numbering == Numbering::PerChapter && !add_bib_in_each_chapter {
+            return Err(anyhow!(
+                "numbering = \"per-chapter\" needs the chapter reference lists. \
+                Set add-bib-in-chapters = true."
+            ));
+        }
+
+        let global_bib = match table.get("global-bib") {
+            None => true,
+            Some(v) => value_as_bool(v, "global-bib")?,
+        };
+        // Per-chapter numbering also links citations to the chapter lists
+        if !global_bib && !link_in_chapters && numbering == Numbering::Global {
+            return Err(anyhow!(
+                "global-bib = false leaves citations linking to a missing bibliography chapter. \
+                Set link-in-chapters = true."
+            ));
+        }
+
+        let citation_metadata = match table.get("citation-metadata") {
+            Some(v) => CitationMetadata::from_str(value_as_str(v, "citation-metadata")?)?,
+            None => CitationMetadata::None,
+        };
+
+        let latex_renderers = match table.get("latex-renderers") {
+            Some(v) => value_as_str_list(v, "latex-renderers")?,
+            // mdbook-pandoc also writes formats dropping raw LaTeX, so users opt it in
+            None => vec!["latex".to_string()],
+        };
+
+        let pandoc_renderers = match table.get("pandoc-renderers") {
+            Some(v) => value_as_str_list(v, "pandoc-renderers")?,
+            None => vec!["pandoc".to_string()],
+        };
+
+        let epub_renderers = match table.get("epub-renderers") {
+            Some(v) => value_as_str_list(v, "epub-renderers")?,
+            None => vec!["epub".to_string()],
+        };
+
+        let markdown_renderers = match table.get("markdown-renderers") {
+            Some(v) => value_as_str_list(v, "markdown-renderers")?,
+            None => ["markdown", "linkcheck", "linkcheck2"]
+                .map(str::to_string)
+                .to_vec(),
+        };
+
+        let key_pattern = match table.get("key-pattern") {
+            Some(v) => parse_key_pattern(value_as_str(v, "key-pattern")?)?,
+            None => DEFAULT_KEY_PATTERN,
+        };
+
         Ok(Self {
             title,
             bibliography,
             add_bib_in_each_chapter,
+            link_in_chapters,
+            global_bib,
             zotero_uid,
             cited_only,
+            nocite,
             bib_hb_html,
             cite_hb_html,
+            chapter_refs_hb_html,
             css_html,
             js_html,
             order,
             backend,
             csl_style,
             citation_syntax,
+            backrefs,
+            cite_popover,
+            download_bib,
+            copy_bib,
+            numbering,
+            citation_metadata,
+            latex_renderers,
+            pandoc_renderers,
+            epub_renderers,
+            markdown_renderers,
+            key_pattern,
         })
     }
+
+    /// Whether the given mdBook renderer produces LaTeX.
+    pub fn is_latex_renderer(&self, renderer: &str) -> bool {
+        self.latex_renderers.iter().any(|r| r == renderer)
+    }
+
+    /// Whether citations should be left for the given Pandoc-based mdBook renderer.
+    ///
+    /// Only applies with `citation-syntax = "pandoc"`, as pandoc can't read native citations.
+    pub fn is_pandoc_passthrough(&self, renderer: &str) -> bool {
+        self.citation_syntax == CitationSyntax::Pandoc
+            && self.pandoc_renderers.iter().any(|r| r == renderer)
+    }
+
+    /// Output format for the given mdBook renderer, HTML unless configured otherwise.
+    pub fn output_format(&self, renderer: &str) -> OutputFormat {
+        if self.is_pandoc_passthrough(renderer) {
+            OutputFormat::Pandoc
+        } else if self.is_latex_renderer(renderer) {
+            OutputFormat::Latex
+        } else if self.epub_renderers.iter().any(|r| r == renderer) {
+            OutputFormat::Epub
+        } else if self.markdown_renderers.iter().any(|r| r == renderer) {
+            OutputFormat::Markdown
+        } else {
+            OutputFormat::Html
+        }
+    }
 }
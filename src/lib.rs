@@ -3,12 +3,14 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context};
 use handlebars::Handlebars;
-use mdbook_preprocessor::book::{Book, Chapter};
+use indexmap::IndexMap;
+use mdbook_preprocessor::book::{Book, BookItem, Chapter};
 use mdbook_preprocessor::errors::Error;
 use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
 
 mod backend;
 mod citation;
+pub mod commands;
 mod config;
 mod file_utils;
 mod io;
@@ -16,8 +18,12 @@ mod models;
 mod parser;
 mod renderer;
 
-use crate::backend::{BackendMode, CslBackend, CustomBackend};
-use crate::config::Config;
+use crate::backend::{
+    BackendMode, BibliographyBackend, CslBackend, CustomBackend, EpubBackend, LatexBackend,
+    MarkdownBackend, OutputFormat, PandocBackend,
+};
+use crate::config::{Config, Numbering};
+use crate::models::{ChapterCitations, ChapterRefs};
 use crate::parser::BibFormat;
 
 // Re-export for tests
@@ -26,6 +32,10 @@ pub use citation::{AT_REF_PATTERN, REF_PATTERN};
 
 static NAME: &str = "bib";
 static BIB_OUT_FILE: &str = "bibliography";
+/// Copy of the Zotero library saved in the book directory by the last download.
+pub(crate) static ZOTERO_CACHE_FILE: &str = "my_zotero.bib";
+static PANDOC_REFS_DIV: &str = "::: {#refs}\n:::\n";
+static MARKDOWN_CHAPTER_REFS_HEADER: &str = "\n---\n\n**Chapter References**\n\n";
 
 pub struct Bibliography;
 
@@ -36,7 +46,7 @@ impl Default for Bibliography {
 }
 
 impl Bibliography {
-    fn retrieve_bibliography_content(
+    pub(crate) fn retrieve_bibliography_content(
         ctx: &PreprocessorContext,
         cfg: &Config,
     ) -> Result<(String, BibFormat), Error> {
@@ -63,7 +73,7 @@ impl Bibliography {
                             .inspect_err(|e| tracing::warn!("Zotero download failed: {e}"))
                             .unwrap_or_default();
                         if !bib_str.is_empty() {
-                            let biblio_path = ctx.root.join(Path::new("my_zotero.bib"));
+                            let biblio_path = ctx.root.join(ZOTERO_CACHE_FILE);
                             tracing::info!("Saving Zotero bibliography to {:?}", biblio_path);
                             let _ = fs::write(biblio_path, &bib_str);
                             // Zotero always returns BibTeX format
@@ -79,6 +89,53 @@ impl Bibliography {
         Ok((bib_content, format))
     }
 
+    /// Export the bibliography (and CSL style) for pandoc's citeproc and point the
+    /// book's metadata at them, through a YAML block at the top of the first chapter.
+    fn add_pandoc_metadata(
+        ctx: &PreprocessorContext,
+        cfg: &Config,
+        book: &mut Book,
+        bib: &IndexMap<String, models::BibItem>,
+        nocite: &[String],
+    ) -> Result<(), Error> {
+        let out_dir = ctx.root.join(&ctx.config.build.build_dir);
+        fs::create_dir_all(&out_dir)
+            .with_context(|| format!("Failed to create directory {out_dir:?}"))?;
+
+        let bib_path = out_dir.join(format!("{BIB_OUT_FILE}.json"));
+        fs::write(&bib_path, renderer::generate_csl_json(bib)?)
+            .with_context(|| format!("Failed to write CSL-JSON bibliography {bib_path:?}"))?;
+        let mut metadata = vec![format!("bibliography: {}", yaml_quote(&bib_path))];
+
+        if cfg.backend == BackendMode::Csl {
+            let style = cfg.csl_style.as_deref().unwrap_or("apa");
+            let csl_path = out_dir.join(format!("{BIB_OUT_FILE}.csl"));
+            let xml = CslBackend::new(style.to_string())?.style_xml()?;
+            fs::write(&csl_path, xml)
+                .with_context(|| format!("Failed to write CSL style {csl_path:?}"))?;
+            metadata.push(format!("csl: {}", yaml_quote(&csl_path)));
+        }
+        if !cfg.cited_only {
+            metadata.push("nocite: '@*'".to_string());
+        } else if !nocite.is_empty() {
+            let keys: Vec<String> = nocite
+                .iter()
+                .map(|key| PandocBackend::pandoc_key(key).replace('\'', "''"))
+                .collect();
+            metadata.push(format!("nocite: '{}'", keys.join(", ")));
+        }
+        metadata.push("link-citations: true".to_string());
+
+        let first_chapter = book.items.iter_mut().find_map(|item| match item {
+            BookItem::Chapter(ch) if ch.path.is_some() => Some(ch),
+            _ => None,
+        });
+        if let Some(chapter) = first_chapter {
+            chapter.content = format!("---\n{}\n---\n\n{}", metadata.join("\n"), chapter.content);
+        }
+        Ok(())
+    }
+
     fn create_bibliography_chapter(
         title: String,
         js_html_part: String,
@@ -137,13 +194,8 @@ impl Preprocessor for Bibliography {
             .register_template_string("references", &config.bib_hb_html)
             .context("Failed to register references template. Check your 'hb-tpl' configuration for valid Handlebars syntax")?;
         handlebars
-            .register_template_string(
-                "chapter_refs",
-                config::DEFAULT_CHAPTER_REFS_FOOTER_HB_TEMPLATE,
-            )
-            .context(
-                "Failed to register chapter_refs template. This is a bug - please report it",
-            )?;
+            .register_template_string("chapter_refs", &config.chapter_refs_hb_html)
+            .context("Failed to register chapter_refs template. Check your 'chapter-refs-tpl' configuration for valid Handlebars syntax")?;
         handlebars
             .register_template_string("citation", &config.cite_hb_html)
             .context("Failed to register citation template. Check your 'cite-hb-tpl' configuration for valid Handlebars syntax")?;
@@ -160,6 +212,14 @@ impl Preprocessor for Bibliography {
         }
 
         let (bib_content, format) = bib_result?;
+        // Kept for the download link, as the parsed entries lose them
+        let definitions: Vec<String> = match format {
+            BibFormat::BibTeX => parser::bibtex_definitions(&bib_content)
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            BibFormat::Yaml => Vec::new(),
+        };
 
         let bibliography = parser::parse_bibliography(bib_content, format);
         if bibliography.is_err() {
@@ -172,69 +232,234 @@ impl Preprocessor for Bibliography {
 
         let mut bib = bibliography.unwrap();
 
-        // Create the appropriate backend based on configuration
-        let backend: Box<dyn crate::backend::BibliographyBackend> = match config.backend {
-            BackendMode::Custom => {
-                tracing::debug!("Using Custom (Handlebars) backend for rendering");
-                Box::new(CustomBackend::new(&handlebars))
-            }
-            BackendMode::Csl => {
-                tracing::debug!(
-                    "Using CSL backend with style '{}'",
-                    config.csl_style.as_deref().unwrap_or("apa")
-                );
-                let style = config.csl_style.as_deref().unwrap_or("apa");
-                Box::new(
-                    CslBackend::new(style.to_string())
-                        .context("Failed to initialize CSL backend")?,
-                )
+        let output = config.output_format(&ctx.renderer);
+        tracing::info!(
+            "Output format for renderer '{}': {:?}",
+            ctx.renderer,
+            output
+        );
+
+        // Create the appropriate backend based on configuration and output format
+        let backend: Box<dyn BibliographyBackend> = match output {
+            OutputFormat::Pandoc => Box::new(PandocBackend::new()),
+            OutputFormat::Latex => Box::new(LatexBackend::new()),
+            OutputFormat::Epub => {
+                let csl = match config.backend {
+                    BackendMode::Custom => None,
+                    BackendMode::Csl => {
+                        let style = config.csl_style.as_deref().unwrap_or("apa");
+                        Some(
+                            CslBackend::new(style.to_string())
+                                .context("Failed to initialize CSL backend")?,
+                        )
+                    }
+                };
+                Box::new(EpubBackend::new(csl))
             }
+            OutputFormat::Markdown => Box::new(MarkdownBackend::new()),
+            OutputFormat::Html => match config.backend {
+                BackendMode::Custom => {
+                    tracing::debug!("Using Custom (Handlebars) backend for rendering");
+                    let backend = CustomBackend::new(&handlebars);
+                    Box::new(if config.copy_bib {
+                        backend.with_copy_button(format)
+                    } else {
+                        backend
+                    })
+                }
+                BackendMode::Csl => {
+                    tracing::debug!(
+                        "Using CSL backend with style '{}'",
+                        config.csl_style.as_deref().unwrap_or("apa")
+                    );
+                    let style = config.csl_style.as_deref().unwrap_or("apa");
+                    Box::new(
+                        CslBackend::new(style.to_string())
+                            .context("Failed to initialize CSL backend")?,
+                    )
+                }
+            },
+        };
+        let backend: Box<dyn BibliographyBackend> = if backend.supports_renderer(output) {
+            backend
+        } else {
+            tracing::info!(
+                "{} backend doesn't support renderer '{}'. Using plain Markdown",
+                backend.name(),
+                ctx.renderer
+            );
+            Box::new(MarkdownBackend::new())
         };
 
         tracing::info!("Backend initialized: {}", backend.name());
         tracing::debug!("Citation syntax: {:?}", config.citation_syntax);
 
+        // Only HTML and EPUB chapter lists show the numbers cited in their chapter
+        let numbering = match output {
+            OutputFormat::Html | OutputFormat::Epub => config.numbering,
+            _ => Numbering::Global,
+        };
+
+        // LaTeX and Pandoc output have no chapter lists of their own
+        let link_in_chapter = (config.link_in_chapters
+            || config.numbering == Numbering::PerChapter)
+            && !matches!(output, OutputFormat::Latex | OutputFormat::Pandoc);
+
         // First, expand citations to assign indices to BibItems
-        let citation_result = citation::expand_cite_references_in_book(
+        let mut citation_result = citation::expand_cite_references_in_book(
             &mut book,
             &mut bib,
             backend.as_ref(),
             &config.citation_syntax,
+            numbering,
+            link_in_chapter,
+            &config.nocite,
         );
 
-        // Then add per-chapter bibliographies (now items have correct indices)
-        if config.add_bib_in_each_chapter {
-            let chapter_refs_header = handlebars
-                .render("chapter_refs", &String::new())
-                .context("Failed to render chapter_refs header")?;
-
-            citation::add_bib_at_end_of_chapters(
-                &mut book,
-                &mut bib,
-                backend.as_ref(),
-                &chapter_refs_header,
-                config.order.clone(),
-                &citation_result.per_chapter,
-                &config.css_html,
-            );
-        }
+        let bib_chapter = match output {
+            OutputFormat::Pandoc => {
+                Bibliography::add_pandoc_metadata(
+                    ctx,
+                    &config,
+                    &mut book,
+                    &bib,
+                    &citation_result.nocite,
+                )?;
+                Bibliography::create_bibliography_chapter(
+                    config.title,
+                    String::new(),
+                    String::new(),
+                    PANDOC_REFS_DIV.to_string(),
+                )
+            }
+            OutputFormat::Latex => Bibliography::create_bibliography_chapter(
+                config.title,
+                String::new(),
+                String::new(),
+                LatexBackend::print_bibliography(config.cited_only, &citation_result.nocite),
+            ),
+            OutputFormat::Epub | OutputFormat::Markdown => {
+                // Back-references are plain links, fine in EPUB but not in plain Markdown
+                if output == OutputFormat::Markdown || !config.backrefs {
+                    citation_result.backrefs.clear();
+                }
+                if config.add_bib_in_each_chapter {
+                    citation::add_bib_at_end_of_chapters(
+                        &mut book,
+                        &mut bib,
+                        backend.as_ref(),
+                        &|_| MARKDOWN_CHAPTER_REFS_HEADER.to_string(),
+                        config.order.clone(),
+                        &citation_result,
+                        "",
+                    );
+                }
+                let bib_content = renderer::generate_bibliography_html(
+                    &bib,
+                    &citation_result.all_cited,
+                    config.cited_only,
+                    backend.as_ref(),
+                    config.order,
+                    &citation_result.backrefs,
+                    "",
+                );
+                Bibliography::create_bibliography_chapter(
+                    config.title,
+                    String::new(),
+                    String::new(),
+                    bib_content,
+                )
+            }
+            OutputFormat::Html => {
+                if config.cite_popover {
+                    citation::add_citation_popovers(
+                        &mut book,
+                        &bib,
+                        backend.as_ref(),
+                        &citation_result.per_chapter,
+                        &config.js_html,
+                    );
+                }
 
-        let bib_content_html = renderer::generate_bibliography_html(
-            &bib,
-            &citation_result.all_cited,
-            config.cited_only,
-            backend.as_ref(),
-            config.order,
-        );
+                if !config.backrefs {
+                    citation_result.backrefs.clear();
+                }
 
-        let bib_chapter = Bibliography::create_bibliography_chapter(
-            config.title,
-            config.js_html,
-            config.css_html,
-            bib_content_html,
-        );
+                // Then add per-chapter bibliographies (now items have correct indices)
+                if config.add_bib_in_each_chapter {
+                    let book_title = ctx.config.book.title.clone().unwrap_or_default();
+                    let chapter_refs_header = |chapter: &ChapterCitations| {
+                        let refs = ChapterRefs {
+                            chapter,
+                            book_title: &book_title,
+                        };
+                        handlebars
+                            .render("chapter_refs", &refs)
+                            .unwrap_or_else(|e| {
+                                tracing::error!(
+                                    "Failed to render chapter_refs header for '{}': {}",
+                                    chapter.chapter_name,
+                                    e
+                                );
+                                String::new()
+                            })
+                    };
 
-        book.push_item(bib_chapter);
+                    citation::add_bib_at_end_of_chapters(
+                        &mut book,
+                        &mut bib,
+                        backend.as_ref(),
+                        &chapter_refs_header,
+                        config.order.clone(),
+                        &citation_result,
+                        &format!("{}{}", config.js_html, config.css_html),
+                    );
+                }
+
+                let download_html = if config.download_bib {
+                    renderer::generate_download_link(
+                        &bib,
+                        &definitions,
+                        &citation_result.all_cited,
+                        config.cited_only,
+                        format,
+                        BIB_OUT_FILE,
+                    )
+                } else {
+                    String::new()
+                };
+
+                let metadata_html = renderer::generate_citation_metadata(
+                    &bib,
+                    &citation_result.all_cited,
+                    config.cited_only,
+                    config.citation_metadata,
+                );
+
+                let bib_content_html = renderer::generate_bibliography_html(
+                    &bib,
+                    &citation_result.all_cited,
+                    config.cited_only,
+                    backend.as_ref(),
+                    config.order,
+                    &citation_result.backrefs,
+                    "",
+                );
+
+                Bibliography::create_bibliography_chapter(
+                    config.title,
+                    config.js_html,
+                    config.css_html,
+                    format!("{download_html}{bib_content_html}{metadata_html}"),
+                )
+            }
+        };
+
+        citation::remove_chapter_bibliography_directives(&mut book);
+
+        if config.global_bib {
+            book.push_item(bib_chapter);
+        }
 
         Ok(book)
     }
@@ -244,5 +469,10 @@ impl Preprocessor for Bibliography {
     }
 }
 
+/// Single-quote a path for a YAML scalar.
+fn yaml_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "''"))
+}
+
 #[cfg(test)]
 mod tests;
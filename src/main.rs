@@ -1,10 +1,21 @@
 use std::io;
+use std::path::PathBuf;
 use std::process;
 
-use clap::{Arg, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use mdbook_preprocessor::errors::Error;
 use mdbook_preprocessor::{parse_input, Preprocessor};
 
+use mdbook_bib::commands::add::{self, AddOptions};
+use mdbook_bib::commands::convert::{self, TargetFormat, FORMATS};
+use mdbook_bib::commands::export::{self, ExportOptions};
+use mdbook_bib::commands::init::{self, InitOptions, BACKENDS, SYNTAXES};
+use mdbook_bib::commands::keys;
+use mdbook_bib::commands::lsp;
+use mdbook_bib::commands::render::{self, RenderOptions, VARIANTS};
+use mdbook_bib::commands::stats;
+use mdbook_bib::commands::styles::{self, STYLE_FORMATS};
+
 pub fn make_app() -> Command {
     Command::new("bib")
         .about(
@@ -16,6 +27,223 @@ pub fn make_app() -> Command {
                 .arg(Arg::new("renderer").required(true))
                 .about("Check whether a renderer is supported by this preprocessor"),
         )
+        .subcommand(
+            Command::new("check")
+                .arg(book_dir_arg())
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .action(ArgAction::SetTrue)
+                        .help("Fail on warnings (e.g. uncited entries) too"),
+                )
+                .about(
+                    "Check the citations of a book against its bibliography without building it",
+                ),
+        )
+        .subcommand(
+            Command::new("styles")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(STYLE_FORMATS.to_vec())
+                        .help("Only list styles with this citation format"),
+                )
+                .arg(json_arg())
+                .about("List the bundled CSL styles with their citation format"),
+        )
+        .subcommand(
+            Command::new("render")
+                .arg(
+                    Arg::new("bib")
+                        .long("bib")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Bibliography file (.bib or .yaml)"),
+                )
+                .arg(
+                    Arg::new("style")
+                        .long("style")
+                        .help("CSL style; the Handlebars templates are used without one"),
+                )
+                .arg(
+                    Arg::new("keys")
+                        .long("keys")
+                        .value_delimiter(',')
+                        .help("Comma-separated keys to cite [default: all entries]"),
+                )
+                .arg(
+                    Arg::new("variant")
+                        .long("variant")
+                        .default_value("standard")
+                        .value_parser(VARIANTS.iter().map(|(name, _)| *name).collect::<Vec<_>>())
+                        .help("Citation variant"),
+                )
+                .arg(
+                    Arg::new("hb-tpl")
+                        .long("hb-tpl")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Handlebars template for references"),
+                )
+                .arg(
+                    Arg::new("cite-hb-tpl")
+                        .long("cite-hb-tpl")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Handlebars template for citations"),
+                )
+                .arg(
+                    Arg::new("text")
+                        .long("text")
+                        .action(ArgAction::SetTrue)
+                        .help("Print plain text instead of HTML"),
+                )
+                .about("Preview the citations and references of entries with a style or templates"),
+        )
+        .subcommand(
+            Command::new("convert")
+                .arg(
+                    Arg::new("input")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Bibliography file to convert (.bib or .yaml)"),
+                )
+                .arg(to_arg().help(
+                    "Output format [default: from the output file, or the other of BibTeX and YAML]",
+                ))
+                .arg(output_arg())
+                .about("Convert a bibliography between BibTeX, hayagriva YAML and CSL-JSON"),
+        )
+        .subcommand(
+            Command::new("export")
+                .arg(book_dir_arg())
+                .arg(
+                    Arg::new("chapter")
+                        .long("chapter")
+                        .action(ArgAction::Append)
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Only export the citations of this chapter (repeatable)"),
+                )
+                .arg(to_arg().help(
+                    "Output format [default: from the output file, or the bibliography's format]",
+                ))
+                .arg(output_arg())
+                .arg(
+                    Arg::new("by-citation")
+                        .long("by-citation")
+                        .action(ArgAction::SetTrue)
+                        .help("Order entries by first citation instead of as in the bibliography"),
+                )
+                .about("Export the entries cited in a book as a standalone bibliography"),
+        )
+        .subcommand(
+            Command::new("keys")
+                .arg(book_dir_arg())
+                .arg(json_arg().help("Print the entries with their title, authors, year and type as JSON"))
+                .about("List the citation keys of a book's bibliography, for editor completion"),
+        )
+        .subcommand(
+            Command::new("stats")
+                .arg(book_dir_arg())
+                .arg(json_arg())
+                .about("Report how often entries are cited, per entry, chapter, year and type"),
+        )
+        .subcommand(
+            Command::new("init")
+                .arg(book_dir_arg())
+                .arg(
+                    Arg::new("bibliography")
+                        .long("bibliography")
+                        .default_value("references.bib")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Bibliography to create in the source directory (.bib or .yaml)"),
+                )
+                .arg(
+                    Arg::new("backend")
+                        .long("backend")
+                        .value_parser(BACKENDS.to_vec())
+                        .help("Rendering backend [default: csl with --style, else custom]"),
+                )
+                .arg(
+                    Arg::new("style")
+                        .long("style")
+                        .help("Bundled CSL style of the csl backend [default: apa]"),
+                )
+                .arg(
+                    Arg::new("syntax")
+                        .long("syntax")
+                        .default_value("default")
+                        .value_parser(SYNTAXES.to_vec())
+                        .help("Citation syntax"),
+                )
+                .arg(
+                    Arg::new("templates")
+                        .long("templates")
+                        .action(ArgAction::SetTrue)
+                        .help("Copy the built-in templates, style sheet and script to customize them"),
+                )
+                .about("Add bibliography support to a book, with a starter bibliography"),
+        )
+        .subcommand(
+            Command::new("add")
+                .arg(
+                    Arg::new("identifier")
+                        .required(true)
+                        .help("DOI, ISBN or arXiv identifier of the work"),
+                )
+                .arg(book_dir_arg())
+                .arg(
+                    Arg::new("key")
+                        .long("key")
+                        .help("Citation key [default: from key-pattern]"),
+                )
+                .arg(
+                    Arg::new("base-url")
+                        .long("base-url")
+                        .help(
+                            "Metadata service to query [default: https://doi.org, \
+                            https://openlibrary.org or https://export.arxiv.org]",
+                        ),
+                )
+                .about("Look up a DOI, ISBN or arXiv paper and append it to the bibliography"),
+        )
+        .subcommand(
+            Command::new("lsp")
+                .arg(
+                    Arg::new("dir")
+                        .value_name("BOOK_DIR")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Root directory of the book [default: the editor's workspace]"),
+                )
+                .about("Run a language server completing, checking and resolving citations"),
+        )
+}
+
+fn json_arg() -> Arg {
+    Arg::new("json")
+        .long("json")
+        .action(ArgAction::SetTrue)
+        .help("Print JSON instead of a table")
+}
+
+fn to_arg() -> Arg {
+    Arg::new("to")
+        .long("to")
+        .value_parser(FORMATS.iter().map(|(name, _)| *name).collect::<Vec<_>>())
+}
+
+fn output_arg() -> Arg {
+    Arg::new("output")
+        .short('o')
+        .long("output")
+        .value_parser(value_parser!(PathBuf))
+        .help("File to write instead of standard output")
+}
+
+fn book_dir_arg() -> Arg {
+    Arg::new("dir")
+        .value_name("BOOK_DIR")
+        .default_value(".")
+        .value_parser(value_parser!(PathBuf))
+        .help("Root directory of the book, holding book.toml")
 }
 
 fn main() {
@@ -27,6 +255,78 @@ fn main() {
 
     if let Some(sub_args) = matches.subcommand_matches("supports") {
         handle_supports(&preprocessor, sub_args);
+    } else if let Some(sub_args) = matches.subcommand_matches("check") {
+        handle_check(sub_args);
+    } else if let Some(sub_args) = matches.subcommand_matches("styles") {
+        let format = sub_args.get_one::<String>("format").map(String::as_str);
+        exit_on_error(styles::run(format, sub_args.get_flag("json")));
+    } else if let Some(sub_args) = matches.subcommand_matches("render") {
+        exit_on_error(render::run(&render_options(sub_args)));
+    } else if let Some(sub_args) = matches.subcommand_matches("convert") {
+        exit_on_error(convert::run(
+            sub_args.get_one::<PathBuf>("input").expect("Required"),
+            target_format(sub_args),
+            sub_args.get_one::<PathBuf>("output").map(PathBuf::as_path),
+        ));
+    } else if let Some(sub_args) = matches.subcommand_matches("export") {
+        exit_on_error(export::run(&ExportOptions {
+            book_dir: sub_args
+                .get_one::<PathBuf>("dir")
+                .expect("Has a default")
+                .clone(),
+            chapters: sub_args
+                .get_many::<PathBuf>("chapter")
+                .map(|chapters| chapters.cloned().collect())
+                .unwrap_or_default(),
+            to: target_format(sub_args),
+            output: sub_args.get_one::<PathBuf>("output").cloned(),
+            by_citation: sub_args.get_flag("by-citation"),
+        }));
+    } else if let Some(sub_args) = matches.subcommand_matches("keys") {
+        exit_on_error(keys::run(
+            sub_args.get_one::<PathBuf>("dir").expect("Has a default"),
+            sub_args.get_flag("json"),
+        ));
+    } else if let Some(sub_args) = matches.subcommand_matches("stats") {
+        exit_on_error(stats::run(
+            sub_args.get_one::<PathBuf>("dir").expect("Has a default"),
+            sub_args.get_flag("json"),
+        ));
+    } else if let Some(sub_args) = matches.subcommand_matches("init") {
+        exit_on_error(init::run(&InitOptions {
+            book_dir: sub_args
+                .get_one::<PathBuf>("dir")
+                .expect("Has a default")
+                .clone(),
+            bibliography: sub_args
+                .get_one::<PathBuf>("bibliography")
+                .expect("Has a default")
+                .clone(),
+            backend: sub_args.get_one::<String>("backend").cloned(),
+            csl_style: sub_args.get_one::<String>("style").cloned(),
+            citation_syntax: sub_args
+                .get_one::<String>("syntax")
+                .expect("Has a default")
+                .clone(),
+            templates: sub_args.get_flag("templates"),
+        }));
+    } else if let Some(sub_args) = matches.subcommand_matches("add") {
+        exit_on_error(add::run(&AddOptions {
+            book_dir: sub_args
+                .get_one::<PathBuf>("dir")
+                .expect("Has a default")
+                .clone(),
+            identifier: sub_args
+                .get_one::<String>("identifier")
+                .expect("Required")
+                .clone(),
+            key: sub_args.get_one::<String>("key").cloned(),
+            base_url: sub_args.get_one::<String>("base-url").cloned(),
+        }));
+    } else if let Some(sub_args) = matches.subcommand_matches("lsp") {
+        exit_on_error(lsp::run(
+            sub_args.get_one::<PathBuf>("dir").map(PathBuf::as_path),
+        ));
     } else if let Err(e) = handle_preprocessing(&preprocessor) {
         eprintln!("Errors: {e}");
         process::exit(1);
@@ -86,6 +386,60 @@ fn handle_preprocessing(pre: &dyn Preprocessor) -> Result<(), Error> {
     Ok(())
 }
 
+fn render_options(sub_args: &ArgMatches) -> RenderOptions {
+    let variant = sub_args
+        .get_one::<String>("variant")
+        .expect("Has a default");
+    RenderOptions {
+        bib: sub_args
+            .get_one::<PathBuf>("bib")
+            .expect("Required")
+            .clone(),
+        style: sub_args.get_one::<String>("style").cloned(),
+        keys: sub_args
+            .get_many::<String>("keys")
+            .map(|keys| keys.cloned().collect())
+            .unwrap_or_default(),
+        variant: VARIANTS
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map(|(_, variant)| *variant)
+            .expect("Checked by clap"),
+        hb_tpl: sub_args.get_one::<PathBuf>("hb-tpl").cloned(),
+        cite_hb_tpl: sub_args.get_one::<PathBuf>("cite-hb-tpl").cloned(),
+        text: sub_args.get_flag("text"),
+    }
+}
+
+fn target_format(sub_args: &ArgMatches) -> Option<TargetFormat> {
+    sub_args.get_one::<String>("to").map(|to| {
+        FORMATS
+            .iter()
+            .find(|(name, _)| name == to)
+            .map(|(_, format)| *format)
+            .expect("Checked by clap")
+    })
+}
+
+fn exit_on_error(result: Result<(), Error>) {
+    if let Err(e) = result {
+        eprintln!("Errors: {e:#}");
+        process::exit(1);
+    }
+}
+
+fn handle_check(sub_args: &ArgMatches) -> ! {
+    let dir = sub_args.get_one::<PathBuf>("dir").expect("Has a default");
+    match mdbook_bib::commands::check::run(dir, sub_args.get_flag("strict")) {
+        Ok(true) => process::exit(0),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Errors: {e:#}");
+            process::exit(2);
+        }
+    }
+}
+
 fn handle_supports(pre: &dyn Preprocessor, sub_args: &ArgMatches) -> ! {
     let renderer = sub_args
         .get_one::<String>("renderer")
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::sync::Arc;
 
 /// Bibliography item representation.
@@ -24,6 +25,9 @@ pub struct BibItem {
     pub url: Option<String>,
     /// The item's index for first citation in the book.
     pub index: Option<u32>,
+    /// Number of times the item is cited in the book.
+    #[serde(default)]
+    pub citation_count: u32,
 
     // === Extended fields from hayagriva (new, all optional for backward compatibility) ===
     /// Entry type (Article, Book, Inproceedings, etc.).
@@ -78,6 +82,14 @@ pub struct BibItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub organization: Option<String>,
 
+    /// Original BibTeX/YAML text of the entry, as written in the bibliography file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_source: Option<String>,
+
+    /// Byte range of `raw_source` in the bibliography file.
+    #[serde(skip)]
+    pub source_span: Option<Range<usize>>,
+
     // === Internal fields for CSL rendering ===
     /// Original hayagriva Entry for CSL rendering.
     /// This is kept for CSL backend to use with BibliographyDriver.
@@ -107,6 +119,7 @@ impl BibItem {
             summary,
             url,
             index: None,
+            citation_count: 0,
             // Extended fields default to None
             entry_type: None,
             doi: None,
@@ -121,9 +134,62 @@ impl BibItem {
             edition: None,
             note: None,
             organization: None,
+            raw_source: None,
+            source_span: None,
             hayagriva_entry: None,
         }
     }
+
+    /// Author names, read from the hayagriva entry when available and from `authors`
+    /// otherwise. The "N/A" placeholder of entries without authors is skipped.
+    pub fn author_names(&self) -> Vec<AuthorName> {
+        match &self.hayagriva_entry {
+            Some(entry) => entry
+                .authors()
+                .unwrap_or_default()
+                .iter()
+                .filter(|person| !person.name.is_empty())
+                .map(|person| AuthorName {
+                    family: person.name.clone(),
+                    given: person.given_name.clone(),
+                    prefix: person.prefix.clone(),
+                })
+                .collect(),
+            None => self
+                .authors
+                .iter()
+                .filter_map(|parts| {
+                    let family = parts.first()?;
+                    (family != "N/A" && !family.is_empty()).then(|| AuthorName {
+                        family: family.clone(),
+                        given: parts.get(1).cloned(),
+                        prefix: None,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Name of an author, split in its family name, given name and particle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorName {
+    /// Family name without its particle (e.g. "Rossum").
+    pub family: String,
+    /// Given name (e.g. "Guido").
+    pub given: Option<String>,
+    /// Particle preceding the family name (e.g. "van").
+    pub prefix: Option<String>,
+}
+
+impl AuthorName {
+    /// Family name with its particle, as written in running text (e.g. "van Rossum").
+    pub fn full_family(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{prefix} {}", self.family),
+            None => self.family.clone(),
+        }
+    }
 }
 
 /// Citation context for rendering.
@@ -134,4 +200,61 @@ pub struct Citation {
     /// Citation variant as string for template use: "standard", "author_in_text", "parenthetical", "suppress_author"
     #[serde(default)]
     pub variant: String,
+    /// Unique id of this inline citation, target of the bibliography back-references
+    #[serde(default)]
+    pub anchor: String,
+    /// Id of the cited entry on the page at `path`
+    #[serde(default)]
+    pub entry_anchor: String,
+}
+
+/// Location in the book where a bibliography entry is cited.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackReference {
+    /// Display label of the citing chapter (e.g. "Ch. 2" or the chapter name).
+    pub label: String,
+    /// Source path of the citing chapter.
+    pub chapter_path: String,
+    /// Id of the first inline citation of the entry in that chapter.
+    pub anchor: String,
+    /// Link to the citation, relative to the page rendering the bibliography.
+    pub href: String,
+}
+
+/// Reference context for rendering a bibliography entry.
+///
+/// The item fields are flattened, so templates keep using `{{citation_key}}`, `{{title}}`, etc.
+#[derive(Debug, Clone, Serialize)]
+pub struct Reference<'a> {
+    #[serde(flatten)]
+    pub item: &'a BibItem,
+    /// Places in the book where the entry is cited (empty if back-references are disabled)
+    pub backrefs: &'a [BackReference],
+    /// Id of the entry: its citation key, or a distinct id in chapter reference lists
+    pub entry_anchor: &'a str,
+    /// Label of the button copying the entry's source, when enabled with `copy-bib`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copy_label: Option<&'a str>,
+}
+
+/// Citations of a chapter getting its own reference list.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterCitations {
+    /// Name of the chapter.
+    pub chapter_name: String,
+    /// Number of inline citations in the chapter.
+    pub citation_count: u32,
+    /// Number of entries listed, i.e. distinct entries cited in the chapter.
+    pub entry_count: usize,
+}
+
+/// Context for rendering the header of a chapter's reference list.
+///
+/// The chapter fields are flattened, so templates use `{{chapter_name}}`, `{{citation_count}}`, etc.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterRefs<'a> {
+    #[serde(flatten)]
+    pub chapter: &'a ChapterCitations,
+    /// Title of the book (empty if not set in book.toml)
+    pub book_title: &'a str,
 }
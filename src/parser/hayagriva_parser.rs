@@ -7,6 +7,8 @@ use std::sync::Arc;
 
 use crate::models::BibItem;
 
+use super::raw_source::extract_raw_sources;
+
 /// Parse bibliography content using hayagriva.
 /// Supports both BibTeX/BibLaTeX and YAML formats.
 pub fn parse_bibliography(
@@ -28,6 +30,8 @@ pub fn parse_bibliography(
 
     tracing::info!("{} bibliography items read", bibliography.len());
 
+    let mut raw_sources = extract_raw_sources(&raw_content, format);
+
     let result: IndexMap<String, BibItem> = bibliography
         .iter()
         .map(|entry| {
@@ -61,6 +65,10 @@ pub fn parse_bibliography(
             let edition = extract_edition(entry);
             let note = extract_note(entry);
             let organization = extract_organization(entry);
+            let source_span = raw_sources.remove(&citation_key);
+            let raw_source = source_span
+                .as_ref()
+                .map(|span| raw_content[span.clone()].to_string());
 
             tracing::debug!(
                 "Entry {}: processed - title='{}', type={:?}, authors={:?}, year='{:?}', month='{:?}'",
@@ -83,6 +91,7 @@ pub fn parse_bibliography(
                     summary,
                     url,
                     index: None,
+                    citation_count: 0,
                     // Extended fields
                     entry_type,
                     doi,
@@ -97,6 +106,8 @@ pub fn parse_bibliography(
                     edition,
                     note,
                     organization,
+                    raw_source,
+                    source_span,
                     // Store original hayagriva Entry for CSL rendering
                     hayagriva_entry: Some(Arc::new(entry.clone())),
                 },
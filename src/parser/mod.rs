@@ -1,4 +1,6 @@
 mod hayagriva_parser;
+mod raw_source;
 
 // Re-export hayagriva parser as the default parser
 pub use hayagriva_parser::{parse_bibliography, BibFormat};
+pub use raw_source::bibtex_definitions;
@@ -0,0 +1,214 @@
+//! Extraction of each entry's original source text from a bibliography file.
+//!
+//! hayagriva does not keep the text an entry was parsed from, so we scan the raw
+//! content ourselves to offer "copy BibTeX/YAML" and ".bib download" features.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use super::BibFormat;
+
+/// Map each citation key to the byte range of its entry in the bibliography source.
+pub fn extract_raw_sources(content: &str, format: BibFormat) -> HashMap<String, Range<usize>> {
+    match format {
+        BibFormat::BibTeX => extract_bibtex_sources(content),
+        BibFormat::Yaml => extract_yaml_sources(content),
+    }
+}
+
+/// Raw text of the `@string` and `@preamble` blocks of a BibTeX file, in order, which the
+/// entries may depend on.
+pub fn bibtex_definitions(content: &str) -> Vec<&str> {
+    bibtex_blocks(content)
+        .into_iter()
+        .filter(|(entry_type, _)| {
+            ["string", "preamble"]
+                .iter()
+                .any(|special| entry_type.eq_ignore_ascii_case(special))
+        })
+        .map(|(_, span)| &content[span])
+        .collect()
+}
+
+/// Keep the entries of `@type{key, ...}` blocks.
+fn extract_bibtex_sources(content: &str) -> HashMap<String, Range<usize>> {
+    let mut sources = HashMap::new();
+    for (entry_type, span) in bibtex_blocks(content) {
+        let is_entry = !["comment", "string", "preamble"]
+            .iter()
+            .any(|special| entry_type.eq_ignore_ascii_case(special));
+        if is_entry {
+            let source = &content[span.clone()];
+            let body = &source[source.find(['{', '(']).unwrap_or(0) + 1..];
+            if let Some(key) = body.split(',').next().map(str::trim) {
+                if !key.is_empty() {
+                    sources.insert(key.to_string(), span);
+                }
+            }
+        }
+    }
+    sources
+}
+
+/// Scan `@type{...}` (or `@type(...)`) blocks with their type, matching delimiters to find
+/// their end.
+///
+/// As in BibTeX, `"..."` values are opaque, and so are `{...}` values in `@type(...)`
+/// blocks; quotes only delimit values outside braces, and not in comments.
+fn bibtex_blocks(content: &str) -> Vec<(&str, Range<usize>)> {
+    let mut blocks = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = content[pos..].find('@') {
+        let start = pos + offset;
+        let after_at = start + 1;
+        let type_len = content[after_at..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(content.len() - after_at);
+        let entry_type = &content[after_at..after_at + type_len];
+
+        let rest = &content[after_at + type_len..];
+        let open = after_at + type_len + (rest.len() - rest.trim_start().len());
+        let close_delim = match content[open..].chars().next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => {
+                pos = after_at;
+                continue;
+            }
+        };
+
+        let quotes = !entry_type.eq_ignore_ascii_case("comment");
+        // Braces opened inside the block, and whether a quoted value is being read
+        let mut braces = 0;
+        let mut quoted = false;
+        let mut end = content.len();
+        for (idx, c) in content[open + 1..].char_indices() {
+            match c {
+                '{' => braces += 1,
+                '}' if braces > 0 => braces -= 1,
+                '"' if quotes && braces == 0 => quoted = !quoted,
+                c if c == close_delim && braces == 0 && !quoted => {
+                    end = open + 1 + idx + c.len_utf8();
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        blocks.push((entry_type, start..end));
+        pos = end;
+    }
+
+    blocks
+}
+
+/// Split a hayagriva YAML file into its top-level `key:` blocks.
+fn extract_yaml_sources(content: &str) -> HashMap<String, Range<usize>> {
+    let mut sources = HashMap::new();
+    // Key and start of the block being read
+    let mut current: Option<(String, usize)> = None;
+
+    let mut flush = |entry: Option<(String, usize)>, end: usize| {
+        if let Some((key, start)) = entry {
+            let end = start + content[start..end].trim_end().len();
+            sources.insert(key, start..end);
+        }
+    };
+
+    let mut line_start = 0;
+    for line in content.split_inclusive('\n') {
+        if let Some(key) = yaml_top_level_key(line) {
+            flush(current.take(), line_start);
+            current = Some((key, line_start));
+        }
+        line_start += line.len();
+    }
+    flush(current, content.len());
+
+    sources
+}
+
+/// Return the key if the line starts a top-level YAML mapping entry.
+fn yaml_top_level_key(line: &str) -> Option<String> {
+    if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == '-') {
+        return None;
+    }
+    let (key, _) = line.split_once(':')?;
+    let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+    (!key.is_empty()).then(|| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(content: &str, format: BibFormat) -> HashMap<String, &str> {
+        extract_raw_sources(content, format)
+            .into_iter()
+            .map(|(key, span)| (key, &content[span]))
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_bibtex_sources() {
+        let content = r#"
+% contact: someone@example.com
+@string{acm = {ACM}}
+@article{smith2024,
+    title = {Nested {Braces} (and parens)},
+    author = {Smith, John},
+}
+@book(jones2023, title = "A Book")
+"#;
+        let sources = sources(content, BibFormat::BibTeX);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources["smith2024"],
+            "@article{smith2024,\n    title = {Nested {Braces} (and parens)},\n    author = {Smith, John},\n}"
+        );
+        assert_eq!(
+            sources["jones2023"],
+            r#"@book(jones2023, title = "A Book")"#
+        );
+        assert_eq!(bibtex_definitions(content), vec!["@string{acm = {ACM}}"]);
+    }
+
+    #[test]
+    fn test_quoted_values_are_opaque() {
+        let content = r#"
+@misc(smiley, title = "Smiley :)", note = {Wink ;)})
+@comment{Say "hi}
+@article{quoted, title = "A {"} quote"}
+"#;
+        let sources = sources(content, BibFormat::BibTeX);
+        assert_eq!(
+            sources["smiley"],
+            r#"@misc(smiley, title = "Smiley :)", note = {Wink ;)})"#
+        );
+        assert_eq!(
+            sources["quoted"],
+            r#"@article{quoted, title = "A {"} quote"}"#
+        );
+    }
+
+    #[test]
+    fn test_extract_yaml_sources() {
+        let content =
+            "smith2024:\n  type: article\n  title: A Title\n\n\"jones2023\":\n  type: book\n";
+        let sources = sources(content, BibFormat::Yaml);
+        assert_eq!(sources.len(), 2);
+        assert_eq!(
+            sources["smith2024"],
+            "smith2024:\n  type: article\n  title: A Title"
+        );
+        assert_eq!(sources["jones2023"], "\"jones2023\":\n  type: book");
+
+        let content = content.replace('\n', "\r\n");
+        let crlf = extract_raw_sources(&content, BibFormat::Yaml);
+        assert_eq!(
+            &content[crlf["smith2024"].clone()],
+            "smith2024:\r\n  type: article\r\n  title: A Title"
+        );
+    }
+}
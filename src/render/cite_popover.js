@@ -0,0 +1,59 @@
+function initBibPopovers() {
+    var data = document.getElementById("bib-popover-data");
+    if (!data) {
+        return;
+    }
+    var refs = JSON.parse(data.textContent);
+
+    var popover = document.createElement("div");
+    popover.id = "bib-popover";
+    popover.className = "bib-popover";
+    popover.setAttribute("role", "tooltip");
+    popover.hidden = true;
+    document.body.appendChild(popover);
+
+    function show(link, key) {
+        popover.innerHTML = refs[key];
+        // Avoid duplicate ids with the entries in the page
+        popover.querySelectorAll("[id]").forEach(function(el) { el.removeAttribute("id"); });
+        popover.hidden = false;
+        var rect = link.getBoundingClientRect();
+        var left = Math.min(
+            rect.left + window.scrollX,
+            window.scrollX + document.documentElement.clientWidth - popover.offsetWidth - 8
+        );
+        popover.style.left = Math.max(window.scrollX + 8, left) + "px";
+        popover.style.top = (rect.bottom + window.scrollY + 6) + "px";
+    }
+
+    function hide() {
+        popover.hidden = true;
+    }
+
+    document.querySelectorAll("a[href*='#']").forEach(function(link) {
+        // Chapter reference lists prefix their entry ids
+        var key = decodeURIComponent(link.hash.slice(1)).replace(/^chapter-bib-/, "");
+        if (!Object.prototype.hasOwnProperty.call(refs, key)) {
+            return;
+        }
+        // The popover replaces the native tooltip
+        link.removeAttribute("title");
+        link.setAttribute("aria-describedby", "bib-popover");
+        link.addEventListener("mouseenter", function() { show(link, key); });
+        link.addEventListener("focus", function() { show(link, key); });
+        link.addEventListener("mouseleave", hide);
+        link.addEventListener("blur", hide);
+    });
+
+    document.addEventListener("keydown", function(event) {
+        if (event.key === "Escape") {
+            hide();
+        }
+    });
+}
+
+if (document.readyState === "loading") {
+    document.addEventListener("DOMContentLoaded", initBibPopovers);
+} else {
+    initBibPopovers();
+}
@@ -33,3 +33,11 @@ function copyToClipboard(text) {
         console.error('Error copying text: ', err);
     });
 }
+
+function copyBibSource(button) {
+    var entry = button.closest(".bib-entry");
+    var source = entry ? entry.querySelector(".bib-source") : null;
+    if (source) {
+        copyToClipboard(source.textContent);
+    }
+}
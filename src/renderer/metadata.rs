@@ -0,0 +1,349 @@
+//! Machine-readable citation metadata for reference managers and citation processors.
+//!
+//! Reference managers such as Zotero detect COinS spans (OpenURL ContextObjects in
+//! `<span class="Z3988">`) and schema.org JSON-LD, letting readers import the
+//! references of a page in one click.
+
+use hayagriva::types::EntryType;
+use serde_json::{json, Map, Value};
+
+use super::percent_encode;
+use crate::models::BibItem;
+
+/// Kind of publication, driving the OpenURL format and the schema.org type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PublicationKind {
+    /// Article in a journal, magazine or newspaper
+    JournalArticle,
+    /// Paper in conference proceedings
+    ConferencePaper,
+    /// Chapter or section of a book
+    BookChapter,
+    Book,
+    Thesis,
+    Report,
+    WebPage,
+    Other,
+}
+
+impl PublicationKind {
+    fn of(item: &BibItem) -> Self {
+        match &item.hayagriva_entry {
+            Some(entry) => {
+                let parent = entry.parents().first().map(|p| p.entry_type());
+                match (entry.entry_type(), parent) {
+                    (EntryType::Article, Some(EntryType::Proceedings | EntryType::Conference)) => {
+                        PublicationKind::ConferencePaper
+                    }
+                    (EntryType::Article, _) => PublicationKind::JournalArticle,
+                    (EntryType::Chapter, _) => PublicationKind::BookChapter,
+                    (EntryType::Book | EntryType::Anthology, _) => PublicationKind::Book,
+                    (EntryType::Thesis, _) => PublicationKind::Thesis,
+                    (EntryType::Report, _) => PublicationKind::Report,
+                    (EntryType::Web, _) => PublicationKind::WebPage,
+                    _ => PublicationKind::Other,
+                }
+            }
+            None => match item.entry_type.as_deref() {
+                Some("Article") => PublicationKind::JournalArticle,
+                Some("Chapter") => PublicationKind::BookChapter,
+                Some("Book" | "Anthology") => PublicationKind::Book,
+                Some("Thesis") => PublicationKind::Thesis,
+                Some("Report") => PublicationKind::Report,
+                Some("Web") => PublicationKind::WebPage,
+                _ => PublicationKind::Other,
+            },
+        }
+    }
+
+    fn schema_type(self) -> &'static str {
+        match self {
+            PublicationKind::JournalArticle | PublicationKind::ConferencePaper => {
+                "ScholarlyArticle"
+            }
+            PublicationKind::BookChapter => "Chapter",
+            PublicationKind::Book => "Book",
+            PublicationKind::Thesis => "Thesis",
+            PublicationKind::Report => "Report",
+            PublicationKind::WebPage => "WebPage",
+            PublicationKind::Other => "CreativeWork",
+        }
+    }
+}
+
+/// Title of the containing publication (journal, proceedings, book), if any.
+fn container_title(item: &BibItem) -> Option<String> {
+    item.hayagriva_entry
+        .as_ref()?
+        .parents()
+        .first()?
+        .title()
+        .map(|title| title.to_string())
+}
+
+/// Volume of the entry, falling back to its parent (journal volumes live there).
+fn volume(item: &BibItem) -> Option<String> {
+    item.volume.clone().or_else(|| {
+        item.hayagriva_entry
+            .as_ref()?
+            .parents()
+            .first()?
+            .volume()
+            .map(|volume| volume.to_string())
+    })
+}
+
+/// Publisher name, taken from the hayagriva entry when available.
+fn publisher_name(item: &BibItem) -> Option<String> {
+    item.hayagriva_entry
+        .as_ref()?
+        .publisher()?
+        .name()
+        .map(|name| name.to_string())
+}
+
+fn publication_date(item: &BibItem) -> Option<String> {
+    let year = item.pub_year.as_deref()?;
+    Some(match item.pub_month.as_deref() {
+        Some(month) => format!("{year}-{month}"),
+        None => year.to_string(),
+    })
+}
+
+/// Render a COinS span (OpenURL 1.0 KEV ContextObject) for an entry.
+pub fn coins_span(item: &BibItem) -> String {
+    let kind = PublicationKind::of(item);
+    let mut fields: Vec<(&str, String)> = vec![("ctx_ver", "Z39.88-2004".to_string())];
+
+    let (format, genre) = match kind {
+        PublicationKind::JournalArticle => ("journal", Some("article")),
+        PublicationKind::ConferencePaper => ("book", Some("proceeding")),
+        PublicationKind::BookChapter => ("book", Some("bookitem")),
+        PublicationKind::Book => ("book", Some("book")),
+        PublicationKind::Report => ("book", Some("report")),
+        _ => ("dc", None),
+    };
+    fields.push(("rft_val_fmt", format!("info:ofi/fmt:kev:mtx:{format}")));
+    if let Some(genre) = genre {
+        fields.push(("rft.genre", genre.to_string()));
+    }
+
+    let title = item.title.clone();
+    match kind {
+        PublicationKind::JournalArticle => {
+            fields.push(("rft.atitle", title));
+            if let Some(journal) = container_title(item) {
+                fields.push(("rft.jtitle", journal));
+            }
+        }
+        PublicationKind::ConferencePaper | PublicationKind::BookChapter => {
+            fields.push(("rft.atitle", title));
+            if let Some(book) = container_title(item) {
+                fields.push(("rft.btitle", book));
+            }
+        }
+        PublicationKind::Book | PublicationKind::Report => fields.push(("rft.btitle", title)),
+        _ => fields.push(("rft.title", title)),
+    }
+
+    let author_key = if format == "dc" {
+        "rft.creator"
+    } else {
+        "rft.au"
+    };
+    for author in item.author_names() {
+        let name = match &author.given {
+            Some(given) => format!("{}, {given}", author.full_family()),
+            None => author.full_family(),
+        };
+        fields.push((author_key, name));
+    }
+
+    if let Some(date) = publication_date(item) {
+        fields.push(("rft.date", date));
+    }
+    if let Some(volume) = volume(item) {
+        fields.push(("rft.volume", volume));
+    }
+    let optional = [
+        ("rft.issue", &item.issue),
+        ("rft.pages", &item.pages),
+        ("rft.edition", &item.edition),
+        ("rft.isbn", &item.isbn),
+        ("rft.issn", &item.issn),
+        ("rft.place", &item.address),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            fields.push((key, value.clone()));
+        }
+    }
+    if let Some(publisher) = publisher_name(item) {
+        let key = if format == "dc" {
+            "rft.publisher"
+        } else {
+            "rft.pub"
+        };
+        fields.push((key, publisher));
+    }
+    if let Some(doi) = &item.doi {
+        fields.push(("rft_id", format!("info:doi/{doi}")));
+    }
+    if let Some(url) = &item.url {
+        fields.push(("rft_id", url.clone()));
+    }
+
+    let context_object = fields
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&amp;");
+    format!("<span class=\"Z3988\" title=\"{context_object}\"></span>")
+}
+
+/// Build the schema.org JSON-LD description of an entry.
+pub fn json_ld(item: &BibItem) -> Value {
+    let kind = PublicationKind::of(item);
+    let mut node = Map::new();
+    node.insert("@type".into(), json!(kind.schema_type()));
+    node.insert("@id".into(), json!(format!("#{}", item.citation_key)));
+    node.insert("name".into(), json!(item.title));
+
+    let authors: Vec<Value> = item
+        .author_names()
+        .into_iter()
+        .map(|author| {
+            let mut person = json!({ "@type": "Person", "familyName": author.full_family() });
+            if let Some(given) = author.given {
+                person["givenName"] = json!(given);
+            }
+            person
+        })
+        .collect();
+    if !authors.is_empty() {
+        node.insert("author".into(), Value::Array(authors));
+    }
+
+    if let Some(date) = publication_date(item) {
+        node.insert("datePublished".into(), json!(date));
+    }
+    if let Some(container) = container_title(item) {
+        let container_type = match kind {
+            PublicationKind::JournalArticle => "Periodical",
+            PublicationKind::BookChapter => "Book",
+            _ => "CreativeWork",
+        };
+        node.insert(
+            "isPartOf".into(),
+            json!({ "@type": container_type, "name": container }),
+        );
+    }
+    if let Some(publisher) = publisher_name(item) {
+        node.insert(
+            "publisher".into(),
+            json!({ "@type": "Organization", "name": publisher }),
+        );
+    }
+    if let Some(doi) = &item.doi {
+        node.insert("identifier".into(), json!(format!("https://doi.org/{doi}")));
+        node.insert("sameAs".into(), json!(format!("https://doi.org/{doi}")));
+    }
+    if let Some(volume) = volume(item) {
+        node.insert("volumeNumber".into(), json!(volume));
+    }
+    let optional = [
+        ("url", &item.url),
+        ("issueNumber", &item.issue),
+        ("pagination", &item.pages),
+        ("bookEdition", &item.edition),
+        ("isbn", &item.isbn),
+        ("issn", &item.issn),
+        ("abstract", &item.summary),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            node.insert(key.into(), json!(value));
+        }
+    }
+
+    Value::Object(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_bibliography, BibFormat};
+
+    const BIB_SRC: &str = r#"
+@article{smith2024,
+    author = {Smith, John and Doe, Jane},
+    title = {Research on Bibliography Systems},
+    journal = {Journal of Documentation},
+    year = {2024},
+    volume = {10},
+    pages = {123-145},
+    doi = {10.1000/xyz123},
+}
+@book{rust_book,
+    author = {Klabnik, Steve and Nichols, Carol},
+    title = {The Rust Programming Language},
+    year = {2018},
+    isbn = {1593278284},
+    publisher = {No Starch Press},
+}
+"#;
+
+    #[test]
+    fn test_coins_span_for_journal_article() {
+        let bibliography = parse_bibliography(BIB_SRC.to_string(), BibFormat::BibTeX).unwrap();
+        let span = coins_span(&bibliography["smith2024"]);
+
+        assert!(span.starts_with("<span class=\"Z3988\" title=\"ctx_ver=Z39.88-2004&amp;"));
+        assert!(span.contains("rft_val_fmt=info%3Aofi%2Ffmt%3Akev%3Amtx%3Ajournal"));
+        assert!(span.contains("rft.genre=article"));
+        assert!(span.contains("rft.atitle=Research%20on%20Bibliography%20Systems"));
+        assert!(span.contains("rft.jtitle=Journal%20of%20Documentation"));
+        assert!(span.contains("rft.au=Smith%2C%20John&amp;rft.au=Doe%2C%20Jane"));
+        assert!(span.contains("rft.volume=10"));
+        assert!(span.contains("rft_id=info%3Adoi%2F10.1000%2Fxyz123"));
+    }
+
+    #[test]
+    fn test_json_ld_for_book() {
+        let bibliography = parse_bibliography(BIB_SRC.to_string(), BibFormat::BibTeX).unwrap();
+        let node = json_ld(&bibliography["rust_book"]);
+
+        assert_eq!(node["@type"], "Book");
+        assert_eq!(node["@id"], "#rust_book");
+        assert_eq!(node["name"], "The Rust Programming Language");
+        assert_eq!(node["author"][0]["familyName"], "Klabnik");
+        assert_eq!(node["author"][0]["givenName"], "Steve");
+        assert_eq!(node["datePublished"], "2018");
+        assert_eq!(node["isbn"], "1593278284");
+        assert_eq!(node["publisher"]["name"], "No Starch Press");
+    }
+
+    #[test]
+    fn test_metadata_keeps_name_particles_with_family_name() {
+        let src = "@book{python, author = {van Rossum, Guido}, title = {Python}, year = {1995}}";
+        let bibliography = parse_bibliography(src.to_string(), BibFormat::BibTeX).unwrap();
+        let item = &bibliography["python"];
+
+        assert!(coins_span(item).contains("rft.au=van%20Rossum%2C%20Guido"));
+        let node = json_ld(item);
+        assert_eq!(node["author"][0]["familyName"], "van Rossum");
+        assert_eq!(node["author"][0]["givenName"], "Guido");
+    }
+
+    #[test]
+    fn test_kind_from_entry_type_without_hayagriva_entry() {
+        let item = BibItem {
+            citation_key: "web".to_string(),
+            title: "A Page".to_string(),
+            entry_type: Some("Web".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(PublicationKind::of(&item), PublicationKind::WebPage);
+        assert!(coins_span(&item).contains("rft.title=A%20Page"));
+    }
+}
@@ -1,18 +1,28 @@
+mod metadata;
+
+use std::cmp::Ordering;
 use std::collections::HashSet;
 
 use indexmap::IndexMap;
+use mdbook_preprocessor::errors::Error;
 
 use crate::backend::BibliographyBackend;
-use crate::config::SortOrder;
-use crate::models::BibItem;
+use crate::commands::convert;
+use crate::config::{CitationMetadata, SortCriterion, SortDirection, SortField, SortOrder};
+use crate::models::{BackReference, BibItem};
+use crate::parser::BibFormat;
 
 /// Generate bibliography HTML from BibItems using the specified backend.
+///
+/// Entry ids are the citation keys, prefixed with `anchor_prefix`.
 pub fn generate_bibliography_html(
     bibliography: &IndexMap<String, BibItem>,
     cited: &HashSet<String>,
     cited_only: bool,
     backend: &dyn BibliographyBackend,
     order: SortOrder,
+    backrefs: &IndexMap<String, Vec<BackReference>>,
+    anchor_prefix: &str,
 ) -> String {
     let sorted: Vec<(&str, &BibItem)> = match order {
         SortOrder::None => bibliography.iter().map(|(k, v)| (k.as_str(), v)).collect(),
@@ -23,18 +33,9 @@ pub fn generate_bibliography_html(
             v
         }
         SortOrder::Author => {
-            let empty = "!".to_string();
             let mut v: Vec<(&str, &BibItem)> =
                 bibliography.iter().map(|(k, v)| (k.as_str(), v)).collect();
-            v.sort_by_cached_key(|item| {
-                let val: &str = item
-                    .1
-                    .authors
-                    .first()
-                    .map(|vec| vec.first().unwrap_or(&empty))
-                    .unwrap_or(&empty);
-                val
-            });
+            v.sort_by_cached_key(|item| first_author(item.1).unwrap_or_else(|| "!".to_string()));
             v
         }
         SortOrder::Index => {
@@ -43,12 +44,20 @@ pub fn generate_bibliography_html(
             v.sort_by_key(|item| item.1.index);
             v
         }
+        SortOrder::Spec(criteria) => {
+            let mut v: Vec<(&str, &BibItem)> =
+                bibliography.iter().map(|(k, v)| (k.as_str(), v)).collect();
+            v.sort_by(|a, b| compare_by_criteria(a.1, b.1, &criteria));
+            v
+        }
     };
 
     let mut content = String::new();
     for (key, value) in sorted {
         if !cited_only || cited.contains(key) {
-            match backend.format_reference(value) {
+            let item_backrefs = backrefs.get(key).map(Vec::as_slice).unwrap_or_default();
+            let anchor = format!("{anchor_prefix}{key}");
+            match backend.format_anchored_reference(value, item_backrefs, &anchor) {
                 Ok(html) => content.push_str(&html),
                 Err(e) => {
                     tracing::error!("Failed to format reference for '{}': {}", key, e);
@@ -63,3 +72,172 @@ pub fn generate_bibliography_html(
     tracing::debug!("Generated Bib Content: {:?}", content);
     content
 }
+
+/// Generate a link downloading the original source of the rendered entries as a single file.
+///
+/// The file starts with `definitions` (BibTeX `@string` and `@preamble` blocks) the entries
+/// may depend on. Returns an empty string when none of the entries kept their raw source.
+pub fn generate_download_link(
+    bibliography: &IndexMap<String, BibItem>,
+    definitions: &[String],
+    cited: &HashSet<String>,
+    cited_only: bool,
+    format: BibFormat,
+    file_stem: &str,
+) -> String {
+    let sources: Vec<&str> = bibliography
+        .iter()
+        .filter(|(key, _)| !cited_only || cited.contains(*key))
+        .filter_map(|(_, item)| item.raw_source.as_deref())
+        .collect();
+
+    if sources.is_empty() {
+        return String::new();
+    }
+
+    let extension = match format {
+        BibFormat::BibTeX => "bib",
+        BibFormat::Yaml => "yaml",
+    };
+    let blocks: Vec<&str> = definitions
+        .iter()
+        .map(String::as_str)
+        .chain(sources)
+        .collect();
+    let content = format!("{}\n", blocks.join("\n\n"));
+    format!(
+        "<a class=\"bib-download\" download=\"{file_stem}.{extension}\" \
+         href=\"data:text/plain;charset=utf-8,{}\">Download .{extension}</a>\n\n",
+        percent_encode(&content)
+    )
+}
+
+/// Generate machine-readable metadata (COinS spans and/or JSON-LD) for the rendered entries.
+pub fn generate_citation_metadata(
+    bibliography: &IndexMap<String, BibItem>,
+    cited: &HashSet<String>,
+    cited_only: bool,
+    mode: CitationMetadata,
+) -> String {
+    let items: Vec<&BibItem> = bibliography
+        .iter()
+        .filter(|(key, _)| !cited_only || cited.contains(*key))
+        .map(|(_, item)| item)
+        .collect();
+
+    if mode == CitationMetadata::None || items.is_empty() {
+        return String::new();
+    }
+
+    let mut content = String::new();
+    if matches!(mode, CitationMetadata::Coins | CitationMetadata::All) {
+        for item in &items {
+            content.push_str(&metadata::coins_span(item));
+            content.push('\n');
+        }
+    }
+    if matches!(mode, CitationMetadata::JsonLd | CitationMetadata::All) {
+        let graph: Vec<serde_json::Value> =
+            items.iter().map(|item| metadata::json_ld(item)).collect();
+        let json_ld = serde_json::json!({
+            "@context": "https://schema.org",
+            "@graph": graph,
+        });
+        content.push_str(&format!(
+            "<script type=\"application/ld+json\">{}</script>\n",
+            script_json(&json_ld)
+        ));
+    }
+
+    format!("\n{content}\n")
+}
+
+/// Generate a CSL-JSON array with every entry of the bibliography, written as `convert` does.
+pub fn generate_csl_json(bibliography: &IndexMap<String, BibItem>) -> Result<String, Error> {
+    let entries = bibliography
+        .values()
+        .filter_map(|item| item.hayagriva_entry.as_deref());
+    let items = convert::csl_json_items(entries, &mut Vec::new())?;
+    Ok(serde_json::to_string_pretty(&items)?)
+}
+
+/// Serialize a value as JSON to embed in a `<script>` element.
+pub(crate) fn script_json(value: &impl serde::Serialize) -> String {
+    // Escaping "</" keeps the JSON from closing its <script> element early
+    serde_json::to_string(value)
+        .unwrap_or_default()
+        .replace("</", "<\\/")
+}
+
+/// Percent-encode text for a `data:` URI, a COinS `title` attribute or a URL path segment,
+/// keeping only unreserved characters.
+pub(crate) fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Compare two items using a multi-key sort specification.
+///
+/// Criteria are applied in order; later criteria only break ties of earlier ones.
+/// Items missing a value for a criterion always sort after items that have one.
+fn compare_by_criteria(a: &BibItem, b: &BibItem, criteria: &[SortCriterion]) -> Ordering {
+    criteria
+        .iter()
+        .map(|criterion| compare_by_field(a, b, criterion))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn compare_by_field(a: &BibItem, b: &BibItem, criterion: &SortCriterion) -> Ordering {
+    let directed = |ordering: Ordering| match criterion.direction {
+        SortDirection::Asc => ordering,
+        SortDirection::Desc => ordering.reverse(),
+    };
+    match criterion.field {
+        SortField::Key => directed(a.citation_key.cmp(&b.citation_key)),
+        SortField::Author => compare_missing_last(first_author(a), first_author(b), directed),
+        SortField::Title => directed(a.title.to_lowercase().cmp(&b.title.to_lowercase())),
+        SortField::Year => compare_missing_last(
+            a.pub_year.as_deref().and_then(|y| y.parse::<i32>().ok()),
+            b.pub_year.as_deref().and_then(|y| y.parse::<i32>().ok()),
+            directed,
+        ),
+        SortField::Month => {
+            compare_missing_last(a.pub_month.as_ref(), b.pub_month.as_ref(), directed)
+        }
+        SortField::EntryType => {
+            compare_missing_last(a.entry_type.as_ref(), b.entry_type.as_ref(), directed)
+        }
+        SortField::Index => compare_missing_last(a.index, b.index, directed),
+        SortField::Citations => directed(a.citation_count.cmp(&b.citation_count)),
+    }
+}
+
+fn compare_missing_last<T: Ord>(
+    a: Option<T>,
+    b: Option<T>,
+    directed: impl Fn(Ordering) -> Ordering,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => directed(a.cmp(&b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Family name of the first author, without its particle ("van Rossum" sorts under R).
+fn first_author(item: &BibItem) -> Option<String> {
+    item.author_names()
+        .into_iter()
+        .next()
+        .map(|author| author.family)
+}
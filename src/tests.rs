@@ -9,6 +9,7 @@
 //! - [`backend`] - Custom and CSL backend tests
 //! - [`integration`] - Full book processing tests
 //! - [`edge_cases`] - Error handling and edge case tests
+//! - [`commands`] - Subcommands of the binary (`check`, ...)
 
 #[cfg(test)]
 mod common;
@@ -30,3 +31,6 @@ mod integration;
 
 #[cfg(test)]
 mod edge_cases;
+
+#[cfg(test)]
+mod commands;
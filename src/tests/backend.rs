@@ -7,11 +7,15 @@
 //! - Regression tests for output formats
 
 use super::common::{
-    create_citation_backend, create_references_backend, dummy_bibliography, yaml_bibliography,
+    create_citation_backend, create_references_backend, create_references_backend_with_template,
+    dummy_bibliography, yaml_bibliography, BibItemBuilder,
 };
 use crate::backend::{BibliographyBackend, CitationContext, CitationVariant, CslBackend};
-use crate::config::{CitationSyntax, SortOrder};
+use crate::config::{
+    CitationMetadata, CitationSyntax, SortCriterion, SortDirection, SortField, SortOrder,
+};
 use crate::parser::{self, BibFormat};
+use indexmap::IndexMap;
 use mdbook_preprocessor::book::Chapter;
 use rstest::rstest;
 use std::collections::HashSet;
@@ -43,6 +47,7 @@ fn regression_custom_citation_format() {
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     // Custom format: <a class="bib-cite" href="bibliography.html#key">key</a>
@@ -64,6 +69,8 @@ fn regression_custom_bibliography_html_structure() {
         false, // render all
         &backend,
         SortOrder::None,
+        &IndexMap::new(),
+        "",
     );
 
     // Verify expected structure elements
@@ -93,6 +100,8 @@ fn bibliography_render_all_vs_cited() {
         false,
         &backend,
         SortOrder::None,
+        &IndexMap::new(),
+        "",
     );
 
     assert!(html.contains("This is a bib entry!"));
@@ -104,6 +113,8 @@ fn bibliography_render_all_vs_cited() {
         true,
         &backend,
         SortOrder::None,
+        &IndexMap::new(),
+        "",
     );
 
     assert!(html.contains("This is a bib entry!"));
@@ -121,10 +132,272 @@ fn bibliography_renders_url_when_present() {
         false,
         &backend,
         SortOrder::None,
+        &IndexMap::new(),
+        "",
     );
     assert!(html.contains("href=\"https://doc.rust-lang.org/book/\""));
 }
 
+#[test]
+fn bibliography_multi_key_sort_spec() {
+    let mut bibliography = IndexMap::new();
+    for item in [
+        BibItemBuilder::article("old_b")
+            .title("Old B")
+            .authors(&["Brown, Bob"])
+            .year("2001")
+            .build(),
+        BibItemBuilder::article("new_z")
+            .title("New Z")
+            .authors(&["Zeta, Zoe"])
+            .year("2024")
+            .build(),
+        BibItemBuilder::misc("undated")
+            .title("Undated")
+            .authors(&["Adams, Ann"])
+            .build(),
+        BibItemBuilder::article("new_a")
+            .title("New A")
+            .authors(&["Adams, Ann"])
+            .year("2024")
+            .build(),
+    ] {
+        bibliography.insert(item.citation_key.clone(), item);
+    }
+
+    let backend = create_references_backend_with_template("{{citation_key}};");
+    let order = SortOrder::Spec(vec![
+        SortCriterion {
+            field: SortField::Year,
+            direction: SortDirection::Desc,
+        },
+        SortCriterion {
+            field: SortField::Author,
+            direction: SortDirection::Asc,
+        },
+    ]);
+
+    let html = crate::renderer::generate_bibliography_html(
+        &bibliography,
+        &HashSet::new(),
+        false,
+        &backend,
+        order,
+        &IndexMap::new(),
+        "",
+    );
+    // Newest first, ties broken by author, entries without a year last
+    assert_eq!(html, "new_a;new_z;old_b;undated;");
+}
+
+#[rstest]
+#[case::author(SortOrder::Author)]
+#[case::spec(SortOrder::Spec(vec![SortCriterion {
+    field: SortField::Author,
+    direction: SortDirection::Asc,
+}]))]
+fn bibliography_author_sort_ignores_name_particles(#[case] order: SortOrder) {
+    let src = r#"
+@book{wirth, author = {Wirth, Niklaus}, title = {Algorithms}, year = {1976}}
+@book{python, author = {van Rossum, Guido}, title = {Python}, year = {1995}}
+@book{cpp, author = {Stroustrup, Bjarne}, title = {C++}, year = {1985}}
+"#;
+    let bibliography = parser::parse_bibliography(src.to_string(), BibFormat::BibTeX).unwrap();
+    let backend = create_references_backend_with_template("{{citation_key}};");
+
+    let html = crate::renderer::generate_bibliography_html(
+        &bibliography,
+        &HashSet::new(),
+        false,
+        &backend,
+        order,
+        &IndexMap::new(),
+        "",
+    );
+    // "van Rossum" sorts under R, by its family name
+    assert_eq!(html, "python;cpp;wirth;");
+}
+
+#[test]
+fn bibliography_sort_by_citation_count() {
+    let mut bibliography = IndexMap::new();
+    for item in [
+        BibItemBuilder::misc("rare").citation_count(1).build(),
+        BibItemBuilder::misc("popular").citation_count(5).build(),
+        BibItemBuilder::misc("medium").citation_count(3).build(),
+    ] {
+        bibliography.insert(item.citation_key.clone(), item);
+    }
+
+    let backend = create_references_backend_with_template("{{citation_key}};");
+    let order = SortOrder::Spec(vec![SortCriterion {
+        field: SortField::Citations,
+        direction: SortDirection::Desc,
+    }]);
+
+    let html = crate::renderer::generate_bibliography_html(
+        &bibliography,
+        &HashSet::new(),
+        false,
+        &backend,
+        order,
+        &IndexMap::new(),
+        "",
+    );
+    assert_eq!(html, "popular;medium;rare;");
+}
+
+#[test]
+fn bibliography_download_link_contains_cited_sources() {
+    let bibliography = dummy_bibliography();
+    let mut cited = HashSet::new();
+    cited.insert("fps".to_string());
+
+    let link = crate::renderer::generate_download_link(
+        &bibliography,
+        &[],
+        &cited,
+        true,
+        BibFormat::BibTeX,
+        "bibliography",
+    );
+    assert!(link.contains(r#"download="bibliography.bib""#));
+    assert!(link.contains("Download .bib"));
+    assert!(
+        link.contains("%40misc%7Bfps%2C"),
+        "Should embed encoded fps: {link}"
+    );
+    assert!(!link.contains("rust_book"));
+
+    let link = crate::renderer::generate_download_link(
+        &yaml_bibliography(),
+        &[],
+        &HashSet::new(),
+        false,
+        BibFormat::Yaml,
+        "refs",
+    );
+    assert!(link.contains(r#"download="refs.yaml""#));
+    assert!(link.contains("jones2023"));
+}
+
+#[test]
+fn bibliography_download_link_keeps_string_macros() {
+    let src = r#"@string{rustconf = "RustConf"}
+
+@inproceedings{talk,
+    author = {Doe, Jane},
+    title = {A Talk},
+    booktitle = rustconf,
+    year = {2024},
+}
+"#;
+    let bibliography = parser::parse_bibliography(src.to_string(), BibFormat::BibTeX).unwrap();
+    let definitions: Vec<String> = parser::bibtex_definitions(src)
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+
+    let link = crate::renderer::generate_download_link(
+        &bibliography,
+        &definitions,
+        &HashSet::new(),
+        false,
+        BibFormat::BibTeX,
+        "bibliography",
+    );
+    let macro_at = link.find("%40string%7Brustconf").expect("macro definition");
+    let entry_at = link.find("%40inproceedings%7Btalk").expect("entry");
+    assert!(macro_at < entry_at, "{link}");
+}
+
+#[test]
+fn bibliography_citation_metadata_for_listed_entries() {
+    let bibliography = dummy_bibliography();
+    let mut cited = HashSet::new();
+    cited.insert("rust_book".to_string());
+
+    let html = crate::renderer::generate_citation_metadata(
+        &bibliography,
+        &cited,
+        true,
+        CitationMetadata::All,
+    );
+    assert_eq!(html.matches("class=\"Z3988\"").count(), 1);
+    assert!(html.contains("<script type=\"application/ld+json\">"));
+    assert!(html.contains("\"@context\":\"https://schema.org\""));
+    assert!(html.contains("The Rust Programming Language"));
+    assert!(!html.contains("This is a bib entry!"));
+
+    let html = crate::renderer::generate_citation_metadata(
+        &bibliography,
+        &cited,
+        true,
+        CitationMetadata::None,
+    );
+    assert!(html.is_empty());
+}
+
+#[test]
+fn csl_json_export_keeps_editors_particles_and_parents() {
+    let src = r#"
+@incollection{python,
+    author = {van Rossum, Guido},
+    editor = {Wirth, Niklaus},
+    title = {Python},
+    booktitle = {Programming Languages},
+    publisher = {Addison-Wesley},
+    year = {1995},
+}
+"#;
+    let bibliography = parser::parse_bibliography(src.to_string(), BibFormat::BibTeX).unwrap();
+    let csl_json = crate::renderer::generate_csl_json(&bibliography).unwrap();
+    let items: serde_json::Value = serde_json::from_str(&csl_json).unwrap();
+
+    let item = &items[0];
+    assert_eq!(item["type"], "chapter");
+    assert_eq!(
+        item["author"][0],
+        serde_json::json!({ "family": "Rossum", "given": "Guido", "non-dropping-particle": "van" })
+    );
+    assert_eq!(
+        item["editor"][0],
+        serde_json::json!({ "family": "Wirth", "given": "Niklaus" })
+    );
+    assert_eq!(item["container-title"], "Programming Languages");
+}
+
+#[test]
+fn bibliography_default_template_has_copy_button() {
+    let render = |bibliography: &IndexMap<String, crate::models::BibItem>, backend| {
+        crate::renderer::generate_bibliography_html(
+            bibliography,
+            &HashSet::new(),
+            false,
+            backend,
+            SortOrder::None,
+            &IndexMap::new(),
+            "",
+        )
+    };
+
+    let backend = create_references_backend();
+    let html = render(&dummy_bibliography(), &backend);
+    assert!(
+        !html.contains("copyBibSource") && !html.contains("bib-source"),
+        "The copy button is off by default: {html}"
+    );
+
+    let backend = create_references_backend().with_copy_button(BibFormat::BibTeX);
+    let html = render(&dummy_bibliography(), &backend);
+    assert!(html.contains(r#"copyBibSource(this)">Copy BibTeX</button>"#));
+    assert!(html.contains(r#"<pre class="bib-source" hidden>@book{rust_book,"#));
+
+    let backend = create_references_backend().with_copy_button(BibFormat::Yaml);
+    let html = render(&yaml_bibliography(), &backend);
+    assert!(html.contains(r#"copyBibSource(this)">Copy YAML</button>"#));
+}
+
 // =============================================================================
 // Custom vs CSL Backend Comparison Tests
 // =============================================================================
@@ -147,6 +420,8 @@ fn backend_custom_vs_csl_citation_format_differs() {
         bib_page_path: "bibliography.html".to_string(),
         chapter_path: "chapter.md".to_string(),
         variant: CitationVariant::Standard,
+        anchor: String::new(),
+        entry_anchor: "smith2024".to_string(),
     };
 
     // Custom backend
@@ -203,6 +478,8 @@ fn backend_csl_citation_links_to_bibliography(#[case] style: &str) {
         bib_page_path: "bibliography.html".to_string(),
         chapter_path: "chapter.md".to_string(),
         variant: CitationVariant::Standard,
+        anchor: String::new(),
+        entry_anchor: "test_entry".to_string(),
     };
 
     let backend = CslBackend::new(style.to_string()).unwrap();
@@ -225,7 +502,7 @@ fn backend_csl_reference_has_entry_class(#[case] style: &str) {
     let item = bibliography.get("test_entry").unwrap();
 
     let backend = CslBackend::new(style.to_string()).unwrap();
-    let reference = backend.format_reference(item).unwrap();
+    let reference = backend.format_reference(item, &[]).unwrap();
 
     assert!(
         reference.contains("class='csl-entry'"),
@@ -247,6 +524,8 @@ fn backend_csl_nature_uses_superscript() {
         bib_page_path: "bibliography.html".to_string(),
         chapter_path: "chapter.md".to_string(),
         variant: CitationVariant::Standard,
+        anchor: String::new(),
+        entry_anchor: "test_entry".to_string(),
     };
 
     let nature_backend = CslBackend::new("nature".to_string()).unwrap();
@@ -273,6 +552,8 @@ fn yaml_bibliography_with_custom_backend() {
         false,
         &backend,
         SortOrder::None,
+        &IndexMap::new(),
+        "",
     );
 
     assert!(
@@ -291,7 +572,7 @@ fn yaml_bibliography_with_csl_backend() {
     let item = bibliography.get("smith2024").unwrap();
 
     let csl_backend = CslBackend::new("apa".to_string()).unwrap();
-    let reference = csl_backend.format_reference(item);
+    let reference = csl_backend.format_reference(item, &[]);
 
     assert!(
         reference.is_ok(),
@@ -5,15 +5,26 @@
 //! - Regex pattern matching
 //! - Subfolder linking
 //! - BibLaTeX-compliant citation keys
+//! - Citation anchors and bibliography back-references
+//! - Per-chapter citation numbering
+//! - Chapter references header and placement
+//! - Per-chapter settings (`<!-- bib: ... -->`)
+//! - `nocite` entries
+//! - Citation scanning with source positions
+//! - Citation hover popovers
+//! - LaTeX output with biblatex commands
+//! - Note styles with chapter footnotes
 
 use super::common::{
-    create_citation_backend, create_citation_backend_with_template, dummy_bibliography,
-    BibItemBuilder, DUMMY_TEXT_WITH_2_VALID_CITE_PLACEHOLDERS,
+    create_citation_backend, create_citation_backend_with_template,
+    create_references_backend_with_template, dummy_bibliography, BibItemBuilder,
+    DUMMY_TEXT_WITH_2_VALID_CITE_PLACEHOLDERS,
     DUMMY_TEXT_WITH_A_VALID_AND_AN_INVALID_CITE_PLACEHOLDERS,
 };
-use crate::config::CitationSyntax;
+use crate::config::{CitationSyntax, Numbering, SortOrder};
+use crate::models::{ChapterCitations, ChapterRefs};
 use indexmap::IndexMap;
-use mdbook_preprocessor::book::Chapter;
+use mdbook_preprocessor::book::{Book, Chapter, SectionNumber};
 use rstest::rstest;
 use std::collections::HashSet;
 
@@ -43,6 +54,7 @@ fn valid_and_invalid_citations_are_replaced_properly_in_book_text() {
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     assert!(text_with_citations.contains(r#"href="bibliography.html#fps""#));
@@ -63,6 +75,7 @@ fn valid_and_invalid_citations_are_replaced_properly_in_book_text() {
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
     assert!(text_with_citations.contains(">fps</a>"));
     assert!(text_with_citations.contains("[Unknown bib ref:"));
@@ -92,6 +105,7 @@ fn citations_in_subfolders_link_properly(#[case] chapter_path: &str, #[case] exp
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     assert!(
@@ -120,6 +134,7 @@ fn citations_in_draft_chapter_link_properly() {
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     assert!(text_with_citations.contains(r#"href="bibliography.html#fps""#));
@@ -184,6 +199,7 @@ This is a reference to {{#cite DUMMY:1}}
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 }
 
@@ -232,6 +248,7 @@ This is another reference @@simple_key that should also work.
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     // Check that both citations were found and added to cited set
@@ -339,6 +356,7 @@ Citations in parentheses (see @@Jones2019).
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     // Check that all citations were found
@@ -474,6 +492,7 @@ User citation @@user@domain is valid.
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     // Verify all citations were found
@@ -703,6 +722,7 @@ Legacy syntax still works: @@legacy_key and {{#cite another_key}}.
         &backend,
         &mut last_index,
         &CitationSyntax::Pandoc,
+        false,
     );
 
     // All citations should be found
@@ -731,6 +751,37 @@ Legacy syntax still works: @@legacy_key and {{#cite another_key}}.
     );
 }
 
+#[rstest]
+#[case::bracketed("See [@fps].", "See [@fps].")]
+#[case::author_in_text("As @fps shows.", "As @fps shows.")]
+#[case::native("See {{#cite fps}}.", "See [@fps].")]
+#[case::unknown_key("See [@missing] and @other.", "See [@missing] and @other.")]
+fn test_pandoc_backend_counts_each_citation_once(#[case] content: &str, #[case] expected: &str) {
+    let mut bibliography = dummy_bibliography();
+    let chapter = Chapter::new(
+        "Test",
+        content.to_string(),
+        std::path::PathBuf::new(),
+        vec![],
+    );
+    let mut cited = HashSet::new();
+    let mut last_index = 0;
+
+    let result = crate::citation::replace_all_placeholders(
+        &chapter,
+        &mut bibliography,
+        &mut cited,
+        &crate::backend::PandocBackend::new(),
+        &mut last_index,
+        &CitationSyntax::Pandoc,
+        false,
+    );
+
+    assert_eq!(result, expected);
+    let expected_count = u32::from(content.contains("fps"));
+    assert_eq!(bibliography["fps"].citation_count, expected_count);
+}
+
 #[test]
 fn test_code_block_protection() {
     let content = r#"
@@ -786,6 +837,7 @@ Back to regular text with @Jones2020.
         &backend,
         &mut last_index,
         &CitationSyntax::Pandoc,
+        false,
     );
 
     // Only regular citations should be found
@@ -866,6 +918,7 @@ Another escaped: admin\@server.local
         &backend,
         &mut last_index,
         &CitationSyntax::Pandoc,
+        false,
     );
 
     // Citation should be found and replaced
@@ -929,6 +982,7 @@ And {{#cite another_key}} too.
         &backend,
         &mut last_index,
         &CitationSyntax::Default, // Default syntax, Pandoc disabled
+        false,
     );
 
     // @citation should NOT be replaced (Pandoc syntax disabled)
@@ -945,3 +999,544 @@ And {{#cite another_key}} too.
     assert!(cited.contains("legacy_key"), "@@legacy_key should work");
     assert!(cited.contains("another_key"), "{{#cite}} should work");
 }
+
+// =============================================================================
+// Back-Reference Tests
+// =============================================================================
+
+#[test]
+fn citations_get_unique_anchors_and_backrefs() {
+    let mut bibliography = dummy_bibliography();
+    let backend = create_citation_backend();
+
+    let mut intro = Chapter::new(
+        "Intro",
+        "See {{#cite fps}} and again @@fps.".to_string(),
+        "intro.md",
+        vec![],
+    );
+    intro.number = Some(SectionNumber::new(vec![1]));
+    let mut nested = Chapter::new(
+        "Nested",
+        "Both {{#cite rust_book}} and {{#cite fps}}.".to_string(),
+        "part/nested.md",
+        vec![],
+    );
+    nested.number = Some(SectionNumber::new(vec![2, 1]));
+    let unnumbered = Chapter::new("Preface", "{{#cite fps}}".to_string(), "preface.md", vec![]);
+
+    let mut book = Book::new();
+    book.push_item(intro)
+        .push_item(nested)
+        .push_item(unnumbered);
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert!(contents[0].contains(r#"id="bib-cite-fps-1""#));
+    assert!(contents[0].contains(r#"id="bib-cite-fps-2""#));
+    assert!(contents[1].contains(r#"id="bib-cite-fps-3""#));
+    assert!(contents[1].contains(r#"id="bib-cite-rust_book-1""#));
+    assert_eq!(bibliography["fps"].citation_count, 4);
+
+    let fps_backrefs = &result.backrefs["fps"];
+    let labels: Vec<&str> = fps_backrefs.iter().map(|b| b.label.as_str()).collect();
+    assert_eq!(labels, vec!["Ch. 1", "Ch. 2.1", "Preface"]);
+    // One back-reference per chapter, pointing at its first citation
+    assert_eq!(fps_backrefs[0].href, "intro.html#bib-cite-fps-1");
+    assert_eq!(fps_backrefs[1].href, "part/nested.html#bib-cite-fps-3");
+    assert_eq!(fps_backrefs[2].href, "preface.html#bib-cite-fps-4");
+    assert_eq!(result.backrefs["rust_book"].len(), 1);
+}
+
+#[test]
+fn chapter_bibliography_backrefs_link_in_page() {
+    let mut bibliography = dummy_bibliography();
+    let citation_backend = create_citation_backend();
+    let references_backend =
+        create_references_backend_with_template("{{#each backrefs}}[{{href}}]{{/each}}");
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "One",
+        "{{#cite fps}}".to_string(),
+        "one.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Two",
+        "{{#cite fps}}".to_string(),
+        "two.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &citation_backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+    crate::citation::add_bib_at_end_of_chapters(
+        &mut book,
+        &mut bibliography,
+        &references_backend,
+        &|_| String::new(),
+        SortOrder::None,
+        &result,
+        "",
+    );
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert!(contents[0].ends_with("[#bib-cite-fps-1]"));
+    assert!(contents[1].ends_with("[#bib-cite-fps-2]"));
+}
+
+#[test]
+fn per_chapter_numbering_restarts_in_each_chapter() {
+    let mut bibliography = dummy_bibliography();
+    let citation_backend =
+        create_citation_backend_with_template("[{{item.index}}]({{path}}#{{entry_anchor}})");
+    let references_backend = create_references_backend_with_template("{{index}}:{{citation_key}} ");
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "One",
+        "{{#cite rust_book}} {{#cite fps}}".to_string(),
+        "one.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Two",
+        "{{#cite fps}}".to_string(),
+        "part/two.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &citation_backend,
+        &CitationSyntax::Default,
+        Numbering::PerChapter,
+        false,
+        &[],
+    );
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert_eq!(
+        contents[0],
+        "[1](#chapter-bib-rust_book) [2](#chapter-bib-fps)"
+    );
+    assert_eq!(contents[1], "[1](#chapter-bib-fps)");
+    // The bibliography chapter keeps book-wide indices
+    assert_eq!(bibliography["rust_book"].index, Some(1));
+    assert_eq!(bibliography["fps"].index, Some(2));
+
+    crate::citation::add_bib_at_end_of_chapters(
+        &mut book,
+        &mut bibliography,
+        &references_backend,
+        &|_| String::new(),
+        SortOrder::Index,
+        &result,
+        "",
+    );
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert!(contents[0].ends_with("1:rust_book 2:fps "));
+    assert!(contents[1].ends_with("1:fps "));
+    assert_eq!(bibliography["fps"].index, Some(2));
+}
+
+#[test]
+fn chapter_bibliography_directive_places_references_with_header() {
+    let mut bibliography = dummy_bibliography();
+    let citation_backend = create_citation_backend();
+    let references_backend = create_references_backend_with_template("<li>{{citation_key}}</li>");
+    let mut handlebars = handlebars::Handlebars::new();
+    handlebars
+        .register_template_string(
+            "chapter_refs",
+            "<h2>{{book_title}}: {{chapter_name}} ({{citation_count}}/{{entry_count}})</h2>",
+        )
+        .unwrap();
+    let header = |chapter: &ChapterCitations| {
+        let refs = ChapterRefs {
+            chapter,
+            book_title: "Notes",
+        };
+        handlebars.render("chapter_refs", &refs).unwrap()
+    };
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "Lesson",
+        "{{#cite fps}} and @@fps.\n\n{{#chapter_bibliography}}\n\n## Exercises\n\n\
+         `{{#chapter_bibliography}}` places the list."
+            .to_string(),
+        "lesson.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Glossary",
+        "No citations.\n{{#chapter_bibliography}}".to_string(),
+        "glossary.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &citation_backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+    crate::citation::add_bib_at_end_of_chapters(
+        &mut book,
+        &mut bibliography,
+        &references_backend,
+        &header,
+        SortOrder::None,
+        &result,
+        "",
+    );
+    crate::citation::remove_chapter_bibliography_directives(&mut book);
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    let refs = contents[0]
+        .find("<h2>Notes: Lesson (2/1)</h2>\n<li>fps</li>")
+        .unwrap();
+    assert!(refs < contents[0].find("## Exercises").unwrap());
+    assert!(contents[0].ends_with("`{{#chapter_bibliography}}` places the list."));
+    assert_eq!(contents[1], "No citations.\n");
+}
+
+#[test]
+fn chapter_settings_override_config() {
+    let mut bibliography = dummy_bibliography();
+    let citation_backend = create_citation_backend();
+    let references_backend = create_references_backend_with_template("<li>{{citation_key}}</li>");
+
+    let glossary = "<!-- bib: off -->\n@@fps and {{#cite fps}} stay.";
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "Glossary",
+        glossary.to_string(),
+        "glossary.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Notes",
+        "<!-- bib: syntax=pandoc chapter-bib=false -->\nAs @fps shows.".to_string(),
+        "notes.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Main",
+        "As @fps shows {{#cite rust_book}}.".to_string(),
+        "main.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &citation_backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+    crate::citation::add_bib_at_end_of_chapters(
+        &mut book,
+        &mut bibliography,
+        &references_backend,
+        &|_| String::new(),
+        SortOrder::None,
+        &result,
+        "",
+    );
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert_eq!(contents[0], glossary);
+    assert!(result.per_chapter["glossary.md"].is_empty());
+    // Pandoc syntax in this chapter only, without a reference list
+    assert!(contents[1].contains("href=\"bibliography.html#fps\""));
+    assert!(!contents[1].contains("<li>"));
+    assert!(contents[2].starts_with("\nAs @fps shows <a class=\"bib-cite\""));
+    assert!(contents[2].ends_with("<li>rust_book</li>"));
+}
+
+#[test]
+fn nocite_lists_entries_without_citing_them() {
+    let mut bibliography = dummy_bibliography();
+    let backend = create_citation_backend();
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "Intro",
+        "{{#nocite rust_book, missing}}As {{#cite fps}} shows. `{{#nocite fps}}` stays."
+            .to_string(),
+        "intro.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &["fps".to_string()],
+    );
+
+    let content = &book.chapters().next().unwrap().content;
+    assert!(content.starts_with("As <a class=\"bib-cite\""));
+    assert!(content.ends_with("`{{#nocite fps}}` stays."));
+    assert!(!content.contains("rust_book"));
+    assert_eq!(result.nocite, vec!["rust_book".to_string()]);
+    assert!(result.all_cited.contains("rust_book"));
+    assert_eq!(bibliography["fps"].index, Some(1));
+    assert_eq!(bibliography["rust_book"].index, Some(2));
+    assert!(result.per_chapter["intro.md"].contains("fps"));
+    assert!(!result.per_chapter["intro.md"].contains("rust_book"));
+
+    // `*` lists every entry
+    let mut bibliography = dummy_bibliography();
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "Intro",
+        "No citations.".to_string(),
+        "intro.md",
+        vec![],
+    ));
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &["*".to_string()],
+    );
+    assert_eq!(result.all_cited.len(), bibliography.len());
+    assert_eq!(result.nocite.len(), bibliography.len());
+}
+
+// =============================================================================
+// Citation Popover Tests
+// =============================================================================
+
+#[test]
+fn citation_popovers_embed_cited_references() {
+    let mut bibliography = dummy_bibliography();
+    let citation_backend = create_citation_backend();
+    let references_backend =
+        create_references_backend_with_template("<p>{{title}}</p><script></script>");
+
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "One",
+        "{{#cite fps}}".to_string(),
+        "one.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Two",
+        "No citations here.".to_string(),
+        "two.md",
+        vec![],
+    ));
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &citation_backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+    crate::citation::add_citation_popovers(
+        &mut book,
+        &bibliography,
+        &references_backend,
+        &result.per_chapter,
+        "<script>function copyBibSource() {}</script>\n",
+    );
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert!(contents[0].contains(r#"<script type="application/json" id="bib-popover-data">"#));
+    assert!(contents[0].contains(r#""fps":"<p>This is a bib entry!<\/p><script><\/script>""#));
+    assert!(!contents[0].contains("The Rust Programming Language"));
+    assert!(contents[0].contains("initBibPopovers"));
+    assert!(contents[0].contains("function copyBibSource"));
+    assert_eq!(contents[1], "No citations here.");
+}
+
+// =============================================================================
+// LaTeX Output Tests
+// =============================================================================
+
+#[test]
+fn latex_backend_rewrites_citations_to_biblatex_commands() {
+    let mut bibliography = dummy_bibliography();
+    let chapter = Chapter::new(
+        "Intro",
+        "{{#cite fps}}, @@rust_book, @fps, [@rust_book] and [-@fps]. `@@fps` stays.".to_string(),
+        "intro.md",
+        vec![],
+    );
+    let mut book = Book::new();
+    book.push_item(chapter);
+
+    let result = crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &crate::backend::LatexBackend::new(),
+        &CitationSyntax::Pandoc,
+        Numbering::Global,
+        false,
+        &[],
+    );
+
+    let content = &book.chapters().next().unwrap().content;
+    assert_eq!(
+        content,
+        "\\cite{fps}, \\cite{rust_book}, \\textcite{fps}, \\parencite{rust_book} \
+         and \\parencite*{fps}. `@@fps` stays."
+    );
+    assert_eq!(result.all_cited.len(), 2);
+}
+
+// =============================================================================
+// Note Style Tests
+// =============================================================================
+
+#[test]
+fn note_style_footnotes_are_appended_to_each_chapter() {
+    let mut bibliography = dummy_bibliography();
+    let backend = crate::backend::CslBackend::new("chicago-notes".to_string()).unwrap();
+    let mut book = Book::new();
+    book.push_item(Chapter::new(
+        "One",
+        "First {{#cite rust_book}}.\n".to_string(),
+        "one.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Two",
+        "No citations here.".to_string(),
+        "two.md",
+        vec![],
+    ))
+    .push_item(Chapter::new(
+        "Three",
+        "Again @@rust_book.".to_string(),
+        "three.md",
+        vec![],
+    ));
+
+    crate::citation::expand_cite_references_in_book(
+        &mut book,
+        &mut bibliography,
+        &backend,
+        &CitationSyntax::Default,
+        Numbering::Global,
+        false,
+        &[],
+    );
+
+    let contents: Vec<String> = book.chapters().map(|ch| ch.content.clone()).collect();
+    assert!(contents[0].starts_with(
+        "First <span class='csl-cite-anchor' id='bib-cite-rust_book-1'></span>[^bib-1]."
+    ));
+    assert!(contents[0].contains("\n\n[^bib-1]: "));
+    assert_eq!(contents[1], "No citations here.");
+    // Numbering and positions restart in each chapter
+    assert!(contents[2].contains("[^bib-1].\n\n[^bib-1]: "));
+}
+
+// =============================================================================
+// Citation Scanning Tests
+// =============================================================================
+
+#[test]
+fn scanning_locates_citations_in_source() {
+    use crate::backend::CitationVariant;
+    use crate::citation::{find_citations, find_malformed_citations, find_nocite_keys};
+
+    let content = "Ünïcode {{#cite fps}}, `@@skip` [@rust_book] and @fps.\n\\@fps {{#nocite a, b}}";
+    let citations = find_citations(content, &CitationSyntax::Pandoc);
+    let found: Vec<(&str, CitationVariant, &str)> = citations
+        .iter()
+        .map(|c| (c.key.as_str(), c.variant, &content[c.span.clone()]))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("fps", CitationVariant::Standard, "{{#cite fps}}"),
+            ("rust_book", CitationVariant::Parenthetical, "[@rust_book]"),
+            ("fps", CitationVariant::AuthorInText, "@fps"),
+        ]
+    );
+    assert_eq!(&content[citations[1].key_span.clone()], "rust_book");
+
+    // Only {{#cite}} and @@ with the default syntax
+    assert_eq!(find_citations(content, &CitationSyntax::Default).len(), 1);
+
+    let keys: Vec<(String, &str)> = find_nocite_keys(content)
+        .into_iter()
+        .map(|(key, span)| (key, &content[span]))
+        .collect();
+    assert_eq!(keys, vec![("a".to_string(), "a"), ("b".to_string(), "b")]);
+
+    let content = "{{#cite}} {{#cite a b}} @@ [-@9lives] `{{#cite}}`";
+    let malformed: Vec<&str> = find_malformed_citations(content, &CitationSyntax::Pandoc)
+        .into_iter()
+        .map(|span| &content[span])
+        .collect();
+    assert_eq!(
+        malformed,
+        vec!["{{#cite}}", "{{#cite a b}}", "@@", "[-@9lives]"]
+    );
+}
+
+#[test]
+fn scanning_finds_key_being_typed() {
+    use crate::citation::find_partial_key;
+
+    let partial = |text: &'static str, syntax: CitationSyntax| {
+        find_partial_key(text, &syntax).map(|range| &text[range])
+    };
+    assert_eq!(
+        partial("See {{#cite rust_b", CitationSyntax::Default),
+        Some("rust_b")
+    );
+    assert_eq!(partial("See {{#cite ", CitationSyntax::Default), Some(""));
+    assert_eq!(partial("As @@fp", CitationSyntax::Default), Some("fp"));
+    assert_eq!(
+        partial("{{#nocite fps, ru", CitationSyntax::Default),
+        Some("ru")
+    );
+    assert_eq!(partial("Done {{#cite fps}}", CitationSyntax::Default), None);
+    // Pandoc openings only count with Pandoc syntax
+    assert_eq!(
+        partial("In [@fps; -@ru", CitationSyntax::Pandoc),
+        Some("ru")
+    );
+    assert_eq!(partial("In [@ru", CitationSyntax::Default), None);
+    assert_eq!(partial("mail me@exa", CitationSyntax::Pandoc), None);
+}
@@ -0,0 +1,1099 @@
+//! Tests for the subcommands of the `mdbook-bib` binary.
+//!
+//! This module covers:
+//! - Loading a book from disk (`book.toml`, `SUMMARY.md`, bibliography)
+//! - `check`: unknown keys, malformed citations and uncited entries
+//! - `styles`: listing bundled CSL styles
+//! - `render`: previewing citations and references
+//! - `convert`: BibTeX, YAML and CSL-JSON output and the lost fields
+//! - `export`: the cited subset of a book or of some chapters
+//! - `stats`: citation counts per entry, chapter, year and type
+//! - `keys`: the entries offered for completion, from a file or the Zotero copy
+//! - `init`: the configuration, starter bibliography and templates, never overwritten
+//! - `lsp`: completion, hover, definition and diagnostics, directly and over a connection
+//! - `add`: identifiers, citation keys and entries from stubbed DOI, ISBN and arXiv services
+
+use super::common::{create_book_on_disk, serve_http, DUMMY_BIB_SRC, YAML_BIB_SRC};
+use crate::backend::CitationVariant;
+use crate::commands::add::{add, AddOptions, Identifier, Work};
+use crate::commands::check::{check_book, Severity};
+use crate::commands::convert::{convert, Conversion, TargetFormat};
+use crate::commands::export::{export, ExportOptions};
+use crate::commands::init::{init, InitOptions, Outcome};
+use crate::commands::keys::list_keys;
+use crate::commands::lsp::{serve, Workspace};
+use crate::commands::render::{render, RenderOptions};
+use crate::commands::stats::{book_stats, format_report};
+use crate::commands::styles::{format_table, styles};
+use crate::commands::BookProject;
+use crate::parser::{self, BibFormat};
+
+// =============================================================================
+// Check Tests
+// =============================================================================
+
+fn check(bib_config: &str, chapters: &[(&str, &str)]) -> Vec<String> {
+    let root = create_book_on_disk(bib_config, chapters);
+    let project = BookProject::load(root.path()).unwrap();
+    let config = project.config().unwrap();
+    let bibliography = project.load_bibliography(&config).unwrap();
+    check_book(&project, &config, &bibliography)
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[test]
+fn check_reports_unknown_and_malformed_citations_with_locations() {
+    let diagnostics = check(
+        "",
+        &[
+            ("intro.md", "# Intro\n\nSee {{#cite fps}} and @@missing.\n"),
+            (
+                "methods.md",
+                "Broken {{#cite}} here.\n```\n{{#cite also_missing}}\n```\n`@@nope`\n",
+            ),
+        ],
+    );
+
+    assert_eq!(
+        diagnostics,
+        vec![
+            "src/intro.md:3:25: error: unknown citation key 'missing'",
+            "src/methods.md:1:8: error: malformed citation '{{#cite}}'",
+            "src/refs.bib:9: warning: entry 'rust_book' is never cited",
+        ]
+    );
+}
+
+#[test]
+fn check_honors_pandoc_syntax_nocite_and_chapter_settings() {
+    let diagnostics = check(
+        "citation-syntax = \"pandoc\"",
+        &[
+            (
+                "intro.md",
+                "As @fps notes [@fps, p. 3], mail me@example.com.\n{{#nocite rust_book, gone}}\n",
+            ),
+            ("glossary.md", "<!-- bib: off -->\n@@unknown_key\n"),
+        ],
+    );
+
+    assert_eq!(
+        diagnostics,
+        vec![
+            "src/intro.md:1:15: error: malformed citation '[@fps, p. 3]'",
+            "src/intro.md:2:22: error: unknown citation key 'gone' in nocite",
+        ]
+    );
+}
+
+#[test]
+fn check_warns_about_uncited_entries_only() {
+    let root = create_book_on_disk("nocite = [\"rust_book\"]", &[("intro.md", "@@fps")]);
+    let project = BookProject::load(root.path()).unwrap();
+    let config = project.config().unwrap();
+    let bibliography = project.load_bibliography(&config).unwrap();
+    assert!(check_book(&project, &config, &bibliography).is_empty());
+
+    let diagnostics = check("", &[("intro.md", "No citations.")]);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics[0].starts_with("src/refs.bib:2: warning: entry 'fps'"));
+
+    let root = create_book_on_disk("", &[("intro.md", "No citations.")]);
+    let project = BookProject::load(root.path()).unwrap();
+    let config = project.config().unwrap();
+    let bibliography = project.load_bibliography(&config).unwrap();
+    assert!(check_book(&project, &config, &bibliography)
+        .iter()
+        .all(|diagnostic| diagnostic.severity == Severity::Warning));
+}
+
+#[test]
+fn check_requires_bib_section() {
+    let root = create_book_on_disk("", &[]);
+    std::fs::write(
+        root.path().join("book.toml"),
+        "[book]\ntitle = \"No bib\"\n",
+    )
+    .unwrap();
+    let err = BookProject::load(root.path()).err().unwrap();
+    assert!(err.to_string().contains("No [preprocessor.bib] section"));
+}
+
+// =============================================================================
+// Styles Tests
+// =============================================================================
+
+#[test]
+fn styles_lists_bundled_styles_with_aliases_and_format() {
+    let all = styles(None);
+    let ieee = all
+        .iter()
+        .find(|style| style.aliases.contains(&"ieee"))
+        .unwrap();
+    assert_eq!(ieee.format, "numeric");
+    assert!(!ieee.superscript && !ieee.dependent);
+
+    let nature = all.iter().find(|style| style.name == "nature").unwrap();
+    assert!(nature.superscript);
+
+    let label = styles(Some("label"));
+    assert!(!label.is_empty());
+    assert!(label.iter().all(|style| style.format == "label"));
+    assert!(styles(Some("author-date"))
+        .iter()
+        .any(|style| style.name == "american-psychological-association"));
+
+    let table = format_table(&styles(Some("note")));
+    let mut lines = table.lines();
+    assert!(lines.next().unwrap().starts_with("NAME "));
+    assert!(lines.any(|line| line.starts_with("chicago-notes ") && line.contains("note")));
+}
+
+// =============================================================================
+// Render Tests
+// =============================================================================
+
+#[test]
+fn render_previews_csl_style_as_text() {
+    let dir = tempfile::tempdir().unwrap();
+    let bib = dir.path().join("refs.bib");
+    std::fs::write(&bib, DUMMY_BIB_SRC).unwrap();
+
+    let options = RenderOptions {
+        bib: bib.clone(),
+        style: Some("ieee".to_string()),
+        keys: vec!["rust_book".to_string(), "fps".to_string()],
+        text: true,
+        ..Default::default()
+    };
+    let output = render(&options).unwrap();
+    assert!(output.starts_with("Citations:\n\n[1]\n[2]\n\nReferences:\n\n[1] S. Klabnik"));
+    assert!(!output.contains('<'));
+
+    let options = RenderOptions {
+        style: Some("apa".to_string()),
+        keys: vec!["fps".to_string()],
+        variant: CitationVariant::AuthorInText,
+        text: false,
+        ..options
+    };
+    let output = render(&options).unwrap();
+    assert!(output.contains("Perez-Sorrosal ([2020](#fps))"));
+    assert!(output.contains("<div class='csl-entry' id='fps'>"));
+
+    let options = RenderOptions {
+        keys: vec!["missing".to_string()],
+        ..options
+    };
+    assert!(render(&options).is_err());
+}
+
+#[test]
+fn render_uses_custom_templates() {
+    let dir = tempfile::tempdir().unwrap();
+    let bib = dir.path().join("refs.bib");
+    std::fs::write(&bib, DUMMY_BIB_SRC).unwrap();
+    let hb_tpl = dir.path().join("refs.hbs");
+    std::fs::write(&hb_tpl, "<li>{{title}} ({{pub_year}})</li>").unwrap();
+    let cite_hb_tpl = dir.path().join("cite.hbs");
+    std::fs::write(&cite_hb_tpl, "<cite>{{item.citation_key}}</cite>").unwrap();
+
+    let output = render(&RenderOptions {
+        bib,
+        hb_tpl: Some(hb_tpl),
+        cite_hb_tpl: Some(cite_hb_tpl),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(
+        output,
+        "Citations:\n\n<cite>fps</cite>\n<cite>rust_book</cite>\n\nReferences:\n\n\
+         <li>This is a bib entry! (2020)</li>\n\
+         <li>The Rust Programming Language (2018)</li>\n"
+    );
+}
+
+// =============================================================================
+// Convert Tests
+// =============================================================================
+
+#[test]
+fn convert_bibtex_to_yaml_and_back_reports_lost_fields() {
+    let yaml = convert(
+        DUMMY_BIB_SRC.to_string(),
+        BibFormat::BibTeX,
+        TargetFormat::Yaml,
+    )
+    .unwrap();
+    let lost: Vec<String> = yaml.lost.iter().map(ToString::to_string).collect();
+    assert_eq!(lost, ["fps: 'what_is_this' is not read from BibTeX"]);
+    assert!(yaml.output.contains("rust_book:\n  type: book\n"));
+
+    let bibtex = convert(yaml.output, BibFormat::Yaml, TargetFormat::BibTeX).unwrap();
+    assert!(bibtex.lost.is_empty());
+    assert!(bibtex.output.contains(
+        "@book{rust_book,\n  author = {Klabnik, Steve and Nichols, Carol},\n  \
+         title = {The Rust Programming Language},\n  year = {2018},\n"
+    ));
+
+    // The written BibTeX reads back as the same entries
+    let original =
+        parser::parse_bibliography(DUMMY_BIB_SRC.to_string(), BibFormat::BibTeX).unwrap();
+    let round_trip = parser::parse_bibliography(bibtex.output, BibFormat::BibTeX).unwrap();
+    for (key, item) in &original {
+        let converted = &round_trip[key];
+        assert_eq!(converted.title, item.title);
+        assert_eq!(converted.authors, item.authors);
+        assert_eq!(converted.pub_year, item.pub_year);
+        assert_eq!(converted.isbn, item.isbn);
+        assert_eq!(converted.publisher, item.publisher);
+    }
+}
+
+#[test]
+fn convert_flattens_parents_into_bibtex_and_csl_json() {
+    let bibtex = convert(
+        YAML_BIB_SRC.to_string(),
+        BibFormat::Yaml,
+        TargetFormat::BibTeX,
+    )
+    .unwrap();
+    assert!(bibtex.output.contains("@article{smith2024,"));
+    assert!(bibtex
+        .output
+        .contains("  journal = {Journal of YAML Studies},\n"));
+    assert!(bibtex.output.contains("  number = {2},\n"));
+
+    let csl = convert(
+        YAML_BIB_SRC.to_string(),
+        BibFormat::Yaml,
+        TargetFormat::CslJson,
+    )
+    .unwrap();
+    let lost: Vec<String> = csl.lost.iter().map(ToString::to_string).collect();
+    assert_eq!(lost, ["jones2023: 'isbn' is not read from YAML"]);
+    let items: serde_json::Value = serde_json::from_str(&csl.output).unwrap();
+    let article = &items[0];
+    assert_eq!(article["id"], "smith2024");
+    assert_eq!(article["type"], "article-journal");
+    assert_eq!(article["container-title"], "Journal of YAML Studies");
+    assert_eq!(
+        article["issued"]["date-parts"][0],
+        serde_json::json!([2024, 3])
+    );
+    assert_eq!(
+        article["author"][0],
+        serde_json::json!({ "family": "Smith", "given": "John" })
+    );
+    assert_eq!(items[1]["publisher"], "Academic Press");
+    assert_eq!(items[1]["publisher-place"], "Cambridge");
+}
+
+#[test]
+fn convert_reports_fields_without_equivalent() {
+    let yaml = "entry:\n  type: misc\n  title: T\n  runtime: 01:30:00\n  serial-number:\n    arxiv: 2101.00001\n";
+    let csl = convert(yaml.to_string(), BibFormat::Yaml, TargetFormat::CslJson).unwrap();
+    let lost: Vec<String> = csl.lost.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        lost,
+        [
+            "entry: 'runtime' has no CSL-JSON equivalent",
+            "entry: 'serial-number.arxiv' has no CSL-JSON equivalent",
+        ]
+    );
+
+    let bibtex = convert(yaml.to_string(), BibFormat::Yaml, TargetFormat::BibTeX).unwrap();
+    assert!(bibtex
+        .output
+        .contains("  eprint = {2101.00001},\n  eprinttype = {arxiv},\n"));
+    assert_eq!(bibtex.lost.len(), 1);
+}
+
+// =============================================================================
+// Export Tests
+// =============================================================================
+
+const EXPORT_CHAPTERS: &[(&str, &str)] = &[
+    ("intro.md", "See {{#cite rust_book}}.\n"),
+    (
+        "results.md",
+        "As {{#cite fps}} and {{#cite rust_book}} show.\n",
+    ),
+    ("notes.md", "No citations here.\n"),
+];
+
+fn export_keys(conversion: &Conversion) -> Vec<String> {
+    parser::parse_bibliography(conversion.output.clone(), BibFormat::BibTeX)
+        .unwrap()
+        .into_keys()
+        .collect()
+}
+
+#[test]
+fn export_copies_cited_entries_of_selected_chapters() {
+    let root = create_book_on_disk("", EXPORT_CHAPTERS);
+    let project = BookProject::load(root.path()).unwrap();
+
+    let all = export(&project, &ExportOptions::default()).unwrap();
+    assert_eq!(export_keys(&all), ["fps", "rust_book"]);
+    // Entries are copied as written, unread fields included
+    assert!(all.output.contains("what_is_this = {blabla}"));
+    assert!(all.lost.is_empty());
+
+    let by_citation = ExportOptions {
+        by_citation: true,
+        ..Default::default()
+    };
+    let ordered = export(&project, &by_citation).unwrap();
+    assert_eq!(export_keys(&ordered), ["rust_book", "fps"]);
+
+    let intro = ExportOptions {
+        chapters: vec!["src/intro.md".into()],
+        ..Default::default()
+    };
+    assert_eq!(
+        export_keys(&export(&project, &intro).unwrap()),
+        ["rust_book"]
+    );
+
+    let missing = ExportOptions {
+        chapters: vec!["missing.md".into()],
+        ..Default::default()
+    };
+    let err = export(&project, &missing).unwrap_err();
+    assert_eq!(err.to_string(), "No chapter missing.md in SUMMARY.md");
+}
+
+#[test]
+fn export_keeps_string_macros_and_crossref_parents() {
+    let root = create_book_on_disk(
+        "",
+        &[(
+            "intro.md",
+            "See {{#cite knuth1974}} and {{#cite dijkstra1968}}.\n",
+        )],
+    );
+    std::fs::write(
+        root.path().join("src/refs.bib"),
+        r#"@string{cacm = {Communications of the ACM}}
+
+@article{dijkstra1968,
+    author = {Dijkstra, Edsger W.},
+    title = {Go To Statement Considered Harmful},
+    journal = cacm,
+    year = {1968},
+}
+
+@inproceedings{knuth1974,
+    author = {Knuth, Donald E.},
+    title = {Structured Programming with go to Statements},
+    crossref = {acm1974},
+    pages = {261--301},
+}
+
+@proceedings{acm1974,
+    title = {Proceedings of the ACM Annual Conference},
+    year = {1974},
+}
+
+@misc{uncited,
+    title = {Not Cited},
+}
+"#,
+    )
+    .unwrap();
+    let project = BookProject::load(root.path()).unwrap();
+
+    let conversion = export(&project, &ExportOptions::default()).unwrap();
+    assert!(conversion
+        .output
+        .starts_with("@string{cacm = {Communications of the ACM}}\n"));
+    assert_eq!(
+        export_keys(&conversion),
+        ["dijkstra1968", "knuth1974", "acm1974"]
+    );
+    assert!(!conversion.output.contains("uncited"));
+    let exported =
+        parser::parse_bibliography(conversion.output.clone(), BibFormat::BibTeX).unwrap();
+    let journal = exported["dijkstra1968"]
+        .hayagriva_entry
+        .as_ref()
+        .and_then(|entry| entry.parents().first())
+        .and_then(|parent| parent.title())
+        .map(|title| title.to_string());
+    assert_eq!(journal.as_deref(), Some("Communications of the ACM"));
+}
+
+#[test]
+fn export_converts_with_nocite_entries() {
+    let root = create_book_on_disk("nocite = [\"fps\"]", &EXPORT_CHAPTERS[2..]);
+    let project = BookProject::load(root.path()).unwrap();
+    let options = ExportOptions {
+        to: Some(TargetFormat::CslJson),
+        ..Default::default()
+    };
+    let conversion = export(&project, &options).unwrap();
+    let items: serde_json::Value = serde_json::from_str(&conversion.output).unwrap();
+    assert_eq!(items.as_array().unwrap().len(), 1);
+    assert_eq!(items[0]["id"], "fps");
+    let lost: Vec<String> = conversion.lost.iter().map(ToString::to_string).collect();
+    assert_eq!(lost, ["fps: 'what_is_this' is not read from BibTeX"]);
+}
+
+// =============================================================================
+// Stats Tests
+// =============================================================================
+
+#[test]
+fn stats_counts_citations_per_entry_chapter_year_and_type() {
+    let root = create_book_on_disk(
+        "",
+        &[
+            ("intro.md", "See {{#cite fps}}.\n"),
+            ("results.md", "As {{#cite fps}} and @@fps show.\n"),
+            ("notes.md", "No citations here.\n"),
+        ],
+    );
+    let project = BookProject::load(root.path()).unwrap();
+    let stats = book_stats(&project).unwrap();
+
+    assert_eq!(stats.total_citations, 3);
+    assert_eq!(stats.entries.len(), 1);
+    assert_eq!(
+        (
+            stats.entries[0].key.as_str(),
+            stats.entries[0].citations,
+            stats.entries[0].chapters
+        ),
+        ("fps", 3, 2)
+    );
+    let chapters: Vec<(&str, u32)> = stats
+        .chapters
+        .iter()
+        .map(|chapter| (chapter.path.as_str(), chapter.citations))
+        .collect();
+    assert_eq!(
+        chapters,
+        [
+            ("src/results.md", 2),
+            ("src/intro.md", 1),
+            ("src/notes.md", 0)
+        ]
+    );
+    assert_eq!(stats.uncited, ["rust_book"]);
+    let years: Vec<(&str, usize, usize)> = stats
+        .years
+        .iter()
+        .map(|group| (group.name.as_str(), group.entries, group.cited))
+        .collect();
+    assert_eq!(years, [("2018", 1, 0), ("2020", 1, 1)]);
+    let types: Vec<&str> = stats
+        .types
+        .iter()
+        .map(|group| group.name.as_str())
+        .collect();
+    assert_eq!(types, ["misc", "book"]);
+
+    let report = format_report(&stats);
+    assert!(report.starts_with("3 citation(s), 1 of 2 entries cited\n"));
+    assert!(report.contains("\nENTRY  CITATIONS  CHAPTERS\nfps            3         2\n"));
+    assert!(report.ends_with("\nNever cited:\n  rust_book\n"));
+}
+
+// =============================================================================
+// Keys Tests
+// =============================================================================
+
+#[test]
+fn keys_lists_entries_with_completion_details() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let project = BookProject::load(root.path()).unwrap();
+    let keys = list_keys(&project).unwrap();
+
+    let json = serde_json::to_value(&keys).unwrap();
+    assert_eq!(
+        json[1],
+        serde_json::json!({
+            "key": "rust_book",
+            "title": "The Rust Programming Language",
+            "authors": ["Klabnik, Steve", "Nichols, Carol"],
+            "year": "2018",
+            "type": "book",
+        })
+    );
+    assert_eq!(keys[0].key, "fps");
+}
+
+#[test]
+fn keys_reads_zotero_library_from_saved_copy() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    std::fs::write(
+        root.path().join("book.toml"),
+        "[preprocessor.bib]\nzotero-uid = \"123\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.path().join("my_zotero.bib"),
+        "@book{zotero_entry, title = {From Zotero}, author = {Doe, Jane}, year = {2001}}\n",
+    )
+    .unwrap();
+    let project = BookProject::load(root.path()).unwrap();
+    let keys = list_keys(&project).unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].key, "zotero_entry");
+    assert_eq!(keys[0].authors, ["Doe, Jane"]);
+}
+
+// =============================================================================
+// Init Tests
+// =============================================================================
+
+#[test]
+fn init_configures_book_with_starter_bibliography_and_templates() {
+    let root = create_book_on_disk("", &[("intro.md", "@@knuth1984")]);
+    std::fs::write(root.path().join("book.toml"), "[book]\ntitle = \"Test\"").unwrap();
+
+    let files = init(&InitOptions {
+        book_dir: root.path().to_path_buf(),
+        bibliography: "sources/books.bib".into(),
+        csl_style: Some("ieee".to_string()),
+        citation_syntax: "pandoc".to_string(),
+        templates: true,
+        ..Default::default()
+    })
+    .unwrap();
+    let files: Vec<(String, Outcome)> = files
+        .into_iter()
+        .map(|(path, outcome)| (path.display().to_string(), outcome))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("src/sources/books.bib".to_string(), Outcome::Created),
+            ("src/render/references.hbs".to_string(), Outcome::Created),
+            ("src/render/cite_key.hbs".to_string(), Outcome::Created),
+            ("src/render/satancisco.css".to_string(), Outcome::Created),
+            ("src/render/copy2clipboard.js".to_string(), Outcome::Created),
+            ("book.toml".to_string(), Outcome::Configured),
+        ]
+    );
+
+    let book_toml = std::fs::read_to_string(root.path().join("book.toml")).unwrap();
+    assert!(book_toml.starts_with("[book]\ntitle = \"Test\"\n\n[preprocessor.bib]\n"));
+    assert!(book_toml.contains("csl-style = \"ieee\"\n"));
+    assert!(book_toml.contains("hb-tpl = \"render/references.hbs\"\n"));
+
+    // The book builds from what was created
+    let project = BookProject::load(root.path()).unwrap();
+    let config = project.config().unwrap();
+    assert_eq!(
+        config.citation_syntax,
+        crate::config::CitationSyntax::Pandoc
+    );
+    assert_eq!(
+        config.bib_hb_html.trim(),
+        crate::config::DEFAULT_HB_TEMPLATE.trim()
+    );
+    let bibliography = project.load_bibliography(&config).unwrap();
+    assert!(bibliography.contains_key("knuth1984"));
+    assert!(check_book(&project, &config, &bibliography).is_empty());
+}
+
+#[test]
+fn init_keeps_existing_files_and_configuration() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let book_toml = std::fs::read_to_string(root.path().join("book.toml")).unwrap();
+    let options = InitOptions {
+        book_dir: root.path().to_path_buf(),
+        bibliography: "refs.bib".into(),
+        ..Default::default()
+    };
+
+    // An existing section is kept, and nothing created beside it
+    let yaml = InitOptions {
+        bibliography: "refs.yaml".into(),
+        templates: true,
+        ..options.clone()
+    };
+    let files = init(&yaml).unwrap();
+    assert_eq!(files, [("book.toml".into(), Outcome::Kept)]);
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("book.toml")).unwrap(),
+        book_toml
+    );
+    assert!(!root.path().join("src/refs.yaml").exists());
+    assert!(!root.path().join("src/render").exists());
+
+    // An existing bibliography is kept
+    std::fs::write(root.path().join("book.toml"), "[book]\n").unwrap();
+    let files = init(&options).unwrap();
+    assert_eq!(
+        files,
+        [
+            ("src/refs.bib".into(), Outcome::Kept),
+            ("book.toml".into(), Outcome::Configured),
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.path().join("src/refs.bib")).unwrap(),
+        DUMMY_BIB_SRC
+    );
+
+    // A YAML bibliography gets a YAML starter
+    std::fs::write(root.path().join("book.toml"), "[book]\n").unwrap();
+    init(&InitOptions {
+        templates: false,
+        ..yaml
+    })
+    .unwrap();
+    let content = std::fs::read_to_string(root.path().join("src/refs.yaml")).unwrap();
+    let entries = parser::parse_bibliography(content, BibFormat::Yaml).unwrap();
+    assert!(entries.contains_key("knuth1984"));
+
+    let custom_with_style = InitOptions {
+        backend: Some("custom".to_string()),
+        csl_style: Some("apa".to_string()),
+        ..options.clone()
+    };
+    assert!(init(&custom_with_style).is_err());
+    std::fs::remove_file(root.path().join("book.toml")).unwrap();
+    assert!(init(&options)
+        .unwrap_err()
+        .to_string()
+        .contains("mdbook init"));
+}
+
+// =============================================================================
+// Language Server Tests
+// =============================================================================
+
+#[test]
+fn lsp_workspace_completes_hovers_and_resolves_citations() {
+    use lsp_types::{CompletionTextEdit, HoverContents, Position, Range};
+
+    let root = create_book_on_disk(
+        "backend = \"csl\"\ncsl-style = \"ieee\"",
+        &[("intro.md", "")],
+    );
+    let workspace = Workspace::load(root.path()).unwrap();
+    // Characters are counted in UTF-16 code units, two for the emoji
+    let content = "Ünïcode 😀 {{#cite rust_book}}\n{{#nocite fps}} and @@ru";
+
+    let completions = workspace.completions(content, Position::new(1, 24));
+    let labels: Vec<&str> = completions.iter().map(|item| item.label.as_str()).collect();
+    assert_eq!(labels, ["fps", "rust_book"]);
+    assert_eq!(
+        completions[1].detail.as_deref(),
+        Some("The Rust Programming Language (2018)")
+    );
+    let Some(CompletionTextEdit::Edit(edit)) = &completions[1].text_edit else {
+        panic!("Expected a text edit");
+    };
+    assert_eq!(
+        edit.range,
+        Range::new(Position::new(1, 22), Position::new(1, 24))
+    );
+    assert!(workspace
+        .completions(content, Position::new(1, 19))
+        .is_empty());
+
+    let hover = workspace.hover(content, Position::new(0, 20)).unwrap();
+    let HoverContents::Markup(markup) = hover.contents else {
+        panic!("Expected markup");
+    };
+    assert!(markup
+        .value
+        .starts_with("[1] S. Klabnik and C. Nichols, The Rust Programming Language."));
+    assert_eq!(
+        hover.range,
+        Some(Range::new(Position::new(0, 11), Position::new(0, 30)))
+    );
+    assert!(workspace.hover(content, Position::new(0, 5)).is_none());
+
+    // Keys listed with nocite resolve too
+    let location = workspace.definition(content, Position::new(1, 11)).unwrap();
+    assert!(location.uri.path().ends_with("/src/refs.bib"));
+    assert_eq!(location.range.start, Position::new(1, 0));
+    assert_eq!(location.range.end, Position::new(7, 1));
+}
+
+#[test]
+fn lsp_workspace_resolves_entries_of_crlf_yaml_bibliography() {
+    use lsp_types::Position;
+
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    std::fs::write(
+        root.path().join("src/refs.yaml"),
+        YAML_BIB_SRC.replace('\n', "\r\n"),
+    )
+    .unwrap();
+    std::fs::write(
+        root.path().join("book.toml"),
+        "[preprocessor.bib]\nbibliography = \"refs.yaml\"\n",
+    )
+    .unwrap();
+    let workspace = Workspace::load(root.path()).unwrap();
+
+    let location = workspace
+        .definition("{{#cite jones2023}}", Position::new(0, 10))
+        .unwrap();
+    assert!(location.uri.path().ends_with("/src/refs.yaml"));
+    let line = YAML_BIB_SRC
+        .lines()
+        .position(|line| line == "jones2023:")
+        .unwrap() as u32;
+    assert_eq!(location.range.start, Position::new(line, 0));
+}
+
+#[test]
+fn lsp_workspace_reports_unknown_and_malformed_citations() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let workspace = Workspace::load(root.path()).unwrap();
+
+    let diagnostics = workspace.diagnostics("{{#cite fps}}\n{{#cite nope}} {{#cite}}");
+    let found: Vec<(u32, u32, &str)> = diagnostics
+        .iter()
+        .map(|d| {
+            (
+                d.range.start.line,
+                d.range.start.character,
+                d.message.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, 8, "unknown citation key 'nope'"),
+            (1, 15, "malformed citation '{{#cite}}'"),
+        ]
+    );
+    // Chapters opting out are not checked, and other syntaxes follow the chapter's settings
+    assert!(workspace
+        .diagnostics("<!-- bib: off -->\n{{#cite nope}}")
+        .is_empty());
+    assert_eq!(
+        workspace
+            .diagnostics("<!-- bib: syntax=pandoc -->\n[@nope]")
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn lsp_server_answers_over_connection() {
+    use lsp_server::{Connection, Message, Notification, Request, RequestId};
+    use serde_json::json;
+
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let (server, client) = Connection::memory();
+    let book_dir = root.path().to_path_buf();
+    let handle = std::thread::spawn(move || serve(&server, Some(&book_dir)));
+
+    let send = |message: Message| client.sender.send(message).unwrap();
+    let uri = format!("file://{}/src/intro.md", root.path().display());
+    send(
+        Request::new(
+            RequestId::from(1),
+            "initialize".to_string(),
+            json!({ "capabilities": {} }),
+        )
+        .into(),
+    );
+    let Message::Response(response) = client.receiver.recv().unwrap() else {
+        panic!("Expected the initialize response");
+    };
+    assert_eq!(
+        response.result.unwrap()["capabilities"]["hoverProvider"],
+        true
+    );
+    send(Notification::new("initialized".to_string(), json!({})).into());
+
+    send(
+        Notification::new(
+            "textDocument/didOpen".to_string(),
+            json!({ "textDocument": {
+                "uri": uri, "languageId": "markdown", "version": 1,
+                "text": "@@fps and @@nope",
+            }}),
+        )
+        .into(),
+    );
+    let Message::Notification(published) = client.receiver.recv().unwrap() else {
+        panic!("Expected diagnostics");
+    };
+    assert_eq!(published.method, "textDocument/publishDiagnostics");
+    assert_eq!(
+        published.params["diagnostics"][0]["message"],
+        "unknown citation key 'nope'"
+    );
+
+    send(
+        Request::new(
+            RequestId::from(2),
+            "textDocument/hover".to_string(),
+            json!({ "textDocument": { "uri": uri }, "position": { "line": 0, "character": 3 } }),
+        )
+        .into(),
+    );
+    let Message::Response(response) = client.receiver.recv().unwrap() else {
+        panic!("Expected the hover response");
+    };
+    let hover = response.result.unwrap();
+    assert!(hover["contents"]["value"]
+        .as_str()
+        .unwrap()
+        .contains("This is a bib entry!"));
+
+    send(Request::new(RequestId::from(3), "shutdown".to_string(), json!(null)).into());
+    send(Notification::new("exit".to_string(), json!(null)).into());
+    handle.join().unwrap().unwrap();
+}
+
+// =============================================================================
+// Add Tests
+// =============================================================================
+
+const CSL_JSON_RESPONSE: &str = r#"{
+  "type": "article-journal",
+  "title": "Neural Rendering of <i>Everything</i>",
+  "author": [{"family": "Müller", "given": "Thomas"}, {"family": "Doe", "given": "Jane"}],
+  "container-title": "ACM Transactions on Graphics",
+  "volume": "39",
+  "issue": "4",
+  "page": "1-12",
+  "issued": {"date-parts": [[2020, 7, 8]]},
+  "publisher": "Association for Computing Machinery (ACM)",
+  "DOI": "10.1145/3386569.3392470",
+  "URL": "https://doi.org/10.1145/3386569.3392470"
+}"#;
+
+#[rstest::rstest]
+#[case::doi("10.1145/3386569.3392470", Identifier::Doi("10.1145/3386569.3392470".into()))]
+#[case::doi_url("https://doi.org/10.1000/XYZ", Identifier::Doi("10.1000/XYZ".into()))]
+#[case::isbn("978-0-201-13447-6", Identifier::Isbn("9780201134476".into()))]
+#[case::isbn10("ISBN: 0-201-13447-0", Identifier::Isbn("0201134470".into()))]
+#[case::arxiv("2101.00001v2", Identifier::Arxiv("2101.00001v2".into()))]
+#[case::arxiv_old("arXiv:hep-th/9901001", Identifier::Arxiv("hep-th/9901001".into()))]
+fn add_reads_identifiers(#[case] input: &str, #[case] expected: Identifier) {
+    assert_eq!(input.parse::<Identifier>().unwrap(), expected);
+}
+
+#[test]
+fn add_generates_citation_keys_from_pattern() {
+    let work = Work {
+        entry_type: "book",
+        authors: vec![("Pérez-Sorrosal".into(), "Francisco".into())],
+        fields: [
+            ("title", "The Art of Citing".to_string()),
+            ("year", "2024".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    assert_eq!(
+        work.citation_key("{author}{year}", |_| false),
+        "perezsorrosal2024"
+    );
+    assert_eq!(
+        work.citation_key("{author}:{title}", |_| false),
+        "perezsorrosal:art"
+    );
+    // Taken keys get a letter
+    let taken = ["perezsorrosal2024", "perezsorrosal2024a"];
+    assert_eq!(
+        work.citation_key("{author}{year}", |key| taken.contains(&key)),
+        "perezsorrosal2024b"
+    );
+    assert_eq!(
+        Work::default().citation_key("{author}{year}", |_| false),
+        "anonnd"
+    );
+    assert!("not an id".parse::<Identifier>().is_err());
+}
+
+#[test]
+fn add_escapes_bibtex_special_characters() {
+    let work = Work {
+        entry_type: "article",
+        authors: vec![("Smith & Sons".into(), String::new())],
+        fields: [
+            ("title", "Rust & {C} at 100% #1".to_string()),
+            ("url", "https://example.com/a_b#c".to_string()),
+        ]
+        .into_iter()
+        .collect(),
+    };
+    let bibtex = work.to_bibtex("smith");
+    assert!(
+        bibtex.contains(r"  author = {{Smith \& Sons}},"),
+        "{bibtex}"
+    );
+    assert!(bibtex.contains(r"  title = {Rust \& \{C\} at 100\% \#1},"));
+    assert!(bibtex.contains("  url = {https://example.com/a_b#c},"));
+
+    let bibliography = parser::parse_bibliography(bibtex, BibFormat::BibTeX).unwrap();
+    assert_eq!(bibliography["smith"].title, "Rust & {C} at 100% #1");
+}
+
+#[test]
+fn add_appends_doi_entry_and_refuses_duplicates() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let base_url = serve_http(vec![(
+        "/10.1145/3386569.3392470",
+        CSL_JSON_RESPONSE.to_string(),
+    )]);
+    let options = AddOptions {
+        book_dir: root.path().to_path_buf(),
+        identifier: "10.1145/3386569.3392470".to_string(),
+        base_url: Some(base_url.clone()),
+        ..Default::default()
+    };
+
+    let added = add(&options).unwrap();
+    assert_eq!(added.key, "muller2020");
+    assert_eq!(added.path, std::path::Path::new("src/refs.bib"));
+    assert_eq!(
+        added.entry,
+        "@article{muller2020,\n  author = {Müller, Thomas and Doe, Jane},\n  \
+        title = {Neural Rendering of Everything},\n  journal = {ACM Transactions on Graphics},\n  \
+        year = {2020},\n  volume = {39},\n  number = {4},\n  pages = {1-12},\n  \
+        publisher = {Association for Computing Machinery (ACM)},\n  \
+        doi = {10.1145/3386569.3392470},\n  url = {https://doi.org/10.1145/3386569.3392470},\n}\n"
+    );
+    let content = std::fs::read_to_string(root.path().join("src/refs.bib")).unwrap();
+    assert!(content.starts_with(DUMMY_BIB_SRC));
+    let bibliography = parser::parse_bibliography(content, BibFormat::BibTeX).unwrap();
+    assert_eq!(bibliography.len(), 3);
+
+    // The DOI is already there, in any case
+    let again = AddOptions {
+        identifier: "doi:10.1145/3386569.3392470".to_uppercase(),
+        ..options.clone()
+    };
+    let error = add(&again).unwrap_err().to_string();
+    assert!(
+        error.contains("'muller2020' in src/refs.bib already has DOI"),
+        "{error}"
+    );
+
+    let missing = AddOptions {
+        identifier: "10.1000/missing".to_string(),
+        ..options
+    };
+    assert!(add(&missing)
+        .unwrap_err()
+        .to_string()
+        .contains("Nothing found"));
+}
+
+#[test]
+fn add_encodes_doi_in_request_path() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let base_url = serve_http(vec![(
+        "/10.1000/x%231%3Fy%3Bz",
+        CSL_JSON_RESPONSE.to_string(),
+    )]);
+    let added = add(&AddOptions {
+        book_dir: root.path().to_path_buf(),
+        identifier: "10.1000/x#1?y;z".to_string(),
+        base_url: Some(base_url),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(added.key, "muller2020");
+}
+
+#[test]
+fn add_converts_isbn_entry_for_yaml_bibliography() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    std::fs::write(
+        root.path().join("book.toml"),
+        "[preprocessor.bib]\nbibliography = \"refs.yaml\"\nkey-pattern = \"{author}-{title}\"\n",
+    )
+    .unwrap();
+    std::fs::write(root.path().join("src/refs.yaml"), YAML_BIB_SRC).unwrap();
+    let base_url = serve_http(vec![(
+        "/api/books?bibkeys=ISBN:9780201134476",
+        r#"{"ISBN:9780201134476": {"title": "The TeXbook", "authors": [{"name": "Donald E. Knuth"}],
+            "publishers": [{"name": "Addison-Wesley"}], "publish_date": "1984",
+            "number_of_pages": 483}}"#
+            .to_string(),
+    )]);
+
+    let added = add(&AddOptions {
+        book_dir: root.path().to_path_buf(),
+        identifier: "978-0-201-13447-6".to_string(),
+        base_url: Some(base_url),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(added.key, "knuth-texbook");
+    let content = std::fs::read_to_string(root.path().join("src/refs.yaml")).unwrap();
+    let bibliography = parser::parse_bibliography(content, BibFormat::Yaml).unwrap();
+    let item = &bibliography["knuth-texbook"];
+    assert_eq!(item.title, "The TeXbook");
+    assert_eq!(item.pub_year.as_deref(), Some("1984"));
+    assert!(bibliography.contains_key("smith2024"));
+}
+
+#[test]
+fn add_reads_arxiv_feed_with_explicit_key() {
+    let root = create_book_on_disk("", &[("intro.md", "")]);
+    let base_url = serve_http(vec![(
+        "/api/query?id_list=2101.00001",
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+  <title>arXiv Query</title>
+  <entry>
+    <id>http://arxiv.org/abs/2101.00001v1</id>
+    <published>2021-01-01T00:00:00Z</published>
+    <title>Attention Is
+      Still All You Need</title>
+    <author><name>Ada Lovelace</name></author>
+    <author><name>Alan Turing</name></author>
+    <arxiv:doi>10.1000/arxiv.2101</arxiv:doi>
+  </entry>
+</feed>"#
+            .to_string(),
+    )]);
+
+    let added = add(&AddOptions {
+        book_dir: root.path().to_path_buf(),
+        identifier: "arXiv:2101.00001".to_string(),
+        key: Some("attention".to_string()),
+        base_url: Some(base_url),
+    })
+    .unwrap();
+    assert_eq!(added.key, "attention");
+    assert!(added.entry.contains(
+        "author = {Lovelace, Ada and Turing, Alan},\n  title = {Attention Is Still All You Need},"
+    ));
+    assert!(added
+        .entry
+        .contains("doi = {10.1000/arxiv.2101},\n  eprint = {2101.00001},"));
+
+    // Refused before any lookup, as the DOI is known
+    let error = add(&AddOptions {
+        book_dir: root.path().to_path_buf(),
+        identifier: "10.1000/ARXIV.2101".to_string(),
+        ..Default::default()
+    })
+    .unwrap_err();
+    assert!(error.to_string().contains("'attention'"));
+
+    // Keys that could never be cited are refused
+    for key in ["", "attention is", "attention}"] {
+        let error = add(&AddOptions {
+            book_dir: root.path().to_path_buf(),
+            identifier: "2101.00002".to_string(),
+            key: Some(key.to_string()),
+            ..Default::default()
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(error.contains("citation key"), "{error}");
+    }
+}
@@ -4,16 +4,27 @@
 //! - Test fixtures (BibTeX sources, sample text)
 //! - Builder patterns for test data
 //! - Handlebars and backend factory functions
+//! - Preprocessor runs for a given renderer
+//! - Books on disk for the subcommands
+//! - A local HTTP stub for the metadata services
 //! - File assertion utilities
 
 use crate::backend::CustomBackend;
 use crate::config::{DEFAULT_CITE_HB_TEMPLATE, DEFAULT_HB_TEMPLATE};
 use crate::models::BibItem;
 use crate::parser::{self, BibFormat};
+use crate::Bibliography;
 use handlebars::Handlebars;
 use indexmap::IndexMap;
+use mdbook_preprocessor::book::{Book, Chapter};
+use mdbook_preprocessor::config::Config as BookConfig;
+use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::OnceLock;
+use tempfile::{Builder as TempFileBuilder, TempDir};
 
 // =============================================================================
 // Test Fixtures - BibTeX Sources
@@ -117,6 +128,15 @@ pub fn create_references_handlebars() -> Handlebars<'static> {
     handlebars
 }
 
+/// Creates a Handlebars instance with a custom references template.
+pub fn create_references_handlebars_with_template(template: &str) -> Handlebars<'static> {
+    let mut handlebars = Handlebars::new();
+    handlebars
+        .register_template_string("references", template)
+        .unwrap();
+    handlebars
+}
+
 /// Creates a Handlebars instance with the default citation template.
 pub fn create_citation_handlebars() -> Handlebars<'static> {
     let mut handlebars = Handlebars::new();
@@ -147,6 +167,14 @@ pub fn create_references_backend() -> CustomBackend<'static> {
     CustomBackend::new(handlebars)
 }
 
+/// Creates a CustomBackend with a custom references template.
+pub fn create_references_backend_with_template(template: &str) -> CustomBackend<'static> {
+    let handlebars = Box::leak(Box::new(create_references_handlebars_with_template(
+        template,
+    )));
+    CustomBackend::new(handlebars)
+}
+
 /// Creates a CustomBackend with the default citation template.
 pub fn create_citation_backend() -> CustomBackend<'static> {
     let handlebars = Box::leak(Box::new(create_citation_handlebars()));
@@ -184,6 +212,7 @@ pub struct BibItemBuilder {
     summary: Option<String>,
     url: Option<String>,
     index: Option<u32>,
+    citation_count: u32,
     entry_type: Option<String>,
     doi: Option<String>,
     isbn: Option<String>,
@@ -197,6 +226,7 @@ pub struct BibItemBuilder {
     editor: Option<Vec<Vec<String>>>,
     edition: Option<String>,
     note: Option<String>,
+    raw_source: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -268,6 +298,11 @@ impl BibItemBuilder {
         self
     }
 
+    pub fn citation_count(mut self, count: u32) -> Self {
+        self.citation_count = count;
+        self
+    }
+
     pub fn entry_type(mut self, entry_type: &str) -> Self {
         self.entry_type = Some(entry_type.to_string());
         self
@@ -323,6 +358,11 @@ impl BibItemBuilder {
         self
     }
 
+    pub fn raw_source(mut self, raw_source: &str) -> Self {
+        self.raw_source = Some(raw_source.to_string());
+        self
+    }
+
     pub fn build(self) -> BibItem {
         BibItem {
             citation_key: self.citation_key,
@@ -333,6 +373,7 @@ impl BibItemBuilder {
             summary: self.summary,
             url: self.url,
             index: self.index,
+            citation_count: self.citation_count,
             entry_type: self.entry_type,
             doi: self.doi,
             isbn: self.isbn,
@@ -346,11 +387,98 @@ impl BibItemBuilder {
             editor: self.editor,
             edition: self.edition,
             note: self.note,
+            raw_source: self.raw_source,
+            source_span: None,
             hayagriva_entry: None,
         }
     }
 }
 
+// =============================================================================
+// Preprocessor Runner
+// =============================================================================
+
+/// Run the preprocessor for `renderer` on a book made of `(path, content)` chapters.
+///
+/// The book lives in a temporary directory (returned to keep it alive) with
+/// `DUMMY_BIB_SRC` as `src/refs.bib`; `bib_config` is appended to `[preprocessor.bib]`.
+pub fn run_preprocessor_for_renderer(
+    bib_config: &str,
+    renderer: &str,
+    chapters: &[(&str, &str)],
+) -> (Book, TempDir) {
+    let root = TempFileBuilder::new().prefix("book").tempdir().unwrap();
+    std::fs::create_dir_all(root.path().join("src")).unwrap();
+    std::fs::write(root.path().join("src/refs.bib"), DUMMY_BIB_SRC).unwrap();
+
+    let book_toml = format!("[preprocessor.bib]\nbibliography = \"refs.bib\"\n{bib_config}\n");
+    let config = BookConfig::from_str(&book_toml).unwrap();
+    let ctx = PreprocessorContext::new(root.path().to_path_buf(), config, renderer.to_string());
+
+    let mut book = Book::new();
+    for (path, content) in chapters {
+        book.push_item(Chapter::new(path, content.to_string(), *path, vec![]));
+    }
+    let book = Bibliography.run(&ctx, book).unwrap();
+    (book, root)
+}
+
+/// Write a book made of `(path, content)` chapters to a temporary directory.
+///
+/// Chapters are listed in order in `SUMMARY.md`, with `DUMMY_BIB_SRC` as `src/refs.bib`;
+/// `bib_config` is appended to `[preprocessor.bib]`.
+pub fn create_book_on_disk(bib_config: &str, chapters: &[(&str, &str)]) -> TempDir {
+    let root = TempFileBuilder::new().prefix("book").tempdir().unwrap();
+    let src = root.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("refs.bib"), DUMMY_BIB_SRC).unwrap();
+    std::fs::write(
+        root.path().join("book.toml"),
+        format!("[preprocessor.bib]\nbibliography = \"refs.bib\"\n{bib_config}\n"),
+    )
+    .unwrap();
+
+    let mut summary = String::from("# Summary\n\n");
+    for (path, content) in chapters {
+        summary.push_str(&format!("- [{path}]({path})\n"));
+        std::fs::write(src.join(path), content).unwrap();
+    }
+    std::fs::write(src.join("SUMMARY.md"), summary).unwrap();
+    root
+}
+
+/// Answer HTTP requests on a local port with canned `(path prefix, body)` routes, 404 for
+/// anything else, and return the base URL to query.
+pub fn serve_http(routes: Vec<(&'static str, String)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().map_while(Result::ok) {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            // Headers end with an empty line
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 2 {
+                header.clear();
+            }
+
+            let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+            let (status, body) = match routes.iter().find(|(prefix, _)| target.starts_with(prefix))
+            {
+                Some((_, body)) => ("200 OK", body.as_str()),
+                None => ("404 Not Found", ""),
+            };
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    base_url
+}
+
 // =============================================================================
 // File Assertion Utilities
 // =============================================================================
@@ -6,11 +6,21 @@
 //! - Custom templates and styles
 //! - Zotero configuration
 //! - Per-chapter bibliography settings
+//! - Sort order specifications
+//! - Bibliography back-references, popovers, downloads and metadata
+//! - Renderer output formats
 
 use super::common::{EXAMPLE_CSS_TEMPLATE, EXAMPLE_HB_TEMPLATE};
+use crate::backend::OutputFormat;
 use crate::config::Config;
 use crate::config::DEFAULT_JS_TEMPLATE;
-use crate::config::{DEFAULT_CSS_TEMPLATE, DEFAULT_HB_TEMPLATE};
+use crate::config::{
+    CitationMetadata, Numbering, SortCriterion, SortDirection, SortField, SortOrder,
+};
+use crate::config::{
+    DEFAULT_CHAPTER_REFS_FOOTER_HB_TEMPLATE, DEFAULT_CSS_TEMPLATE, DEFAULT_HB_TEMPLATE,
+};
+use rstest::rstest;
 use std::path::PathBuf;
 use toml::value::Table;
 use toml::Value;
@@ -118,6 +128,33 @@ fn check_adhoc_template_and_style() {
     }
 }
 
+#[test]
+fn chapter_refs_template_config() {
+    let t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(
+        config.chapter_refs_hb_html,
+        DEFAULT_CHAPTER_REFS_FOOTER_HB_TEMPLATE
+    );
+
+    let src = tempfile::tempdir().unwrap();
+    std::fs::write(
+        src.path().join("chapter_refs.hbs"),
+        "<h2>Sources for {{chapter_name}}</h2>",
+    )
+    .unwrap();
+    let mut t: Table = Table::new();
+    t.insert(
+        "chapter-refs-tpl".to_string(),
+        Value::String("chapter_refs.hbs".to_string()),
+    );
+    let config = Config::build_from(Some(&t), src.path().to_path_buf()).unwrap();
+    assert_eq!(
+        config.chapter_refs_hb_html,
+        "<h2>Sources for {{chapter_name}}</h2>"
+    );
+}
+
 // =============================================================================
 // Zotero Configuration Tests
 // =============================================================================
@@ -199,3 +236,284 @@ fn per_chapter_bibliography_disabled_by_default() {
         "add_bib_in_chapters should be false by default"
     );
 }
+
+#[test]
+fn per_chapter_numbering_config() {
+    let mut t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.numbering, Numbering::Global);
+
+    // Per-chapter numbering refers to the chapter lists, so they must be enabled
+    t.insert(
+        "numbering".to_string(),
+        Value::String("per-chapter".to_string()),
+    );
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+
+    t.insert("add-bib-in-chapters".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.numbering, Numbering::PerChapter);
+
+    t.insert(
+        "numbering".to_string(),
+        Value::String("per-section".to_string()),
+    );
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+#[test]
+fn chapter_links_and_global_bib_config() {
+    let mut t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(!config.link_in_chapters);
+    assert!(config.global_bib);
+
+    // Citations would link to a missing page
+    t.insert("global-bib".to_string(), Value::Boolean(false));
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+
+    // Linking in chapters needs the chapter lists
+    t.insert("link-in-chapters".to_string(), Value::Boolean(true));
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+
+    t.insert("add-bib-in-chapters".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.link_in_chapters);
+    assert!(!config.global_bib);
+
+    // Per-chapter numbering already links in chapters
+    t.remove("link-in-chapters");
+    t.insert(
+        "numbering".to_string(),
+        Value::String("per-chapter".to_string()),
+    );
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_ok());
+}
+
+// =============================================================================
+// Sort Order Configuration Tests
+// =============================================================================
+
+#[test]
+fn sort_order_single_value_config() {
+    let mut t: Table = Table::new();
+    t.insert("order".to_string(), Value::String("index".to_string()));
+
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.order, SortOrder::Index);
+}
+
+#[test]
+fn sort_order_multi_key_spec_config() {
+    let mut t: Table = Table::new();
+    t.insert(
+        "order".to_string(),
+        Value::Array(vec![
+            Value::String("year:desc".to_string()),
+            Value::String("author".to_string()),
+            Value::String("title:asc".to_string()),
+        ]),
+    );
+
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(
+        config.order,
+        SortOrder::Spec(vec![
+            SortCriterion {
+                field: SortField::Year,
+                direction: SortDirection::Desc,
+            },
+            SortCriterion {
+                field: SortField::Author,
+                direction: SortDirection::Asc,
+            },
+            SortCriterion {
+                field: SortField::Title,
+                direction: SortDirection::Asc,
+            },
+        ])
+    );
+}
+
+#[rstest]
+#[case::unknown_field(vec!["publisher"])]
+#[case::unknown_direction(vec!["year:newest"])]
+#[case::empty_spec(vec![])]
+fn sort_order_invalid_spec_config(#[case] specs: Vec<&str>) {
+    let mut t: Table = Table::new();
+    t.insert(
+        "order".to_string(),
+        Value::Array(
+            specs
+                .into_iter()
+                .map(|s| Value::String(s.to_string()))
+                .collect(),
+        ),
+    );
+
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+// =============================================================================
+// Bibliography Page Extras Configuration Tests
+// =============================================================================
+
+#[test]
+fn backrefs_config() {
+    let t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(!config.backrefs, "backrefs should be disabled by default");
+
+    let mut t: Table = Table::new();
+    t.insert("backrefs".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.backrefs);
+
+    let mut t: Table = Table::new();
+    t.insert("backrefs".to_string(), Value::String("yes".to_string()));
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+#[test]
+fn nocite_config() {
+    let t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.nocite.is_empty());
+
+    let mut t: Table = Table::new();
+    t.insert(
+        "nocite".to_string(),
+        Value::Array(vec![
+            Value::String("fps".to_string()),
+            Value::String("rust_book".to_string()),
+        ]),
+    );
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.nocite, vec!["fps", "rust_book"]);
+
+    let mut t: Table = Table::new();
+    t.insert("nocite".to_string(), Value::Boolean(true));
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+#[test]
+fn cite_popover_config() {
+    let t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(
+        !config.cite_popover,
+        "cite-popover should be disabled by default"
+    );
+
+    let mut t: Table = Table::new();
+    t.insert("cite-popover".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.cite_popover);
+}
+
+#[test]
+fn download_bib_config() {
+    let t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(
+        !config.download_bib,
+        "download-bib should be disabled by default"
+    );
+
+    let mut t: Table = Table::new();
+    t.insert("download-bib".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.download_bib);
+    assert!(!config.copy_bib, "copy-bib should be disabled by default");
+
+    let mut t: Table = Table::new();
+    t.insert("copy-bib".to_string(), Value::Boolean(true));
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.copy_bib);
+}
+
+#[rstest]
+#[case::none("none", CitationMetadata::None)]
+#[case::coins("coins", CitationMetadata::Coins)]
+#[case::json_ld("json-ld", CitationMetadata::JsonLd)]
+#[case::all("all", CitationMetadata::All)]
+fn citation_metadata_config(#[case] value: &str, #[case] expected: CitationMetadata) {
+    let mut t: Table = Table::new();
+    t.insert(
+        "citation-metadata".to_string(),
+        Value::String(value.to_string()),
+    );
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.citation_metadata, expected);
+
+    t.insert(
+        "citation-metadata".to_string(),
+        Value::String("rdfa".to_string()),
+    );
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+#[test]
+fn latex_renderers_config() {
+    let mut t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.is_latex_renderer("latex"));
+    assert!(!config.is_latex_renderer("html"));
+
+    t.insert(
+        "latex-renderers".to_string(),
+        Value::Array(vec![Value::String("pandoc".to_string())]),
+    );
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert!(config.is_latex_renderer("pandoc"));
+    assert!(!config.is_latex_renderer("latex"));
+
+    t.insert(
+        "latex-renderers".to_string(),
+        Value::String("latex".to_string()),
+    );
+    assert!(Config::build_from(Some(&t), PathBuf::new()).is_err());
+}
+
+#[rstest]
+#[case::html("html", OutputFormat::Html)]
+#[case::unknown("my-renderer", OutputFormat::Html)]
+#[case::markdown("markdown", OutputFormat::Markdown)]
+#[case::linkcheck("linkcheck", OutputFormat::Markdown)]
+#[case::epub("epub", OutputFormat::Epub)]
+#[case::latex("latex", OutputFormat::Latex)]
+#[case::pandoc("pandoc", OutputFormat::Pandoc)]
+fn output_format_for_renderer(#[case] renderer: &str, #[case] expected: OutputFormat) {
+    let mut t: Table = Table::new();
+    t.insert(
+        "citation-syntax".to_string(),
+        Value::String("pandoc".to_string()),
+    );
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.output_format(renderer), expected);
+}
+
+#[test]
+fn key_pattern_config() {
+    let mut t: Table = Table::new();
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.key_pattern, "{author}{year}");
+
+    t.insert(
+        "key-pattern".to_string(),
+        Value::String("{author}:{title}-{year}".to_string()),
+    );
+    let config = Config::build_from(Some(&t), PathBuf::new()).unwrap();
+    assert_eq!(config.key_pattern, "{author}:{title}-{year}");
+
+    for invalid in ["{author} {year}", "{editor}{year}", "{author"] {
+        t.insert(
+            "key-pattern".to_string(),
+            Value::String(invalid.to_string()),
+        );
+        assert!(
+            Config::build_from(Some(&t), PathBuf::new()).is_err(),
+            "{invalid}"
+        );
+    }
+}
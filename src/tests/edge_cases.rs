@@ -89,6 +89,7 @@ fn citation_to_nonexistent_key() {
         &backend,
         &mut last_index,
         &CitationSyntax::Default,
+        false,
     );
 
     // Should contain error message for unknown key
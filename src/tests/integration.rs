@@ -4,10 +4,13 @@
 //! - Complete test book builds
 //! - CSL style book builds (IEEE, Chicago, Nature)
 //! - Output verification
+//! - Chapter bibliographies linked in-page
+//! - Renderer-specific output (Pandoc passthrough, EPUB, plain Markdown)
 
-use super::common::find_str_in_file;
+use super::common::{find_str_in_file, run_preprocessor_for_renderer};
 use crate::Bibliography;
 use mdbook_driver::MDBook;
+use rstest::rstest;
 use std::path::PathBuf;
 
 // =============================================================================
@@ -125,3 +128,174 @@ fn process_csl_nature_example_book() {
         }
     }
 }
+
+// =============================================================================
+// Chapter Bibliography Tests
+// =============================================================================
+
+#[rstest]
+#[case::custom("backend = \"custom\"")]
+#[case::csl("backend = \"csl\"\ncsl-style = \"ieee\"")]
+fn citations_link_to_chapter_lists_without_global_bibliography(#[case] backend_config: &str) {
+    let (book, _root) = run_preprocessor_for_renderer(
+        &format!(
+            "{backend_config}\nadd-bib-in-chapters = true\nlink-in-chapters = true\nglobal-bib = false"
+        ),
+        "html",
+        &[
+            ("intro.md", "See {{#cite rust_book}}."),
+            ("part/next.md", "Also {{#cite fps}}."),
+        ],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    assert_eq!(chapters.len(), 2, "no bibliography chapter");
+    let next = &chapters[1].content;
+    assert!(next.contains("href=\"#chapter-bib-fps\"") || next.contains("(#chapter-bib-fps)"));
+    assert!(next.contains("id=\"chapter-bib-fps\"") || next.contains("id='chapter-bib-fps'"));
+    assert!(!next.contains("bibliography.html"));
+}
+
+#[test]
+fn front_matter_settings_are_removed_from_chapters() {
+    let (book, _root) = run_preprocessor_for_renderer(
+        "",
+        "html",
+        &[
+            (
+                "intro.md",
+                "---\ntitle: Intro\nbib: syntax=pandoc\n---\nAs @fps shows.",
+            ),
+            ("glossary.md", "---\nbib: off\n---\n\n@@fps stays."),
+        ],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    assert!(chapters[0].content.contains("bibliography.html#fps"));
+    assert!(chapters[1].content.contains("@@fps stays."));
+    for chapter in &chapters[..2] {
+        assert!(!chapter.content.contains("---"), "{}", chapter.content);
+        assert!(!chapter.content.contains("title: Intro"));
+    }
+}
+
+#[test]
+fn citations_in_chapters_without_list_link_to_bibliography() {
+    let (book, _root) = run_preprocessor_for_renderer(
+        "add-bib-in-chapters = true\nlink-in-chapters = true",
+        "html",
+        &[
+            ("intro.md", "See {{#cite rust_book}}."),
+            (
+                "part/next.md",
+                "<!-- bib: chapter-bib=false -->\nAlso {{#cite fps}}.",
+            ),
+        ],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    let next = &chapters[1].content;
+    assert!(next.contains("href=\"../bibliography.html#fps\""), "{next}");
+    assert!(!next.contains("chapter-bib-fps"));
+    assert!(chapters[2].content.contains("id=\"fps\""));
+}
+
+// =============================================================================
+// Renderer-Specific Output Tests
+// =============================================================================
+
+#[test]
+fn pandoc_renderer_gets_citations_and_csl_json_passed_through() {
+    let (book, root) = run_preprocessor_for_renderer(
+        "citation-syntax = \"pandoc\"\nbackend = \"csl\"\ncsl-style = \"ieee\"",
+        "pandoc",
+        &[("intro.md", "As @fps shows [@rust_book; see {{#cite fps}}].")],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    let intro = &chapters[0].content;
+    assert!(intro.starts_with("---\nbibliography: '"));
+    assert!(intro.contains("bibliography.json'\ncsl: '"));
+    assert!(intro.ends_with("---\n\nAs @fps shows [@rust_book; see [@fps]]."));
+    assert!(chapters[1].content.contains("::: {#refs}"));
+
+    let csl_json = std::fs::read_to_string(root.path().join("book/bibliography.json")).unwrap();
+    let items: serde_json::Value = serde_json::from_str(&csl_json).unwrap();
+    assert_eq!(items[1]["id"], "rust_book");
+    assert_eq!(items[1]["type"], "book");
+    assert!(root.path().join("book/bibliography.csl").exists());
+}
+
+#[test]
+fn pandoc_renderer_without_pandoc_syntax_renders_html() {
+    let (book, root) =
+        run_preprocessor_for_renderer("", "pandoc", &[("intro.md", "See {{#cite fps}}.")]);
+
+    let intro = &book.chapters().next().unwrap().content;
+    assert!(intro.contains("bibliography.html#fps"));
+    assert!(!root.path().join("book/bibliography.json").exists());
+}
+
+#[rstest]
+#[case::custom("")]
+#[case::csl("backend = \"csl\"\ncsl-style = \"ieee\"")]
+#[case::unused_csl_style("backend = \"csl\"\ncsl-style = \"no-such-style\"")]
+fn markdown_renderer_gets_plain_markdown(#[case] backend_config: &str) {
+    let (book, _root) = run_preprocessor_for_renderer(
+        &format!("{backend_config}\nadd-bib-in-chapters = true\ncite-popover = true"),
+        "markdown",
+        &[("part/intro.md", "See {{#cite fps}}.")],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    let intro = &chapters[0].content;
+    assert!(intro
+        .trim_start()
+        .starts_with("See [\\[fps\\]](../bibliography.md)."));
+    assert!(intro.contains("**Chapter References**"));
+    assert!(intro
+        .contains("- **\\[fps\\]** Perez-Sorrosal, Francisco (2020). *This is a bib entry!*\n"));
+
+    for chapter in &chapters {
+        for html in ["<script", "<style", "<details", "<article", "<div"] {
+            assert!(
+                !chapter.content.contains(html),
+                "{html} in {}",
+                chapter.name
+            );
+        }
+    }
+}
+
+#[rstest]
+#[case::custom("")]
+#[case::csl("backend = \"csl\"\ncsl-style = \"apa\"")]
+fn epub_renderer_gets_footnote_style_citations(#[case] backend_config: &str) {
+    let (book, _root) = run_preprocessor_for_renderer(
+        &format!("{backend_config}\nbackrefs = true\ncite-popover = true\ndownload-bib = true"),
+        "epub",
+        &[
+            ("intro.md", "See {{#cite rust_book}}."),
+            ("part/next.md", "Also {{#cite fps}}."),
+        ],
+    );
+
+    let chapters: Vec<_> = book.chapters().collect();
+    assert!(chapters[1].content.contains(
+        "<sup><a epub:type=\"noteref\" id=\"bib-cite-fps-1\" \
+         href=\"../bibliography.html#fps\">2</a></sup>"
+    ));
+
+    let bib = &chapters[2].content;
+    assert!(bib.contains("<div class=\"bib-entry\" epub:type=\"biblioentry\" id=\"rust_book\">1. "));
+    assert!(bib.contains("<a href='part/next.html#bib-cite-fps-1'>"));
+    for chapter in &chapters {
+        for html in ["<script", "<style", "<details", "bib-download"] {
+            assert!(
+                !chapter.content.contains(html),
+                "{html} in {}",
+                chapter.name
+            );
+        }
+    }
+}
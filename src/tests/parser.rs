@@ -6,6 +6,7 @@
 //! - Date/month extraction
 //! - Extended BibItem fields
 //! - Serialization
+//! - Raw entry sources
 
 use super::common::{dummy_bibliography, yaml_bibliography, DUMMY_BIB_SRC, YAML_BIB_SRC};
 use crate::io;
@@ -416,3 +417,30 @@ fn yaml_bibliography_returns_pre_parsed() {
     assert!(bibliography.contains_key("smith2024"));
     assert!(bibliography.contains_key("jones2023"));
 }
+
+// =============================================================================
+// Raw Entry Source Tests
+// =============================================================================
+
+#[test]
+fn bibtex_entries_keep_raw_source() {
+    let bibliography = dummy_bibliography();
+
+    let rust_book = bibliography.get("rust_book").unwrap();
+    let raw = rust_book.raw_source.as_deref().unwrap();
+    assert!(raw.starts_with("@book{rust_book,"));
+    assert!(raw.contains("publisher = {No Starch Press},"));
+    assert!(raw.ends_with('}'));
+    assert!(!raw.contains("@misc{fps"));
+}
+
+#[test]
+fn yaml_entries_keep_raw_source() {
+    let bibliography = yaml_bibliography();
+
+    let smith = bibliography.get("smith2024").unwrap();
+    let raw = smith.raw_source.as_deref().unwrap();
+    assert!(raw.starts_with("smith2024:\n  type: article"));
+    assert!(raw.contains("    issue: 2"));
+    assert!(!raw.contains("jones2023"));
+}